@@ -0,0 +1,228 @@
+// 命令行版本的终端引擎，不需要窗口即可运行命令，方便脚本和 CI 使用
+//
+//   landingterm-cli [--json] [--terminal ID] [--cwd DIR] [--timeout SECS] [COMMAND...]
+//
+// COMMAND 的每个参数按原样传给命令（会被转义，不会再展开变量或拆分空格）；
+// 需要管道、变量等 shell 语法时用 sh -c '...' 或者从标准输入传入。
+// 没有给出 COMMAND 时从标准输入逐行读取命令，所有命令共享同一个终端会话（cd 会保留）。
+
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use app::terminal::engine;
+use app::terminal::lexer;
+use app::terminal::limits::ResourceLimits;
+use app::terminal::process::format_current_dir;
+use app::terminal::session;
+use app::terminal::sink::{OutputSink, TerminalEvent};
+
+struct Options {
+    json: bool,
+    terminal_id: String,
+    cwd: Option<PathBuf>,
+    command: Option<String>,
+    limits: ResourceLimits,
+}
+
+const USAGE: &str = "usage: landingterm-cli [--json] [--terminal ID] [--cwd DIR] [--timeout SECS] [COMMAND [ARG...]]
+
+Arguments are passed to COMMAND as-is. Use sh -c '...' or standard input for pipes and other shell syntax.";
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        json: false,
+        terminal_id: "cli".to_string(),
+        cwd: None,
+        command: None,
+        limits: ResourceLimits::default(),
+    };
+    let mut args = env::args().skip(1);
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--terminal" => {
+                options.terminal_id = args.next().ok_or("--terminal requires a value")?;
+            }
+            "--cwd" => {
+                options.cwd = Some(PathBuf::from(args.next().ok_or("--cwd requires a value")?));
+            }
+            "--timeout" => {
+                let secs = args.next().ok_or("--timeout requires a value")?;
+                let secs = secs.parse().map_err(|_| format!("invalid timeout: {}", secs))?;
+                options.limits.timeout_secs = Some(secs);
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--" => {
+                rest.extend(args.by_ref());
+            }
+            _ => {
+                rest.push(arg);
+                rest.extend(args.by_ref());
+            }
+        }
+    }
+    if !rest.is_empty() {
+        // 保留调用方的参数边界：ls 'my file' 不能变成 ls my file
+        let words: Vec<String> = rest.iter().map(|arg| lexer::escape(arg)).collect();
+        options.command = Some(words.join(" "));
+    }
+    Ok(options)
+}
+
+// 把引擎事件打印到标准输出：纯文本或者每行一个 JSON
+struct PrintSink {
+    json: bool,
+    // 最近一次完成事件的退出码；外层 None 表示命令没有产生完成事件
+    completion: Mutex<Option<Option<i32>>>,
+    // 上一行是否是原地刷新的进度信息
+    in_progress: Mutex<bool>,
+}
+
+impl OutputSink for PrintSink {
+    fn send(&self, event: TerminalEvent) {
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        match &event {
+            TerminalEvent::Complete(complete) => {
+                *session::lock_recover(&self.completion) = Some(complete.code);
+                if !self.json {
+                    if let Some(first) = complete.suggestions.first() {
+                        eprintln!("Did you mean: {}", first.command);
+                    }
+                }
+            }
+            TerminalEvent::Output(output) if !self.json => {
+                let mut in_progress = session::lock_recover(&self.in_progress);
+                if output.should_replace_last {
+                    // 进度信息在同一行刷新
+                    let _ = write!(out, "\r{}\x1b[K", output.content);
+                    let _ = out.flush();
+                    *in_progress = true;
+                    return;
+                }
+                if *in_progress {
+                    let _ = writeln!(out);
+                    *in_progress = false;
+                }
+                if output.output_type == "stderr" {
+                    eprintln!("{}", output.content);
+                } else if !output.content.is_empty() {
+                    let _ = writeln!(out, "{}", output.content);
+                }
+                return;
+            }
+            _ => {}
+        }
+        if self.json {
+            let line = serde_json::json!({ "event": event.name(), "payload": event });
+            let _ = writeln!(out, "{}", line);
+        }
+    }
+}
+
+fn run_one(sink: &Arc<PrintSink>, options: &Options, command: &str) -> Option<i32> {
+    *session::lock_recover(&sink.completion) = None;
+    let started = Instant::now();
+    let dyn_sink: Arc<dyn OutputSink> = sink.clone();
+    let result = engine::execute_with_limits(dyn_sink, &options.terminal_id, command, options.limits.clone());
+    let duration_ms = started.elapsed().as_millis() as u64;
+    if std::mem::take(&mut *session::lock_recover(&sink.in_progress)) {
+        println!();
+    }
+    // cd 等内置命令不发送完成事件，退出码取自会话里这条命令的命令块；
+    // 两者都没有时当作失败，不能把缺失的结果报告成成功
+    let (cwd, block_code) = session::with_session(&options.terminal_id, |s| {
+        let block_code = s.blocks.back().filter(|b| b.command == command).and_then(|b| b.code);
+        (format_current_dir(&s.current_dir), block_code)
+    })
+    .unwrap_or_default();
+    let code = session::lock_recover(&sink.completion).take().unwrap_or(block_code);
+
+    match result {
+        Ok(()) => {
+            if options.json {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "event": "summary",
+                        "payload": {
+                            "terminalId": options.terminal_id,
+                            "command": command,
+                            "code": code,
+                            "durationMs": duration_ms,
+                            "cwd": cwd,
+                        }
+                    })
+                );
+            } else {
+                let code_str = code.map(|c| c.to_string()).unwrap_or_else(|| "none".to_string());
+                eprintln!("[exit {} in {} ms, cwd {}]", code_str, duration_ms, cwd);
+            }
+            code
+        }
+        Err(e) => {
+            if options.json {
+                println!("{}", serde_json::json!({ "event": "error", "payload": e }));
+            } else {
+                eprintln!("error: {}", e);
+            }
+            Some(1)
+        }
+    }
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Some(cwd) = &options.cwd {
+        let dir = match cwd.canonicalize() {
+            Ok(dir) if dir.is_dir() => dir,
+            _ => {
+                eprintln!("not a directory: {}", cwd.display());
+                process::exit(2);
+            }
+        };
+        let _ = session::with_session(&options.terminal_id, |s| s.current_dir = dir);
+    }
+
+    let sink = Arc::new(PrintSink {
+        json: options.json,
+        completion: Mutex::new(None),
+        in_progress: Mutex::new(false),
+    });
+
+    let code = match &options.command {
+        Some(command) => run_one(&sink, &options, command),
+        None => {
+            let mut code = Some(0);
+            for line in io::stdin().lock().lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                code = run_one(&sink, &options, &line);
+            }
+            code
+        }
+    };
+
+    // 被中断的命令没有退出码，返回 1
+    process::exit(code.unwrap_or(1));
+}
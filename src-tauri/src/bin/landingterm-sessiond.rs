@@ -0,0 +1,66 @@
+// 会话守护进程：在应用之外持有终端会话，关闭应用后命令继续运行
+//
+//   landingterm-sessiond --data-dir DIR [--socket PATH]
+//
+// 通常由应用在设置了 LANDINGTERM_SESSIOND=1 时自动启动，也可以手动运行。
+// DIR 为应用的 app data 目录，连接令牌、命令历史和策略都从这里读取。
+
+#[cfg(unix)]
+fn main() {
+    use std::env;
+    use std::path::PathBuf;
+    use std::process;
+
+    use app::terminal::{daemon, guard, history, orphans, persist, policy};
+
+    const USAGE: &str = "usage: landingterm-sessiond --data-dir DIR [--socket PATH]";
+
+    let mut args = env::args().skip(1);
+    let mut socket = None;
+    let mut data_dir = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--socket" => socket = args.next().map(PathBuf::from),
+            "--data-dir" => data_dir = args.next().map(PathBuf::from),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => {
+                eprintln!("unknown argument: {}", arg);
+                process::exit(2);
+            }
+        }
+    }
+
+    let data_dir = match data_dir {
+        Some(dir) => dir,
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    // 守护进程运行的命令和应用内一样记录历史、检查危险命令、受策略限制
+    history::init(Some(data_dir.clone()));
+    guard::init(Some(data_dir.clone()));
+    policy::init(Some(data_dir.clone()));
+    // 守护进程自己的进程注册表单独存放，应用启动时不会把它正在运行的命令当作遗留进程
+    orphans::init(Some(data_dir.join("sessiond")));
+    // 会话快照由守护进程保存，守护进程重启后恢复上次的会话
+    persist::init(Some(data_dir.clone()));
+    if let Err(e) = persist::restore() {
+        eprintln!("Failed to restore sessions: {}", e);
+    }
+
+    let socket = socket.unwrap_or_else(|| daemon::socket_path(&data_dir));
+    if let Err(e) = daemon::serve(&socket, &data_dir) {
+        eprintln!("landingterm-sessiond: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("landingterm-sessiond is only supported on Unix");
+    std::process::exit(1);
+}
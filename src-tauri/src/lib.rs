@@ -6,7 +6,7 @@
 
 use tauri::Runtime;
 use tauri::plugin::{Builder, TauriPlugin};
-use tauri::{Manager, WindowEvent};
+use tauri::{Manager, RunEvent, WindowEvent};
 
 pub mod terminal;  // 声明 terminal 模块
 
@@ -15,17 +15,92 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+// 终端插件：注册全部终端命令，并负责窗口关闭、应用退出时的进程清理
+// 前端通过 `plugin:terminal|<command>` 调用
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
     Builder::new("terminal")
         .invoke_handler(tauri::generate_handler![
             terminal::process::execute_command,
             terminal::process::execute_command_stream,
+            terminal::process::stop_command,
             terminal::process::create_terminal,
             terminal::process::close_terminal,
+            terminal::process::list_sessions,
+            terminal::persist::restore_sessions,
+            terminal::persist::set_terminal_title,
+            terminal::persist::set_terminal_env,
+            terminal::persist::set_startup_command,
+            terminal::history::search_history,
+            terminal::history_import::import_shell_history,
+            terminal::suggest::suggest,
+            terminal::completion::complete,
+            terminal::syntax::check_command,
+            terminal::policy::set_terminal_profile,
+            terminal::policy::list_policy_profiles,
+            terminal::sandbox::set_terminal_sandbox,
+            terminal::process_tree::get_process_tree,
+            terminal::process_tree::send_process_signal,
+            terminal::orphans::list_orphans,
+            terminal::orphans::resolve_orphan,
         ])
-        .setup(|_app| {
+        .setup(|app| {
+            // 读取上次运行遗留的进程记录
+            terminal::orphans::init(app.path_resolver().app_data_dir());
+            // 读取命令历史
+            terminal::history::init(app.path_resolver().app_data_dir());
+            // 读取危险命令的用户规则
+            terminal::guard::init(app.path_resolver().app_data_dir());
+            // 读取受限终端的命令策略
+            terminal::policy::init(app.path_resolver().app_data_dir());
+            // 第一次启动时导入 zsh/bash/fish 的历史
+            std::thread::spawn(terminal::history_import::import_if_empty);
+            // 定期保存会话快照
+            terminal::persist::init(app.path_resolver().app_data_dir());
+            #[cfg(unix)]
+            if let Some(dir) = app.path_resolver().app_data_dir() {
+                // 连接会话守护进程；连接不上时终端在应用内运行
+                if let Err(e) = terminal::daemon::attach(app.clone(), &dir) {
+                    eprintln!("Failed to attach to session daemon: {}", e);
+                }
+                // 供外部工具使用的本地控制接口，启动失败不影响终端本身
+                if let Err(e) = terminal::control::start(app.clone(), &dir) {
+                    eprintln!("Failed to start control socket: {}", e);
+                }
+            }
+            // SIGTERM/SIGHUP 时同样清理所有子进程
+            terminal::shutdown::install_signal_handlers(app.clone());
             Ok(())
         })
+        .on_event(|app, event| match event {
+            RunEvent::WindowEvent {
+                label,
+                event: WindowEvent::CloseRequested { api, .. },
+                ..
+            } => {
+                // 清理完成后再次触发的关闭请求直接放行
+                if terminal::shutdown::is_shut_down() {
+                    return;
+                }
+                if let Some(window) = app.get_window(label) {
+                    println!("Window is closing");
+
+                    api.prevent_close();
+
+                    // 在后台线程终止所有终端进程，完成后关闭窗口
+                    std::thread::spawn(move || {
+                        let _ = window.emit("cleanup", ());
+                        terminal::shutdown::shutdown_all(terminal::shutdown::SHUTDOWN_GRACE);
+                        let _ = window.close();
+                    });
+                }
+            }
+            RunEvent::Exit => {
+                terminal::shutdown::shutdown_all(terminal::shutdown::SHUTDOWN_GRACE);
+                #[cfg(unix)]
+                terminal::control::remove_socket();
+            }
+            _ => {}
+        })
         .build()
 }
 
@@ -34,34 +109,6 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(init::<tauri::Wry>())  // 添加插件
         .invoke_handler(tauri::generate_handler![greet])
-        .setup(|app| {
-            let main_window = app.get_window("main").unwrap();
-            let window_handle = main_window.clone();
-            
-            main_window.on_window_event(move |event| {
-                if let WindowEvent::CloseRequested { api, .. } = event {
-                    // 在这里可以添加关闭前的清理工作
-                    println!("Window is closing");
-                    
-                    api.prevent_close();
-                    
-                    // 异步执行清理工作
-                    let window = window_handle.clone();
-                    tauri::async_runtime::spawn(async move {
-                        // 在这里执行任何需要的异步清理
-                        // 例如，关闭所有终端进程
-                        let _ = window.emit("cleanup", ());
-                        
-                        // 等待一小段时间确保清理完成
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                        
-                        // 关闭窗口
-                        window.close().unwrap();
-                    });
-                }
-            });
-            Ok(())
-        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -4,21 +4,7 @@
     windows_subsystem = "windows"
 )]
 
-use app::terminal;
-
-#[tauri::command]
-async fn execute_terminal_command(command: String) -> Result<terminal::process::CommandOutput, String> {
-    terminal::process::execute_command(&command).await
-}
-
 fn main() {
     println!("Starting Landing Terminal...");
-    tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![
-            execute_terminal_command,
-            terminal::process::execute_command_stream,
-            terminal::process::stop_command
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+    app::run();
 }
@@ -0,0 +1,516 @@
+// Tab 补全：按 shell 规则切分输入，根据光标所在的位置补全命令名（PATH 中的可执行文件和内建命令）、
+// 相对终端工作目录的文件和目录、环境变量以及 git 的子命令、分支和远程仓库。
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::error::TerminalResult;
+use super::lexer::{self, Token, TokenKind};
+use super::engine;
+use super::process::{self, run_blocking};
+use super::session::{self, lock_recover};
+use super::shell_completion;
+
+const MAX_CANDIDATES: usize = 500;
+// PATH 中可执行文件列表的缓存时间
+const PATH_CACHE_TTL: Duration = Duration::from_secs(30);
+
+const BUILTINS: &[(&str, &str)] = &[
+    ("alias", "define or display aliases"),
+    ("bg", "resume a job in the background"),
+    ("builtin", "run a shell builtin"),
+    ("cd", "change the current directory"),
+    ("command", "run a command bypassing functions"),
+    ("echo", "write arguments to standard output"),
+    ("eval", "evaluate arguments as a command"),
+    ("exec", "replace the shell with a command"),
+    ("exit", "exit the shell"),
+    ("export", "set environment variables"),
+    ("fg", "bring a job to the foreground"),
+    ("history", "show command history"),
+    ("jobs", "list background jobs"),
+    ("kill", "send a signal to a process"),
+    ("printf", "format and print data"),
+    ("pwd", "print the current directory"),
+    ("read", "read a line from standard input"),
+    ("set", "set shell options"),
+    ("source", "run commands from a file"),
+    ("test", "evaluate a conditional expression"),
+    ("type", "describe how a name would be interpreted"),
+    ("ulimit", "get or set resource limits"),
+    ("umask", "get or set the file creation mask"),
+    ("unalias", "remove aliases"),
+    ("unset", "unset variables or functions"),
+    ("wait", "wait for background jobs"),
+];
+
+const GIT_SUBCOMMANDS: &[(&str, &str)] = &[
+    ("add", "add file contents to the index"),
+    ("bisect", "find the commit that introduced a bug"),
+    ("blame", "show who last modified each line"),
+    ("branch", "list, create or delete branches"),
+    ("checkout", "switch branches or restore files"),
+    ("cherry-pick", "apply changes from existing commits"),
+    ("clone", "clone a repository"),
+    ("commit", "record changes to the repository"),
+    ("diff", "show changes"),
+    ("fetch", "download objects and refs from a remote"),
+    ("init", "create an empty repository"),
+    ("log", "show commit logs"),
+    ("merge", "join development histories together"),
+    ("pull", "fetch and integrate with a remote branch"),
+    ("push", "update remote refs"),
+    ("rebase", "reapply commits on top of another base"),
+    ("remote", "manage tracked repositories"),
+    ("reset", "reset HEAD to a specified state"),
+    ("restore", "restore working tree files"),
+    ("show", "show objects"),
+    ("stash", "stash away changes"),
+    ("status", "show the working tree status"),
+    ("switch", "switch branches"),
+    ("tag", "create, list or delete tags"),
+];
+
+// 第一个参数是分支名的 git 子命令
+const GIT_BRANCH_COMMANDS: &[&str] = &[
+    "branch", "checkout", "cherry-pick", "diff", "log", "merge", "rebase", "reset", "show", "switch",
+];
+// 参数依次是远程仓库和分支的 git 子命令
+const GIT_REMOTE_COMMANDS: &[&str] = &["fetch", "pull", "push"];
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    Command,
+    Builtin,
+    File,
+    Directory,
+    Variable,
+    Subcommand,
+    Branch,
+    Remote,
+    // shell 补全脚本给出的其他参数
+    Argument,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Candidate {
+    // 替换进输入的文本，已经按需要加上引号或转义
+    pub value: String,
+    // 菜单中显示的文本
+    pub display: String,
+    pub kind: CompletionKind,
+    pub description: Option<String>,
+}
+
+// 补全结果；[start, end) 是输入中要被 value 替换的字符范围
+#[derive(Serialize, Clone, Debug, PartialEq, Default)]
+pub struct Completions {
+    pub start: usize,
+    pub end: usize,
+    pub candidates: Vec<Candidate>,
+}
+
+// 光标所在的单词
+struct Word {
+    // 去掉引号和转义后的内容
+    value: String,
+    open_quote: Option<char>,
+    start: usize,
+}
+
+// PATH 的值、读取时间和其中的可执行文件
+type PathCache = Option<(String, Instant, Vec<String>)>;
+
+static PATH_CACHE: Lazy<Mutex<PathCache>> = Lazy::new(|| Mutex::new(None));
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+// PATH 中所有可执行文件的名字（排序、去重），短时间内重复调用使用缓存
+pub fn path_executables(path_var: &str) -> Vec<String> {
+    let mut cache = lock_recover(&PATH_CACHE);
+    if let Some((cached_path, at, names)) = cache.as_ref() {
+        if cached_path == path_var && at.elapsed() < PATH_CACHE_TTL {
+            return names.clone();
+        }
+    }
+
+    let mut names: Vec<String> = env::split_paths(path_var)
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(Result::ok))
+        .filter(|entry| is_executable(&entry.path()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names.dedup();
+    *cache = Some((path_var.to_string(), Instant::now(), names.clone()));
+    names
+}
+
+pub fn builtins() -> impl Iterator<Item = &'static str> {
+    BUILTINS.iter().map(|(name, _)| *name)
+}
+
+fn current_word(tokens: &[Token], cursor: usize) -> Word {
+    match tokens.last() {
+        Some(token) if token.kind == TokenKind::Word && token.end == cursor => Word {
+            value: token.value.clone(),
+            open_quote: token.open_quote,
+            start: token.start,
+        },
+        _ => Word {
+            value: String::new(),
+            open_quote: None,
+            start: cursor,
+        },
+    }
+}
+
+// 当前命令中光标之前的单词（不含开头的变量赋值）
+fn command_words(tokens: &[Token], word_start: usize) -> Vec<String> {
+    let begin = tokens
+        .iter()
+        .rposition(lexer::is_command_separator)
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let mut words = Vec::new();
+    let mut after_redirect = false;
+    for token in &tokens[begin..] {
+        if token.start >= word_start {
+            break;
+        }
+        match token.kind {
+            // 重定向的目标不算参数
+            TokenKind::Operator => after_redirect = true,
+            TokenKind::Word if after_redirect => after_redirect = false,
+            TokenKind::Word if words.is_empty() && lexer::is_assignment(&token.value) => {}
+            TokenKind::Word => words.push(token.value.clone()),
+        }
+    }
+    words
+}
+
+fn after_redirect(tokens: &[Token], word_start: usize) -> bool {
+    tokens
+        .iter()
+        .rev()
+        .find(|t| t.end <= word_start)
+        .map(|t| t.kind == TokenKind::Operator && (t.value.contains('>') || t.value.contains('<')))
+        .unwrap_or(false)
+}
+
+// 替换文本：保持用户已经输入的引号，否则转义特殊字符
+fn quote(word: &Word, text: &str, finished: bool) -> String {
+    match word.open_quote {
+        Some(q) if finished => format!("{}{}{}", q, text, q),
+        Some(q) => format!("{}{}", q, text),
+        None => match text.strip_prefix('~') {
+            Some(rest) => format!("~{}", lexer::escape(rest)),
+            None => lexer::escape(text),
+        },
+    }
+}
+
+fn candidate(word: &Word, text: &str, kind: CompletionKind, description: Option<&str>) -> Candidate {
+    let finished = kind != CompletionKind::Directory;
+    Candidate {
+        value: quote(word, text, finished),
+        display: match kind {
+            CompletionKind::File | CompletionKind::Directory => {
+                text.rsplit('/').find(|s| !s.is_empty()).unwrap_or(text).to_string()
+            }
+            _ => text.to_string(),
+        },
+        kind,
+        description: description.map(str::to_string),
+    }
+}
+
+fn expand_dir(dir: &str, cwd: &Path) -> PathBuf {
+    if dir == "~" || dir.starts_with("~/") {
+        session::home_dir().join(dir.trim_start_matches('~').trim_start_matches('/'))
+    } else if dir.is_empty() {
+        cwd.to_path_buf()
+    } else {
+        cwd.join(dir)
+    }
+}
+
+fn complete_paths(word: &Word, cwd: &Path, executables_only: bool) -> Vec<Candidate> {
+    let text = word.value.as_str();
+    // "~" 本身补全成 "~/"
+    if text == "~" {
+        return vec![candidate(word, "~/", CompletionKind::Directory, None)];
+    }
+    let (dir, prefix) = match text.rfind('/') {
+        Some(i) => (&text[..=i], &text[i + 1..]),
+        None => ("", text),
+    };
+    let entries = match fs::read_dir(expand_dir(dir, cwd)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut candidates = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+            continue;
+        }
+        let path = entry.path();
+        if path.is_dir() {
+            let text = format!("{}{}/", dir, name);
+            candidates.push(candidate(word, &text, CompletionKind::Directory, Some("directory")));
+        } else if !executables_only || is_executable(&path) {
+            let text = format!("{}{}", dir, name);
+            candidates.push(candidate(word, &text, CompletionKind::File, None));
+        }
+    }
+    candidates
+}
+
+fn complete_variables(word: &Word, env_overrides: &HashMap<String, String>) -> Vec<Candidate> {
+    let braced = word.value.starts_with("${");
+    let prefix = word.value.trim_start_matches('$').trim_start_matches('{');
+    let mut vars: BTreeMap<String, String> = env::vars().collect();
+    vars.extend(env_overrides.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    vars.into_iter()
+        .filter(|(name, _)| name.starts_with(prefix))
+        .map(|(name, value)| {
+            let text = if braced { format!("${{{}}}", name) } else { format!("${}", name) };
+            let mut description: String = value.chars().take(60).collect();
+            if description.len() < value.len() {
+                description.push('…');
+            }
+            Candidate {
+                // 变量引用不能被转义，否则不会展开
+                value: match word.open_quote {
+                    Some(q) => format!("{}{}", q, text),
+                    None => text.clone(),
+                },
+                display: text,
+                kind: CompletionKind::Variable,
+                description: Some(description),
+            }
+        })
+        .collect()
+}
+
+fn complete_commands(word: &Word, path_var: &str) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = BUILTINS
+        .iter()
+        .filter(|(name, _)| name.starts_with(&word.value))
+        .map(|(name, description)| candidate(word, name, CompletionKind::Builtin, Some(description)))
+        .collect();
+    for name in path_executables(path_var) {
+        if name.starts_with(&word.value) && !builtins().any(|b| b == name) {
+            candidates.push(candidate(word, &name, CompletionKind::Command, None));
+        }
+    }
+    candidates
+}
+
+fn git_output(cwd: &Path, args: &[&str]) -> Vec<String> {
+    Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn git_branches(word: &Word, cwd: &Path) -> Vec<Candidate> {
+    git_output(cwd, &["for-each-ref", "--format=%(refname)", "refs/heads", "refs/remotes"])
+        .into_iter()
+        .filter_map(|refname| {
+            if let Some(name) = refname.strip_prefix("refs/heads/") {
+                Some((name.to_string(), "local branch"))
+            } else {
+                refname
+                    .strip_prefix("refs/remotes/")
+                    .filter(|name| !name.ends_with("/HEAD"))
+                    .map(|name| (name.to_string(), "remote branch"))
+            }
+        })
+        .filter(|(name, _)| name.starts_with(&word.value))
+        .map(|(name, description)| candidate(word, &name, CompletionKind::Branch, Some(description)))
+        .collect()
+}
+
+fn git_remotes(word: &Word, cwd: &Path) -> Vec<Candidate> {
+    git_output(cwd, &["remote"])
+        .into_iter()
+        .filter(|name| name.starts_with(&word.value))
+        .map(|name| candidate(word, &name, CompletionKind::Remote, Some("remote")))
+        .collect()
+}
+
+// git 的补全；返回 None 表示按普通文件补全
+fn complete_git(word: &Word, args: &[String], cwd: &Path) -> Option<Vec<Candidate>> {
+    let subcommand = match args.first() {
+        None => {
+            return Some(
+                GIT_SUBCOMMANDS
+                    .iter()
+                    .filter(|(name, _)| name.starts_with(&word.value))
+                    .map(|(name, description)| {
+                        candidate(word, name, CompletionKind::Subcommand, Some(description))
+                    })
+                    .collect(),
+            )
+        }
+        Some(subcommand) => subcommand.as_str(),
+    };
+    let positional = args[1..].iter().filter(|a| !a.starts_with('-')).count();
+    if GIT_BRANCH_COMMANDS.contains(&subcommand) {
+        return Some(git_branches(word, cwd));
+    }
+    if GIT_REMOTE_COMMANDS.contains(&subcommand) {
+        return Some(match positional {
+            0 => git_remotes(word, cwd),
+            _ => git_branches(word, cwd),
+        });
+    }
+    if subcommand == "remote" && positional >= 1 {
+        return Some(git_remotes(word, cwd));
+    }
+    None
+}
+
+// 用户 shell 给出的候选；按是否存在判断文件和目录
+fn harvested(
+    shell: &str,
+    line: &str,
+    words: &[String],
+    word: &Word,
+    cwd: &Path,
+    env_overrides: &HashMap<String, String>,
+) -> Option<Vec<Candidate>> {
+    let mut all = words.to_vec();
+    all.push(word.value.clone());
+    let found = shell_completion::harvest(shell, line, &all, cwd, env_overrides)?;
+    let candidates: Vec<Candidate> = found
+        .into_iter()
+        .filter(|c| !c.value.is_empty())
+        .map(|c| {
+            let path = expand_dir(&c.value, cwd);
+            if c.value.ends_with('/') || path.is_dir() {
+                let text = format!("{}/", c.value.trim_end_matches('/'));
+                candidate(word, &text, CompletionKind::Directory, c.description.as_deref())
+            } else if path.exists() {
+                candidate(word, &c.value, CompletionKind::File, c.description.as_deref())
+            } else {
+                candidate(word, &c.value, CompletionKind::Argument, c.description.as_deref())
+            }
+        })
+        .collect();
+    if candidates.is_empty() {
+        None
+    } else {
+        Some(candidates)
+    }
+}
+
+// 只使用内置规则计算补全候选。cursor 和返回的范围都以字符计算。
+pub fn complete_line(
+    line: &str,
+    cursor: usize,
+    cwd: &Path,
+    env_overrides: &HashMap<String, String>,
+) -> Completions {
+    complete_line_with(line, cursor, cwd, env_overrides, None)
+}
+
+// 参数位置先询问 shell（zsh/bash）的补全脚本，没有结果时使用内置规则
+pub fn complete_line_with(
+    line: &str,
+    cursor: usize,
+    cwd: &Path,
+    env_overrides: &HashMap<String, String>,
+    shell: Option<&str>,
+) -> Completions {
+    let cursor = cursor.min(line.chars().count());
+    let before: String = line.chars().take(cursor).collect();
+    let tokens = lexer::tokenize(&before);
+    let word = current_word(&tokens, cursor);
+    let words = command_words(&tokens, word.start);
+    let path_var = env_overrides
+        .get("PATH")
+        .cloned()
+        .or_else(|| env::var("PATH").ok())
+        .unwrap_or_default();
+
+    let mut candidates = if word.open_quote != Some('\'') && word.value.starts_with('$') {
+        complete_variables(&word, env_overrides)
+    } else if after_redirect(&tokens, word.start) {
+        complete_paths(&word, cwd, false)
+    } else if words.is_empty() {
+        if word.value.contains('/') || word.value.starts_with('~') {
+            complete_paths(&word, cwd, true)
+        } else {
+            complete_commands(&word, &path_var)
+        }
+    } else if let Some(found) =
+        shell.and_then(|shell| harvested(shell, &before, &words, &word, cwd, env_overrides))
+    {
+        found
+    } else if word.value.starts_with('-') {
+        Vec::new()
+    } else if words[0] == "git" {
+        complete_git(&word, &words[1..], cwd).unwrap_or_else(|| complete_paths(&word, cwd, false))
+    } else if words[0] == "cd" {
+        complete_paths(&word, cwd, false)
+            .into_iter()
+            .filter(|c| c.kind == CompletionKind::Directory)
+            .collect()
+    } else {
+        complete_paths(&word, cwd, false)
+    };
+
+    candidates.sort_by(|a, b| a.display.cmp(&b.display));
+    candidates.dedup_by(|a, b| a.value == b.value);
+    candidates.truncate(MAX_CANDIDATES);
+    Completions {
+        start: word.start,
+        end: cursor,
+        candidates,
+    }
+}
+
+#[tauri::command]
+pub async fn complete(terminal_id: String, line: String, cursor: usize) -> TerminalResult<Completions> {
+    let (cwd, env) = process::session_context(terminal_id).await?;
+    run_blocking(move || Ok(complete_line_with(&line, cursor, &cwd, &env, Some(engine::DEFAULT_SHELL.as_str()))))
+        .await
+}
@@ -0,0 +1,359 @@
+// 本地控制接口：通过当前用户的 Unix domain socket 提供 JSON-RPC 2.0，
+// 让编辑器插件、脚本等外部工具列出终端、发送命令、读取输出或打开新标签页。
+// 每行一个请求/响应，所有请求都需要在 params.token 中携带 app data 目录下的令牌。
+// send_command 和前端一样经过危险命令检查，需要确认的命令返回 NEEDS_CONFIRMATION。
+
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use tauri::{AppHandle, Manager, Runtime};
+
+use super::daemon;
+use super::engine;
+use super::error::TerminalError;
+use super::guard;
+use super::process::format_current_dir;
+use super::session::{self, SessionInfo};
+use super::sink::{CommandStarted, OpenTerminal, OutputSink, TerminalEvent, WindowSink};
+
+const SOCKET_NAME: &str = "landingterm.sock";
+const TOKEN_FILE: &str = "control-token";
+const DEFAULT_OUTPUT_LINES: usize = 100;
+
+// JSON-RPC 错误码
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const TERMINAL_ERROR: i64 = -32000;
+const UNAUTHORIZED: i64 = -32001;
+// 危险命令需要确认：error.data 为 guard 的判定，确认后带上 params.confirmed = true 重新发送
+const NEEDS_CONFIRMATION: i64 = -32002;
+
+static SOCKET_PATH: OnceCell<PathBuf> = OnceCell::new();
+static REMOTE_TERMINALS: AtomicUsize = AtomicUsize::new(1);
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<TerminalError> for RpcError {
+    fn from(err: TerminalError) -> Self {
+        RpcError {
+            code: TERMINAL_ERROR,
+            message: err.to_string(),
+            data: serde_json::to_value(&err).ok(),
+        }
+    }
+}
+
+// 优先放在 XDG_RUNTIME_DIR（只有当前用户可访问），否则放在 app data 目录
+pub(crate) fn runtime_socket_path(data_dir: &Path, name: &str) -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join(name),
+        _ => data_dir.join(name),
+    }
+}
+
+pub fn socket_path(data_dir: &Path) -> PathBuf {
+    runtime_socket_path(data_dir, SOCKET_NAME)
+}
+
+pub fn token_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(TOKEN_FILE)
+}
+
+// 读取令牌文件，不存在时生成一个只有当前用户可读的新令牌
+pub(crate) fn load_or_create_token(path: &Path) -> io::Result<String> {
+    if let Ok(token) = fs::read_to_string(path) {
+        let token = token.trim().to_string();
+        if !token.is_empty() {
+            return Ok(token);
+        }
+    }
+
+    let mut bytes = [0u8; 32];
+    fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(token.as_bytes())?;
+    Ok(token)
+}
+
+// 比较完所有字节再给出结果，耗时不会泄露令牌有几位猜对了
+pub(crate) fn token_matches(given: Option<&str>, token: &str) -> bool {
+    let given = match given {
+        Some(given) if given.len() == token.len() => given,
+        _ => return false,
+    };
+    given.bytes().zip(token.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// 启动控制接口，监听线程在后台运行
+pub fn start<R: Runtime>(app: AppHandle<R>, data_dir: &Path) -> io::Result<()> {
+    let token = Arc::new(load_or_create_token(&token_path(data_dir))?);
+    let path = socket_path(data_dir);
+    let listener = bind_socket(&path)?;
+    let _ = SOCKET_PATH.set(path.clone());
+    println!("Control socket listening on {}", path.display());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let app = app.clone();
+                    let token = Arc::clone(&token);
+                    thread::spawn(move || handle_connection(stream, &token, &app));
+                }
+                Err(e) => eprintln!("Control socket accept failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+// 绑定只有当前用户可以访问的 socket，清理上次遗留的 socket 文件
+pub(crate) fn bind_socket(path: &Path) -> io::Result<UnixListener> {
+    if path.exists() {
+        // 已有实例在监听时不要抢占它的 socket
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("socket {} is already in use", path.display()),
+            ));
+        }
+        fs::remove_file(path)?;
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+pub fn remove_socket() {
+    if let Some(path) = SOCKET_PATH.get() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn handle_connection<R: Runtime>(stream: UnixStream, token: &str, app: &AppHandle<R>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = handle_line(&line, token, app);
+        if writeln!(writer, "{}", response).is_err() {
+            break;
+        }
+    }
+}
+
+fn handle_line<R: Runtime>(line: &str, token: &str, app: &AppHandle<R>) -> Value {
+    let request: Request = match serde_json::from_str::<Value>(line) {
+        Err(e) => return error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string())),
+        Ok(value) => match serde_json::from_value(value) {
+            Ok(request) => request,
+            Err(e) => {
+                return error_response(Value::Null, RpcError::new(INVALID_REQUEST, e.to_string()))
+            }
+        },
+    };
+
+    if !token_matches(request.params.get("token").and_then(Value::as_str), token) {
+        return error_response(request.id, RpcError::new(UNAUTHORIZED, "invalid or missing token"));
+    }
+
+    match dispatch(app, &request.method, &request.params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+        Err(err) => error_response(request.id, err),
+    }
+}
+
+fn error_response(id: Value, err: RpcError) -> Value {
+    let mut error = json!({ "code": err.code, "message": err.message });
+    if let Some(data) = err.data {
+        error["data"] = data;
+    }
+    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+}
+
+fn str_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("missing string parameter '{}'", name)))
+}
+
+fn main_window<R: Runtime>(app: &AppHandle<R>) -> Result<tauri::Window<R>, RpcError> {
+    app.get_window("main")
+        .ok_or_else(|| TerminalError::NotSupported("no window is open".to_string()).into())
+}
+
+// 所有会话的概要；连接了会话守护进程时会话都在守护进程里，需要向它查询
+fn sessions(lines: usize) -> Result<Vec<SessionInfo>, RpcError> {
+    match daemon::client() {
+        Some(client) => {
+            let value = client.call("sessions", json!({ "lines": lines }))?;
+            serde_json::from_value(value).map_err(|e| TerminalError::Daemon(e.to_string()).into())
+        }
+        None => Ok(session::infos(lines)),
+    }
+}
+
+// 计数器每次启动都从 1 开始，跳过恢复出来的或守护进程里已有的 remote-N
+fn new_remote_id() -> Result<String, RpcError> {
+    let mut existing: HashSet<String> = sessions(0)?.into_iter().map(|info| info.terminal_id).collect();
+    existing.extend(session::ids());
+    loop {
+        let terminal_id = format!("remote-{}", REMOTE_TERMINALS.fetch_add(1, Ordering::SeqCst));
+        if !existing.contains(&terminal_id) {
+            return Ok(terminal_id);
+        }
+    }
+}
+
+fn find_session(terminal_id: &str, lines: usize) -> Result<SessionInfo, RpcError> {
+    sessions(lines)?
+        .into_iter()
+        .find(|info| info.terminal_id == terminal_id)
+        .ok_or_else(|| TerminalError::TerminalNotFound(terminal_id.to_string()).into())
+}
+
+fn dispatch<R: Runtime>(app: &AppHandle<R>, method: &str, params: &Value) -> Result<Value, RpcError> {
+    match method {
+        "list_terminals" => {
+            let terminals = sessions(0)?
+                .into_iter()
+                .map(|info| {
+                    json!({
+                        "terminalId": info.terminal_id,
+                        "cwd": info.current_dir,
+                        "running": info.running,
+                        "command": info.command,
+                    })
+                })
+                .collect();
+            Ok(Value::Array(terminals))
+        }
+        "send_command" => {
+            let terminal_id = str_param(params, "terminalId")?.to_string();
+            let command = str_param(params, "command")?.to_string();
+            if find_session(&terminal_id, 0)?.running {
+                return Err(TerminalError::CommandRunning(terminal_id).into());
+            }
+            let confirmed = params.get("confirmed").and_then(Value::as_bool).unwrap_or(false);
+            if let Some(verdict) = guard::check(&command, confirmed)? {
+                return Err(RpcError {
+                    code: NEEDS_CONFIRMATION,
+                    message: format!("command needs confirmation: {}", verdict.reason()),
+                    data: serde_json::to_value(&verdict).ok(),
+                });
+            }
+
+            let window = main_window(app)?;
+            let sink: Arc<dyn OutputSink> = Arc::new(WindowSink::new(window));
+            sink.send(TerminalEvent::Started(CommandStarted {
+                terminal_id: terminal_id.clone(),
+                command: command.clone(),
+            }));
+            thread::spawn(move || {
+                // 守护进程广播的事件由 daemon::attach 转发给前端
+                let result = match daemon::client() {
+                    Some(client) => client
+                        .call("execute", json!({ "terminalId": terminal_id, "command": command }))
+                        .map(|_| ()),
+                    None => engine::execute(sink, &terminal_id, &command),
+                };
+                if let Err(e) = result {
+                    eprintln!("Remote command in terminal {} failed: {}", terminal_id, e);
+                }
+            });
+            Ok(json!({ "started": true }))
+        }
+        "read_output" => {
+            let terminal_id = str_param(params, "terminalId")?;
+            let lines = params
+                .get("lines")
+                .and_then(Value::as_u64)
+                .map(|n| n as usize)
+                .unwrap_or(DEFAULT_OUTPUT_LINES);
+            Ok(json!({ "lines": find_session(terminal_id, lines)?.scrollback }))
+        }
+        "open_terminal" => {
+            let dir = match params.get("cwd").and_then(Value::as_str) {
+                Some(cwd) => PathBuf::from(cwd)
+                    .canonicalize()
+                    .ok()
+                    .filter(|p| p.is_dir())
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("not a directory: {}", cwd)))?,
+                None => session::home_dir(),
+            };
+            let window = main_window(app)?;
+            let terminal_id = new_remote_id()?;
+            let current_dir = format_current_dir(&dir);
+            match daemon::client() {
+                Some(client) => {
+                    client.call("create", json!({ "terminalId": terminal_id, "cwd": dir }))?;
+                }
+                None => session::with_session(&terminal_id, |s| s.current_dir = dir)?,
+            }
+
+            WindowSink::new(window).send(TerminalEvent::Open(OpenTerminal {
+                terminal_id: terminal_id.clone(),
+                current_dir,
+            }));
+            Ok(json!({ "terminalId": terminal_id }))
+        }
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, format!("unknown method '{}'", method))),
+    }
+}
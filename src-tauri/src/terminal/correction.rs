@@ -0,0 +1,241 @@
+// 命令不存在（退出码 127）时，在 PATH 中的可执行文件、内建命令、shell 别名和历史命令里
+// 按编辑距离找出相近的命令，随完成事件一起发给前端，方便一键改正后重新运行。
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::completion;
+use super::engine::DEFAULT_SHELL;
+use super::history;
+use super::lexer::{self, TokenKind};
+use super::session::{self, lock_recover};
+use super::shell_completion;
+
+const NOT_FOUND_CODE: i32 = 127;
+const MAX_CORRECTIONS: usize = 5;
+// 只在输出的最后几行里找错误信息
+const TAIL_LINES: usize = 20;
+const ALIAS_CACHE_TTL: Duration = Duration::from_secs(300);
+
+// zsh: "zsh: command not found: gti"
+// bash: "bash: line 1: gti: command not found"
+// sh/dash: "sh: 1: gti: not found"
+// fish: "fish: Unknown command: gti"
+static NOT_FOUND_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"command not found: (\S+)|(?:^|: )([^\s:]+): command not found|^\S+: \d+: ([^\s:]+): not found|Unknown command:? '?([^'\s]+)",
+    )
+    .unwrap()
+});
+
+// 读取时间和别名列表
+type AliasCache = Option<(Instant, Vec<String>)>;
+
+static ALIASES: Lazy<Mutex<AliasCache>> = Lazy::new(|| Mutex::new(None));
+static LOADING_ALIASES: AtomicBool = AtomicBool::new(false);
+
+// 顺序即优先级：距离相同时别名优先，其次是历史命令
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum CorrectionSource {
+    Alias,
+    History,
+    Builtin,
+    Path,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Correction {
+    // 改正后的完整命令
+    pub command: String,
+    // 替换进去的命令名
+    pub replacement: String,
+    pub source: CorrectionSource,
+    pub distance: usize,
+}
+
+// 退出码为 127 且输出中有 "command not found" 一类的信息时，返回找不到的命令名
+pub fn missing_command(code: Option<i32>, output: &[String]) -> Option<String> {
+    if code != Some(NOT_FOUND_CODE) {
+        return None;
+    }
+    output.iter().rev().take(TAIL_LINES).find_map(|line| {
+        let caps = NOT_FOUND_RE.captures(line.trim())?;
+        (1..=4).find_map(|i| caps.get(i)).map(|m| m.as_str().to_string())
+    })
+}
+
+// 编辑距离（允许相邻字符交换，例如 gti → git 的距离为 1）
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+// 名字越长允许的差别越大
+fn max_distance(name: &str) -> usize {
+    match name.chars().count() {
+        0..=3 => 1,
+        4..=6 => 2,
+        _ => 3,
+    }
+}
+
+// 把命令中找不到的命令名替换成 replacement
+fn replace_command(command: &str, missing: &str, replacement: &str) -> Option<String> {
+    let token = lexer::tokenize(command)
+        .into_iter()
+        .find(|t| t.kind == TokenKind::Word && t.value == missing)?;
+    let chars: Vec<char> = command.chars().collect();
+    let head: String = chars[..token.start].iter().collect();
+    let tail: String = chars[token.end..].iter().collect();
+    Some(format!("{}{}{}", head, lexer::escape(replacement), tail))
+}
+
+// 在候选名字里找出和 missing 相近的，按距离和来源排序
+pub fn suggest(command: &str, missing: &str, candidates: &[(String, CorrectionSource)]) -> Vec<Correction> {
+    let limit = max_distance(missing);
+    let mut best: HashMap<&str, (usize, CorrectionSource)> = HashMap::new();
+    for (name, source) in candidates {
+        if name == missing {
+            continue;
+        }
+        let distance = edit_distance(missing, name);
+        if distance > limit {
+            continue;
+        }
+        let entry = best.entry(name.as_str()).or_insert((distance, *source));
+        if *source < entry.1 {
+            entry.1 = *source;
+        }
+    }
+
+    let mut matches: Vec<(&str, usize, CorrectionSource)> =
+        best.into_iter().map(|(name, (distance, source))| (name, distance, source)).collect();
+    matches.sort_by(|a, b| (a.1, a.2, a.0).cmp(&(b.1, b.2, b.0)));
+    matches
+        .into_iter()
+        .filter_map(|(name, distance, source)| {
+            Some(Correction {
+                command: replace_command(command, missing, name)?,
+                replacement: name.to_string(),
+                source,
+                distance,
+            })
+        })
+        .take(MAX_CORRECTIONS)
+        .collect()
+}
+
+// `alias` 的输出：bash 为 alias ll='ls -l'，zsh 为 ll='ls -l'
+pub fn parse_aliases(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("alias ").unwrap_or(line);
+            let (name, _) = line.split_once('=')?;
+            let name = name.trim_matches('\'');
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                None
+            } else {
+                Some(name.to_string())
+            }
+        })
+        .collect()
+}
+
+// 用户交互 shell 里定义的别名，缓存几分钟。读取要启动交互 shell，
+// 缓存为空或过期时在后台重新读取，这次先用已有的（可能为空），不拖慢命令完成
+fn shell_aliases() -> Vec<String> {
+    let cached = lock_recover(&ALIASES).clone();
+    let fresh = cached.as_ref().map(|(at, _)| at.elapsed() < ALIAS_CACHE_TTL).unwrap_or(false);
+    if !fresh && !LOADING_ALIASES.swap(true, Ordering::SeqCst) {
+        thread::spawn(|| {
+            let mut command = Command::new(DEFAULT_SHELL.as_str());
+            command.arg("-i").arg("-c").arg("alias");
+            let aliases = shell_completion::run(command, &session::home_dir(), &HashMap::new())
+                .map(|(_, output)| parse_aliases(&output))
+                .unwrap_or_default();
+            *lock_recover(&ALIASES) = Some((Instant::now(), aliases));
+            LOADING_ALIASES.store(false, Ordering::SeqCst);
+        });
+    }
+    cached.map(|(_, aliases)| aliases).unwrap_or_default()
+}
+
+fn candidates(env_overrides: &HashMap<String, String>) -> Vec<(String, CorrectionSource)> {
+    let mut all: Vec<(String, CorrectionSource)> = shell_aliases()
+        .into_iter()
+        .map(|name| (name, CorrectionSource::Alias))
+        .collect();
+
+    // 历史里成功运行过的命令名
+    let mut seen = HashSet::new();
+    history::with_entries(|entries| {
+        for entry in entries.iter().rev() {
+            if entry.exit_code.map(|code| code != 0).unwrap_or(false) {
+                continue;
+            }
+            let name = lexer::tokenize(&entry.command)
+                .into_iter()
+                .find(|t| t.kind == TokenKind::Word && !lexer::is_assignment(&t.value))
+                .map(|t| t.value);
+            if let Some(name) = name {
+                if seen.insert(name.clone()) {
+                    all.push((name, CorrectionSource::History));
+                }
+            }
+        }
+    });
+
+    all.extend(completion::builtins().map(|name| (name.to_string(), CorrectionSource::Builtin)));
+    let path_var = env_overrides
+        .get("PATH")
+        .cloned()
+        .or_else(|| env::var("PATH").ok())
+        .unwrap_or_default();
+    all.extend(
+        completion::path_executables(&path_var)
+            .into_iter()
+            .map(|name| (name, CorrectionSource::Path)),
+    );
+    all
+}
+
+// 命令执行结束后调用：没有找到命令时返回改正建议，否则返回空
+pub fn for_failed_command(
+    command: &str,
+    code: Option<i32>,
+    output: &[String],
+    env_overrides: &HashMap<String, String>,
+) -> Vec<Correction> {
+    match missing_command(code, output) {
+        Some(missing) => suggest(command, &missing, &candidates(env_overrides)),
+        None => Vec::new(),
+    }
+}
@@ -0,0 +1,487 @@
+// 会话守护进程（类似 tmux server）：在独立进程里持有终端会话、运行中的命令和输出缓冲。
+// 应用通过本地 socket 连接上去，关闭应用只是断开连接，命令继续运行，下次启动时重新连接。
+//
+// 协议为每行一个 JSON：请求 {"id", "method", "params"}，应答 {"id", "result"} 或 {"id", "error"}，
+// 引擎事件以 {"event", "payload"} 的形式广播给所有已连接的客户端。
+// 连接后的第一个请求必须是 hello，params.token 为 app data 目录下的令牌，通过之后才会收到事件。
+
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Runtime};
+
+use super::control;
+use super::engine;
+use super::error::{TerminalError, TerminalResult};
+use super::history::{self, HistoryQuery};
+use super::limits::ResourceLimits;
+use super::orphans;
+use super::persist;
+use super::policy;
+use super::process_tree;
+use super::sandbox;
+use super::session::{self, lock_recover};
+use super::shutdown;
+use super::sink::{OutputSink, TerminalEvent};
+use super::suggest;
+
+const SOCKET_NAME: &str = "landingterm-sessiond.sock";
+const TOKEN_FILE: &str = "sessiond-token";
+const DAEMON_BINARY: &str = "landingterm-sessiond";
+// 设置后应用启动时会在需要时自动拉起守护进程
+pub const ENABLE_ENV: &str = "LANDINGTERM_SESSIOND";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+// 客户端长时间不读取时放弃它，避免拖住命令输出
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn socket_path(data_dir: &Path) -> PathBuf {
+    control::runtime_socket_path(data_dir, SOCKET_NAME)
+}
+
+pub fn token_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(TOKEN_FILE)
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+type Writer = Arc<Mutex<UnixStream>>;
+
+// 把引擎事件广播给所有已连接的应用
+#[derive(Default)]
+struct BroadcastSink {
+    clients: Mutex<Vec<Writer>>,
+}
+
+impl BroadcastSink {
+    fn attach(&self, writer: &Writer) {
+        lock_recover(&self.clients).push(Arc::clone(writer));
+    }
+
+    fn detach(&self, writer: &Writer) {
+        lock_recover(&self.clients).retain(|w| !Arc::ptr_eq(w, writer));
+    }
+}
+
+impl OutputSink for BroadcastSink {
+    fn send(&self, event: TerminalEvent) {
+        let line = json!({ "event": event.name(), "payload": event }).to_string();
+        lock_recover(&self.clients).retain(|w| writeln!(lock_recover(w), "{}", line).is_ok());
+    }
+}
+
+// 运行守护进程，直到收到 shutdown 请求
+pub fn serve(path: &Path, data_dir: &Path) -> io::Result<()> {
+    let token = Arc::new(control::load_or_create_token(&token_path(data_dir))?);
+    let listener = control::bind_socket(path)?;
+    println!("Session daemon listening on {}", path.display());
+
+    let sink = Arc::new(BroadcastSink::default());
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let sink = Arc::clone(&sink);
+                let token = Arc::clone(&token);
+                let path = path.to_path_buf();
+                thread::spawn(move || serve_client(stream, sink, &token, &path));
+            }
+            Err(e) => eprintln!("Session daemon accept failed: {}", e),
+        }
+    }
+    Ok(())
+}
+
+fn serve_client(stream: UnixStream, sink: Arc<BroadcastSink>, token: &str, path: &Path) {
+    let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+    let writer: Writer = match stream.try_clone() {
+        Ok(writer) => Arc::new(Mutex::new(writer)),
+        Err(_) => return,
+    };
+    let mut lines = BufReader::new(stream).lines();
+
+    // 令牌不对的连接既不能发请求，也收不到命令输出
+    let hello: Option<Request> = lines.next().and_then(|line| line.ok()).and_then(|l| serde_json::from_str(&l).ok());
+    let given = |request: &Request| request.params.get("token").and_then(Value::as_str).map(str::to_string);
+    match hello {
+        Some(request) if request.method == "hello" && control::token_matches(given(&request).as_deref(), token) => {
+            respond(&writer, request.id, Ok(Value::Null));
+        }
+        other => {
+            let id = other.map(|request| request.id).unwrap_or(Value::Null);
+            let denied = TerminalError::PermissionDenied("invalid or missing token".to_string());
+            respond(&writer, id, Err(denied));
+            return;
+        }
+    }
+    sink.attach(&writer);
+
+    for line in lines {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!("Ignoring malformed daemon request: {}", e);
+                continue;
+            }
+        };
+
+        if request.method == "shutdown" {
+            respond(&writer, request.id, Ok(Value::Null));
+            persist::save();
+            shutdown::shutdown_all(shutdown::SHUTDOWN_GRACE);
+            let _ = std::fs::remove_file(path);
+            std::process::exit(0);
+        }
+
+        // 命令可能长时间运行，每个请求单独一个线程
+        let sink = Arc::clone(&sink);
+        let writer = Arc::clone(&writer);
+        thread::spawn(move || {
+            let result = handle(sink, &request.method, &request.params);
+            respond(&writer, request.id, result);
+        });
+    }
+    // 应用断开连接，会话和命令继续保留
+    sink.detach(&writer);
+}
+
+fn respond(writer: &Writer, id: Value, result: TerminalResult<Value>) {
+    let response = match result {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(e) => json!({ "id": id, "error": e }),
+    };
+    let _ = writeln!(lock_recover(writer), "{}", response);
+}
+
+fn handle(sink: Arc<BroadcastSink>, method: &str, params: &Value) -> TerminalResult<Value> {
+    let str_param = |name: &str| {
+        params
+            .get(name)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| TerminalError::NotSupported(format!("missing parameter '{}'", name)))
+    };
+
+    match method {
+        "execute" => {
+            let terminal_id = str_param("terminalId")?;
+            let command = str_param("command")?;
+            let limits = match params.get("limits") {
+                Some(Value::Null) | None => ResourceLimits::default(),
+                Some(value) => {
+                    serde_json::from_value(value.clone()).map_err(|e| TerminalError::NotSupported(e.to_string()))?
+                }
+            };
+            engine::execute_with_limits(sink, &terminal_id, &command, limits)?;
+            Ok(Value::Null)
+        }
+        "stop" => {
+            engine::stop(sink.as_ref(), &str_param("terminalId")?)?;
+            Ok(Value::Null)
+        }
+        "create" => {
+            let terminal_id = str_param("terminalId")?;
+            session::get_or_create(&terminal_id);
+            if let Some(cwd) = params.get("cwd").and_then(Value::as_str) {
+                session::with_existing(&terminal_id, |s| s.current_dir = PathBuf::from(cwd))?;
+            }
+            Ok(Value::Null)
+        }
+        "close" => {
+            let terminal_id = str_param("terminalId")?;
+            let handle = session::remove(&terminal_id)
+                .ok_or_else(|| TerminalError::TerminalNotFound(terminal_id.clone()))?;
+            let running = session::lock(&terminal_id, &handle)?.running.take();
+            if let Some(mut child) = running {
+                let _ = shutdown::kill_tree(&mut child);
+            }
+            Ok(Value::Null)
+        }
+        "profile" => {
+            let profile = params.get("profile").and_then(Value::as_str).map(str::to_string);
+            policy::set_profile(&str_param("terminalId")?, profile)?;
+            Ok(Value::Null)
+        }
+        "sandbox" => {
+            let sandbox = match params.get("sandbox") {
+                Some(Value::Null) | None => None,
+                Some(value) => Some(
+                    serde_json::from_value(value.clone()).map_err(|e| TerminalError::NotSupported(e.to_string()))?,
+                ),
+            };
+            sandbox::set_sandbox(&str_param("terminalId")?, sandbox)?;
+            Ok(Value::Null)
+        }
+        // 标题、环境变量和启动命令是用户的设置，改了立即保存快照
+        "title" => {
+            let title = params.get("title").and_then(Value::as_str).map(str::to_string);
+            persist::set_title(&str_param("terminalId")?, title)?;
+            persist::save();
+            Ok(Value::Null)
+        }
+        "env" => {
+            let env = serde_json::from_value(params.get("env").cloned().unwrap_or_else(|| json!({})))
+                .map_err(|e| TerminalError::NotSupported(e.to_string()))?;
+            persist::set_env(&str_param("terminalId")?, env)?;
+            persist::save();
+            Ok(Value::Null)
+        }
+        "startup_command" => {
+            let command = params.get("command").and_then(Value::as_str).map(str::to_string);
+            persist::set_startup(&str_param("terminalId")?, command)?;
+            persist::save();
+            Ok(Value::Null)
+        }
+        "process_tree" => {
+            let tree = process_tree::process_tree(&str_param("terminalId")?)?;
+            Ok(serde_json::to_value(tree).unwrap_or(Value::Null))
+        }
+        "signal" => {
+            let pid = params
+                .get("pid")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| TerminalError::NotSupported("missing parameter 'pid'".to_string()))?;
+            process_tree::signal_process(&str_param("terminalId")?, pid as u32, &str_param("signal")?)?;
+            Ok(Value::Null)
+        }
+        "orphans" => Ok(serde_json::to_value(orphans::orphans()).unwrap_or(Value::Null)),
+        "resolve_orphan" => {
+            let pid = params
+                .get("pid")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| TerminalError::NotSupported("missing parameter 'pid'".to_string()))?;
+            let action = serde_json::from_value(params.get("action").cloned().unwrap_or(Value::Null))
+                .map_err(|e| TerminalError::NotSupported(e.to_string()))?;
+            orphans::resolve(pid as u32, action)?;
+            Ok(Value::Null)
+        }
+        // 补全、语法检查在应用里进行，需要会话的工作目录和环境变量
+        "context" => {
+            let terminal_id = str_param("terminalId")?;
+            let context = session::with_existing(&terminal_id, |s| (s.current_dir.clone(), s.env.clone()))?;
+            Ok(serde_json::to_value(context).unwrap_or(Value::Null))
+        }
+        "suggest" => {
+            let suggestion = suggest::suggest_for(&str_param("terminalId")?, &str_param("prefix")?)?;
+            Ok(serde_json::to_value(suggestion).unwrap_or(Value::Null))
+        }
+        "search_history" => {
+            let query: HistoryQuery = serde_json::from_value(params.get("query").cloned().unwrap_or_else(|| json!({})))
+                .map_err(|e| TerminalError::NotSupported(e.to_string()))?;
+            Ok(serde_json::to_value(history::query(&query)).unwrap_or(Value::Null))
+        }
+        "sessions" => {
+            let lines = params.get("lines").and_then(Value::as_u64).unwrap_or(0) as usize;
+            Ok(serde_json::to_value(session::infos(lines)).unwrap_or(Value::Null))
+        }
+        _ => Err(TerminalError::NotSupported(format!("unknown method '{}'", method))),
+    }
+}
+
+// 应用一侧的连接
+pub struct DaemonClient {
+    writer: Mutex<UnixStream>,
+    pending: Mutex<HashMap<u64, Sender<TerminalResult<Value>>>>,
+    next_id: AtomicU64,
+    connected: AtomicBool,
+}
+
+static CLIENT: OnceCell<Arc<DaemonClient>> = OnceCell::new();
+
+// 已连接守护进程时返回客户端，否则终端命令在应用进程内运行
+pub fn client() -> Option<&'static DaemonClient> {
+    CLIENT.get().map(Arc::as_ref).filter(|c| c.is_connected())
+}
+
+fn enabled() -> bool {
+    env::var(ENABLE_ENV).map(|v| !v.is_empty() && v != "0").unwrap_or(false)
+}
+
+// 连接到守护进程。已有守护进程在运行时总是重新连接；
+// 没有时只有启用了 LANDINGTERM_SESSIOND 才会拉起一个新的。
+pub fn attach<R: Runtime>(app: AppHandle<R>, data_dir: &Path) -> io::Result<()> {
+    if CLIENT.get().is_some() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "already attached"));
+    }
+    let path = socket_path(data_dir);
+    let token = control::load_or_create_token(&token_path(data_dir))?;
+    let stream = match UnixStream::connect(&path) {
+        Ok(stream) => stream,
+        Err(_) if enabled() => {
+            spawn_daemon(&path, data_dir)?;
+            connect_with_retry(&path)?
+        }
+        Err(_) => return Ok(()),
+    };
+    let client = DaemonClient::start(stream, &token, move |event, payload| {
+        let _ = app.emit_all(event, payload);
+    })?;
+    if CLIENT.set(client).is_err() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "already attached"));
+    }
+    println!("Attached to session daemon at {}", path.display());
+    Ok(())
+}
+
+fn spawn_daemon(path: &Path, data_dir: &Path) -> io::Result<()> {
+    let exe = env::current_exe()?.with_file_name(DAEMON_BINARY);
+    let mut command = Command::new(&exe);
+    command
+        .arg("--socket")
+        .arg(path)
+        .arg("--data-dir")
+        .arg(data_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // 脱离应用的会话，应用退出时守护进程不会收到 SIGHUP
+    unsafe {
+        command.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", exe.display(), e)))?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+// 发送令牌并等待守护进程确认，返回之后读取事件用的 reader
+fn hello(mut stream: &UnixStream, token: &str) -> io::Result<BufReader<UnixStream>> {
+    writeln!(stream, "{}", json!({ "id": 0, "method": "hello", "params": { "token": token } }))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    stream.set_read_timeout(None)?;
+    let response: Value = serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    match response.get("error") {
+        Some(_) => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "session daemon rejected the token",
+        )),
+        None => Ok(reader),
+    }
+}
+
+fn connect_with_retry(path: &Path) -> io::Result<UnixStream> {
+    let deadline = Instant::now() + CONNECT_TIMEOUT;
+    loop {
+        match UnixStream::connect(path) {
+            Ok(stream) => return Ok(stream),
+            Err(e) if Instant::now() >= deadline => return Err(e),
+            Err(_) => thread::sleep(Duration::from_millis(50)),
+        }
+    }
+}
+
+// 把守护进程广播的事件交给 on_event，并把应答交给等待中的请求
+fn read_events(reader: BufReader<UnixStream>, client: Arc<DaemonClient>, on_event: impl Fn(&str, Value)) {
+    for line in reader.lines() {
+        let message: Value = match line.ok().and_then(|l| serde_json::from_str(&l).ok()) {
+            Some(message) => message,
+            None => break,
+        };
+        if let Some(event) = message.get("event").and_then(Value::as_str) {
+            let payload = message.get("payload").cloned().unwrap_or(Value::Null);
+            on_event(event, payload);
+            continue;
+        }
+
+        let sender = message
+            .get("id")
+            .and_then(Value::as_u64)
+            .and_then(|id| lock_recover(&client.pending).remove(&id));
+        if let Some(sender) = sender {
+            let result = match message.get("error") {
+                Some(error) => Err(TerminalError::Daemon(
+                    error
+                        .get("message")
+                        .and_then(Value::as_str)
+                        .unwrap_or("unknown error")
+                        .to_string(),
+                )),
+                None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+            };
+            let _ = sender.send(result);
+        }
+    }
+
+    // 连接断开后回到应用内运行，等待中的请求全部失败
+    eprintln!("Lost connection to session daemon");
+    client.connected.store(false, Ordering::SeqCst);
+    lock_recover(&client.pending).clear();
+}
+
+impl DaemonClient {
+    // 连接守护进程并完成握手；之后收到的事件交给 on_event
+    pub fn connect<F>(path: &Path, token: &str, on_event: F) -> io::Result<Arc<DaemonClient>>
+    where
+        F: Fn(&str, Value) + Send + 'static,
+    {
+        DaemonClient::start(UnixStream::connect(path)?, token, on_event)
+    }
+
+    fn start<F>(stream: UnixStream, token: &str, on_event: F) -> io::Result<Arc<DaemonClient>>
+    where
+        F: Fn(&str, Value) + Send + 'static,
+    {
+        let reader = hello(&stream, token)?;
+        let client = Arc::new(DaemonClient {
+            writer: Mutex::new(stream),
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            connected: AtomicBool::new(true),
+        });
+        let reader_client = Arc::clone(&client);
+        thread::spawn(move || read_events(reader, reader_client, on_event));
+        Ok(client)
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    // 断开连接；守护进程里的会话和命令继续运行
+    pub fn disconnect(&self) {
+        let _ = lock_recover(&self.writer).shutdown(std::net::Shutdown::Both);
+    }
+
+    pub fn call(&self, method: &str, params: Value) -> TerminalResult<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = mpsc::channel();
+        lock_recover(&self.pending).insert(id, tx);
+
+        let request = json!({ "id": id, "method": method, "params": params });
+        if let Err(e) = writeln!(lock_recover(&self.writer), "{}", request) {
+            lock_recover(&self.pending).remove(&id);
+            return Err(e.into());
+        }
+        rx.recv().unwrap_or_else(|_| {
+            Err(TerminalError::Daemon("connection to session daemon lost".to_string()))
+        })
+    }
+}
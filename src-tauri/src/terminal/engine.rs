@@ -0,0 +1,636 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::env;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+#[cfg(unix)]
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+#[cfg(windows)]
+use std::os::windows::process::ExitStatusExt;
+
+use super::correction;
+use super::error::{TerminalError, TerminalResult};
+use super::history::{self, HistoryEntry};
+use super::limits::{killed_exit_code, Enforcer, ResourceLimits};
+use super::orphans;
+use super::policy;
+use super::ports::PortWatcher;
+use super::procfs;
+use super::sandbox;
+use super::process::format_current_dir;
+use super::session;
+use super::shutdown;
+use super::sink::{CommandComplete, OutputSink, StreamOutput, TerminalEvent};
+use super::stats::{Sampler, StatsSummary};
+use super::title::{self, OscParser};
+
+// 检查资源限制的间隔
+const LIMIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// 采样资源占用和检查监听端口的间隔
+const MONITOR_INTERVAL: Duration = Duration::from_secs(1);
+
+// 用于匹配ANSI转义序列的正则表达式
+static ANSI_ESCAPE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(\x9B|\x1B\[)[0-?]*[ -/]*[@-~]").unwrap()
+});
+
+// 用于提取进度信息的正则表达式
+static PROGRESS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"([\d.]+\s*(?:GB|MB|KB)/[\d.]+\s*(?:GB|MB|KB)|[\d.]+%|[\d.]+\s*(?:GB|MB|KB)/s)").unwrap()
+});
+
+// 用于匹配状态信息的正则表达式
+static STATUS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"pulling (?:manifest|[0-9a-f]{12})\.\.\.").unwrap()
+});
+
+// 优先使用 zsh，没有安装时退回 bash
+pub static DEFAULT_SHELL: Lazy<String> = Lazy::new(|| {
+    if has_program("zsh") { "zsh" } else { "bash" }.to_string()
+});
+
+static HAS_UNBUFFER: Lazy<bool> = Lazy::new(|| has_program("unbuffer"));
+
+pub(crate) fn has_program(name: &str) -> bool {
+    Command::new("which")
+        .arg(name)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamKind {
+    Stdout,
+    Stderr,
+}
+
+impl StreamKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamKind::Stdout => "stdout",
+            StreamKind::Stderr => "stderr",
+        }
+    }
+}
+
+// 把原始输出切分成行；下载类命令（ollama/curl/wget）只保留状态和进度
+pub struct LineDecoder {
+    kind: StreamKind,
+    is_download_command: bool,
+    current_line: String,
+    is_progress_line: bool,
+    last_status: String,
+    osc: OscParser,
+    // 程序通过 OSC 0/1/2 设置、还没有被取走的标题
+    title: Option<String>,
+}
+
+impl LineDecoder {
+    pub fn new(kind: StreamKind, command: &str) -> Self {
+        LineDecoder {
+            kind,
+            is_download_command: is_download_command(command),
+            current_line: String::new(),
+            is_progress_line: false,
+            last_status: String::new(),
+            osc: OscParser::new(),
+            title: None,
+        }
+    }
+
+    pub fn take_title(&mut self) -> Option<String> {
+        self.title.take()
+    }
+
+    // 返回 (内容, 是否替换上一行)
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<(String, bool)> {
+        let mut lines = Vec::new();
+        let (chunk, title) = self.osc.strip(&String::from_utf8_lossy(bytes));
+        if title.is_some() {
+            self.title = title;
+        }
+        let cleaned_chunk = ANSI_ESCAPE_RE.replace_all(&chunk, "");
+
+        for c in cleaned_chunk.chars() {
+            match c {
+                '\r' | '\n' => {
+                    if !self.current_line.is_empty() {
+                        let line = std::mem::take(&mut self.current_line);
+                        self.finish_line(&line, &mut lines);
+                    }
+                }
+                _ => self.current_line.push(c),
+            }
+        }
+
+        // 如果还有未发送的内容且不是进度信息，发送它
+        if !self.current_line.is_empty()
+            && !self.is_progress_line
+            && !STATUS_RE.is_match(&self.current_line)
+        {
+            lines.push((self.current_line.clone(), false));
+        }
+        lines
+    }
+
+    fn finish_line(&mut self, line: &str, lines: &mut Vec<(String, bool)>) {
+        if !self.is_download_command {
+            lines.push((line.to_string(), false));
+            return;
+        }
+        match self.kind {
+            StreamKind::Stdout => {
+                let mut new_progress = String::new();
+                // 提取进度信息
+                for cap in PROGRESS_RE.find_iter(line) {
+                    new_progress.push_str(cap.as_str());
+                    new_progress.push(' ');
+                }
+                // 提取状态信息，只有当状态改变时才发送
+                if let Some(status_match) = STATUS_RE.find(line) {
+                    let new_status = status_match.as_str().to_string();
+                    if new_status != self.last_status {
+                        self.last_status = new_status.clone();
+                        lines.push((new_status, false));
+                    }
+                }
+                if !new_progress.is_empty() {
+                    self.is_progress_line = true;
+                    lines.push((new_progress, true));
+                } else if !line.trim().is_empty() && !STATUS_RE.is_match(line) {
+                    // 如果不是进度信息也不是状态信息，且不是空行，正常发送
+                    lines.push((line.to_string(), false));
+                }
+            }
+            StreamKind::Stderr => {
+                if let Some(status_match) = STATUS_RE.find(line) {
+                    let new_status = status_match.as_str().to_string();
+                    if new_status != self.last_status {
+                        self.last_status = new_status.clone();
+                        lines.push((new_status, false));
+                    }
+                } else if PROGRESS_RE.is_match(line) {
+                    self.is_progress_line = true;
+                    lines.push((line.to_string(), true));
+                } else if !line.trim().is_empty() {
+                    lines.push((line.to_string(), false));
+                }
+            }
+        }
+    }
+}
+
+// 检查命令是否是下载相关命令
+pub fn is_download_command(command: &str) -> bool {
+    command.contains("ollama") || command.contains("curl") || command.contains("wget")
+}
+
+#[cfg(unix)]
+pub(crate) fn new_process_group(command: &mut Command) {
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setpgid(0, 0) == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        });
+    }
+}
+
+// 用 unbuffer 或 script 包一层伪终端，让程序按行刷新输出
+fn wrapped_command(shell: &str, command: &str) -> (&'static str, Command) {
+    if *HAS_UNBUFFER {
+        let mut cmd = Command::new("unbuffer");
+        cmd.arg(shell).arg("-c").arg(command);
+        return ("unbuffer", cmd);
+    }
+
+    let mut cmd = Command::new("script");
+    if cfg!(target_os = "linux") {
+        // util-linux 的 script 只接受 -c 传入的命令字符串
+        cmd.arg("-q")
+            .arg("-e")
+            .arg("-c")
+            .arg(format!("{} -c {}", shell, shell_quote(command)))
+            .arg("/dev/null");
+    } else {
+        cmd.arg("-q").arg("/dev/null").arg(shell).arg("-c").arg(command);
+    }
+    ("script", cmd)
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+// 发送一行输出并记录到该终端的回滚缓冲区；调用时不能持有会话锁
+fn emit_output(sink: &dyn OutputSink, terminal_id: &str, current_dir: &str, kind: StreamKind, content: String, replace: bool) {
+    if !content.is_empty() {
+        let _ = session::with_existing(terminal_id, |s| s.push_output(&content, replace));
+    }
+    sink.send(TerminalEvent::Output(StreamOutput {
+        content,
+        output_type: kind.as_str().to_string(),
+        current_dir: current_dir.to_string(),
+        should_replace_last: replace,
+        terminal_id: terminal_id.to_string(),
+    }));
+}
+
+// 在终端会话里执行一条命令，把输出和完成事件写到 sink。
+// 阻塞直到命令结束，调用方需要放在后台线程里执行。
+pub fn execute(sink: Arc<dyn OutputSink>, terminal_id: &str, command: &str) -> TerminalResult<()> {
+    execute_with_limits(sink, terminal_id, command, ResourceLimits::default())
+}
+
+// 同 execute，超过 limits 中任意一项时终止命令并在完成事件里说明
+pub fn execute_with_limits(
+    sink: Arc<dyn OutputSink>,
+    terminal_id: &str,
+    command: &str,
+    limits: ResourceLimits,
+) -> TerminalResult<()> {
+    // 同一个终端同时只运行一个命令，否则前一个命令将无法停止；
+    // 检查和占用在同一次加锁里完成，同时到达的两个命令只有一个能启动
+    let (current_dir, env, profile, sandbox) = session::with_session(terminal_id, |s| {
+        if s.running.is_some() || s.starting {
+            return Err(TerminalError::CommandRunning(terminal_id.to_string()));
+        }
+        s.starting = true;
+        Ok((s.current_dir.clone(), s.env.clone(), s.profile.clone(), s.sandbox.clone()))
+    })??;
+    let reservation = Reservation(terminal_id);
+    // 受限终端先按策略检查，违反策略的命令不会启动
+    let max_runtime = policy::enforce(terminal_id, profile.as_deref(), command, &current_dir)?;
+    session::with_existing(terminal_id, |s| s.start_block(command))?;
+
+    let started_at = history::now_millis();
+    let started = Instant::now();
+    let record = |exit_code: Option<i32>| {
+        history::record(HistoryEntry {
+            command: command.to_string(),
+            cwd: current_dir.to_string_lossy().into_owned(),
+            terminal_id: terminal_id.to_string(),
+            started_at,
+            duration_ms: started.elapsed().as_millis() as u64,
+            exit_code,
+            source: None,
+            estimated_time: false,
+        });
+    };
+
+    if command.trim().starts_with("cd") {
+        let code = if cd(sink.as_ref(), terminal_id, command)? { 0 } else { 1 };
+        session::with_existing(terminal_id, |s| s.finish_block(Some(code)))?;
+        record(Some(code));
+        return Ok(());
+    }
+
+    let (wrapper, cmd) = wrapped_command(&DEFAULT_SHELL, command);
+    // 沙箱模式的终端把整条命令放进独立的命名空间里运行
+    let (wrapper, mut cmd) = match &sandbox {
+        Some(config) => sandbox::wrap(config, &current_dir, &cmd)?,
+        None => (wrapper, cmd),
+    };
+    cmd.current_dir(&current_dir)
+        .envs(&env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // 放到独立的进程组，关闭应用时可以连同子进程一起终止
+    #[cfg(unix)]
+    new_process_group(&mut cmd);
+    // 策略规定的最长运行时间按超时处理
+    let limits = limits.with_timeout(max_runtime);
+    let enforcer = if limits.is_empty() { None } else { Some(Arc::new(Enforcer::new(limits))) };
+    if let Some(enforcer) = &enforcer {
+        enforcer.prepare(&mut cmd);
+    }
+    let mut child = cmd.spawn().map_err(|e| TerminalError::spawn(wrapper, e))?;
+
+    // 在存储到会话之前，先获取stdout和stderr
+    let stdout = child.stdout.take()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "failed to capture stdout"))?;
+    let stderr = child.stderr.take()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "failed to capture stderr"))?;
+
+    // 记录到磁盘，应用崩溃后可以找回这个进程
+    let pid = child.id();
+    orphans::record(terminal_id, pid, command);
+
+    // 将进程存储到该终端的会话中；启动期间标签页被关闭时直接终止它
+    let mut child = Some(child);
+    let stored = session::with_existing(terminal_id, |s| {
+        s.running = child.take();
+        s.command = Some(command.to_string());
+    });
+    if let Err(e) = stored {
+        if let Some(mut child) = child {
+            let _ = shutdown::kill_tree(&mut child);
+        }
+        orphans::forget(pid);
+        return Err(e);
+    }
+    // 进程已经存进会话，由 running 挡住后来的命令
+    drop(reservation);
+
+    let current_dir_str = format_current_dir(&current_dir);
+    // 读取结束后 finished 被丢弃，监视线程随之退出
+    let (finished, finished_rx) = mpsc::channel::<()>();
+    let watcher = enforcer
+        .as_ref()
+        .filter(|e| e.needs_watch())
+        .map(|enforcer| watch_limits(Arc::clone(enforcer), terminal_id, pid, finished_rx));
+    let (monitor_done, monitor_rx) = mpsc::channel::<()>();
+    let monitor = monitor_process(Arc::clone(&sink), terminal_id, pid, monitor_rx);
+    let readers = [
+        spawn_reader(Box::new(stdout), StreamKind::Stdout, &sink, terminal_id, &current_dir_str, command, enforcer.clone()),
+        spawn_reader(Box::new(stderr), StreamKind::Stderr, &sink, terminal_id, &current_dir_str, command, enforcer.clone()),
+    ];
+
+    // Wait for output handlers to complete first
+    // 读取线程 panic 时只向这个终端报告，不影响其他终端
+    for (reader, kind) in readers.into_iter().zip([StreamKind::Stdout, StreamKind::Stderr]) {
+        if reader.join().is_err() {
+            emit_output(
+                sink.as_ref(),
+                terminal_id,
+                &current_dir_str,
+                StreamKind::Stderr,
+                format!("Internal error while reading {}", kind.as_str()),
+                false,
+            );
+        }
+    }
+
+    // 进程还没有被回收，最后再采样一次
+    drop(monitor_done);
+    let stats = monitor.join().ok().flatten();
+    // 等监视线程返回，被资源限制终止时拿到进程真实的退出状态
+    drop(finished);
+    let killed = watcher.and_then(|watcher| watcher.join().ok().flatten());
+
+    // Then handle the process completion
+    let running = session::with_existing(terminal_id, |s| {
+        s.command = None;
+        s.running.take()
+    });
+    let status = match running {
+        Ok(Some(mut child)) => child.wait()?,
+        // 如果进程不存在（已经被停止或者标签页已关闭），返回一个表示中断的状态
+        _ => killed.unwrap_or_else(interrupted_status),
+    };
+    // 被资源限制终止的命令按 shell 的习惯报告 128 + 信号值，和 limit_exceeded 对得上
+    let code = if killed.is_some() { killed_exit_code(&status) } else { status.code() };
+    orphans::forget(pid);
+    let output = session::with_existing(terminal_id, |s| {
+        s.finish_block(code);
+        s.blocks.back().map(|b| session::tail(&b.output, 20)).unwrap_or_default()
+    })?;
+    record(code);
+    // 命令不存在时附上相近的命令
+    let suggestions = correction::for_failed_command(command, code, &output, &env);
+    let limit_exceeded = enforcer.as_ref().and_then(|e| e.finish(&status, &output));
+    if let (Some(kind), Some(enforcer)) = (limit_exceeded, &enforcer) {
+        emit_output(
+            sink.as_ref(),
+            terminal_id,
+            &current_dir_str,
+            StreamKind::Stderr,
+            kind.message(enforcer.limits()),
+            false,
+        );
+    }
+
+    // 命令结束，标题恢复为工作目录
+    title::set_program_title(sink.as_ref(), terminal_id, None)?;
+
+    // Emit command completion event with terminal ID
+    sink.send(TerminalEvent::Complete(CommandComplete {
+        terminal_id: terminal_id.to_string(),
+        code,
+        suggestions,
+        limit_exceeded,
+        stats,
+    }));
+
+    Ok(())
+}
+
+// 从通过检查到进程存进会话之间占住终端；被丢弃时释放，包括策略拒绝、启动失败等提前返回
+struct Reservation<'a>(&'a str);
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        let _ = session::with_existing(self.0, |s| s.starting = false);
+    }
+}
+
+// 超时、输出过多或 CPU 时间用完时终止命令并返回它的退出状态；说明由 execute 在命令结束后输出
+fn watch_limits(
+    enforcer: Arc<Enforcer>,
+    terminal_id: &str,
+    pid: u32,
+    finished: mpsc::Receiver<()>,
+) -> thread::JoinHandle<Option<ExitStatus>> {
+    let terminal_id = terminal_id.to_string();
+    let started = Instant::now();
+    thread::spawn(move || loop {
+        if finished.recv_timeout(LIMIT_POLL_INTERVAL) != Err(RecvTimeoutError::Timeout) {
+            return None;
+        }
+        if enforcer.check(started.elapsed()).is_none() {
+            continue;
+        }
+        let running = session::with_existing(&terminal_id, |s| match &s.running {
+            Some(child) if child.id() == pid => s.running.take(),
+            _ => None,
+        });
+        if let Ok(Some(mut child)) = running {
+            enforcer.kill_all();
+            return shutdown::kill_tree(&mut child).ok();
+        }
+        return None;
+    })
+}
+
+// 定期发送命令进程树的资源占用、新开的监听端口和标签页标题；done 被丢弃后最后采样一次并返回汇总
+fn monitor_process(
+    sink: Arc<dyn OutputSink>,
+    terminal_id: &str,
+    pid: u32,
+    done: mpsc::Receiver<()>,
+) -> thread::JoinHandle<Option<StatsSummary>> {
+    let terminal_id = terminal_id.to_string();
+    let mut sampler = Sampler::new(&terminal_id, pid);
+    let mut ports = PortWatcher::new(&terminal_id);
+    thread::spawn(move || loop {
+        if done.recv_timeout(MONITOR_INTERVAL) != Err(RecvTimeoutError::Timeout) {
+            sampler.sample();
+            return sampler.summary();
+        }
+        let tree = procfs::tree(pid);
+        if tree.is_empty() {
+            continue;
+        }
+        sink.send(TerminalEvent::Stats(sampler.record(&tree, Instant::now())));
+        for port in ports.poll(&tree) {
+            sink.send(TerminalEvent::PortOpened(port));
+        }
+        let _ = title::refresh(sink.as_ref(), &terminal_id, Some(&tree));
+    })
+}
+
+fn interrupted_status() -> ExitStatus {
+    #[cfg(unix)]
+    {
+        ExitStatusExt::from_raw(1)
+    }
+    #[cfg(windows)]
+    {
+        ExitStatusExt::from_raw(1 << 8)
+    }
+}
+
+fn spawn_reader(
+    mut reader: Box<dyn Read + Send>,
+    kind: StreamKind,
+    sink: &Arc<dyn OutputSink>,
+    terminal_id: &str,
+    current_dir: &str,
+    command: &str,
+    enforcer: Option<Arc<Enforcer>>,
+) -> thread::JoinHandle<()> {
+    let sink = Arc::clone(sink);
+    let terminal_id = terminal_id.to_string();
+    let current_dir = current_dir.to_string();
+    let mut decoder = LineDecoder::new(kind, command);
+
+    thread::spawn(move || {
+        let mut buffer = [0u8; 1024];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => break, // EOF
+                Ok(n) => {
+                    // 超过输出限制后继续读取，避免子进程阻塞在写管道上，但不再显示
+                    if !enforcer.as_ref().map_or(true, |e| e.add_output(n)) {
+                        continue;
+                    }
+                    for (content, replace) in decoder.feed(&buffer[..n]) {
+                        emit_output(sink.as_ref(), &terminal_id, &current_dir, kind, content, replace);
+                    }
+                    if let Some(program_title) = decoder.take_title() {
+                        let _ = title::set_program_title(sink.as_ref(), &terminal_id, Some(program_title));
+                    }
+                }
+                Err(e) => {
+                    emit_output(
+                        sink.as_ref(),
+                        &terminal_id,
+                        &current_dir,
+                        StreamKind::Stderr,
+                        format!("Error reading {}: {}", kind.as_str(), e),
+                        false,
+                    );
+                    break;
+                }
+            }
+        }
+    })
+}
+
+// 解析 cd 的目标目录
+pub fn resolve_directory(current_dir: &Path, new_dir: &str) -> PathBuf {
+    if new_dir == "~" {
+        PathBuf::from(env::var("HOME").unwrap_or_default())
+    } else if let Some(without_tilde) = new_dir.strip_prefix('~') {
+        let home = env::var("HOME").unwrap_or_default();
+        if without_tilde.is_empty() {
+            PathBuf::from(home)
+        } else {
+            PathBuf::from(home).join(&without_tilde[1..])
+        }
+    } else if new_dir.starts_with('/') {
+        PathBuf::from(new_dir)
+    } else {
+        // 处理相对路径，包括 .. 和 .
+        let new_path = current_dir.join(new_dir);
+        if let Ok(canonicalized) = new_path.canonicalize() {
+            canonicalized
+        } else {
+            new_path
+        }
+    }
+}
+
+pub fn change_directory(sink: &dyn OutputSink, terminal_id: &str, command: &str) -> TerminalResult<()> {
+    cd(sink, terminal_id, command).map(|_| ())
+}
+
+// 返回目录是否切换成功
+fn cd(sink: &dyn OutputSink, terminal_id: &str, command: &str) -> TerminalResult<bool> {
+    let parts: Vec<&str> = command.trim().splitn(2, ' ').collect();
+    let new_dir = parts.get(1).map(|s| s.trim()).unwrap_or("~");
+
+    let changed = session::with_existing(terminal_id, |session| {
+        let new_path = resolve_directory(&session.current_dir, new_dir);
+        let changed = new_path.exists() && new_path.is_dir();
+        if changed {
+            session.current_dir = new_path;
+        }
+        (changed, format_current_dir(&session.current_dir))
+    })?;
+
+    match changed {
+        (true, current_dir_str) => {
+            emit_output(sink, terminal_id, &current_dir_str, StreamKind::Stdout, String::new(), false);
+            title::refresh(sink, terminal_id, None)?;
+            Ok(true)
+        }
+        (false, current_dir_str) => {
+            let error_msg = format!("cd: no such directory: {}", new_dir);
+            emit_output(sink, terminal_id, &current_dir_str, StreamKind::Stderr, error_msg, false);
+            Ok(false)
+        }
+    }
+}
+
+// 停止终端里正在运行的命令
+pub fn stop(sink: &dyn OutputSink, terminal_id: &str) -> TerminalResult<()> {
+    let handle = session::get(terminal_id)?;
+    let (running, current_dir) = {
+        let mut session = session::lock(terminal_id, &handle)?;
+        (session.running.take(), session.current_dir.clone())
+    };
+    if let Some(mut child) = running {
+        shutdown::kill_tree(&mut child).map_err(|e| kill_error(terminal_id, e))?;
+
+        // 发送用户停止的消息
+        let current_dir_str = format_current_dir(&current_dir);
+        emit_output(
+            sink,
+            terminal_id,
+            &current_dir_str,
+            StreamKind::Stderr,
+            "User has stopped the command".to_string(),
+            false,
+        );
+    }
+    Ok(())
+}
+
+// kill 已经退出的子进程时 std 返回 InvalidInput
+pub(crate) fn kill_error(terminal_id: &str, err: io::Error) -> TerminalError {
+    if err.kind() == io::ErrorKind::InvalidInput {
+        TerminalError::ProcessExited(terminal_id.to_string())
+    } else {
+        TerminalError::from(err)
+    }
+}
@@ -0,0 +1,118 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::io;
+
+use super::policy::PolicyViolation;
+
+// 终端后端统一的错误类型，序列化为 { code, message } 供前端判断
+#[derive(Debug)]
+pub enum TerminalError {
+    SpawnFailed { command: String, source: io::Error },
+    ShellNotFound(String),
+    TerminalNotFound(String),
+    ProcessExited(String),
+    PermissionDenied(String),
+    SessionPoisoned(String),
+    NotSupported(String),
+    CommandRunning(String),
+    Daemon(String),
+    // 命令违反了终端的策略，没有运行
+    PolicyViolation(PolicyViolation),
+    Io(io::Error),
+}
+
+impl TerminalError {
+    // 稳定的错误码，前端依赖它而不是错误信息文本
+    pub fn code(&self) -> &'static str {
+        match self {
+            TerminalError::SpawnFailed { .. } => "spawn_failed",
+            TerminalError::ShellNotFound(_) => "shell_not_found",
+            TerminalError::TerminalNotFound(_) => "terminal_not_found",
+            TerminalError::ProcessExited(_) => "process_exited",
+            TerminalError::PermissionDenied(_) => "permission_denied",
+            TerminalError::SessionPoisoned(_) => "session_poisoned",
+            TerminalError::NotSupported(_) => "not_supported",
+            TerminalError::CommandRunning(_) => "command_running",
+            TerminalError::Daemon(_) => "daemon_error",
+            TerminalError::PolicyViolation(_) => "policy_violation",
+            TerminalError::Io(_) => "io_error",
+        }
+    }
+
+    // 根据 spawn 失败的原因归类错误
+    pub fn spawn(command: &str, err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => TerminalError::ShellNotFound(command.to_string()),
+            io::ErrorKind::PermissionDenied => TerminalError::PermissionDenied(command.to_string()),
+            _ => TerminalError::SpawnFailed {
+                command: command.to_string(),
+                source: err,
+            },
+        }
+    }
+}
+
+impl fmt::Display for TerminalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TerminalError::SpawnFailed { command, source } => {
+                write!(f, "failed to spawn {}: {}", command, source)
+            }
+            TerminalError::ShellNotFound(shell) => write!(f, "shell not found: {}", shell),
+            TerminalError::TerminalNotFound(id) => write!(f, "terminal not found: {}", id),
+            TerminalError::ProcessExited(id) => {
+                write!(f, "process in terminal {} has already exited", id)
+            }
+            TerminalError::PermissionDenied(what) => write!(f, "permission denied: {}", what),
+            TerminalError::SessionPoisoned(id) => write!(
+                f,
+                "terminal {} hit an internal error and was reset; please retry",
+                id
+            ),
+            TerminalError::NotSupported(what) => write!(f, "not supported: {}", what),
+            TerminalError::CommandRunning(id) => {
+                write!(f, "terminal {} is already running a command", id)
+            }
+            TerminalError::Daemon(message) => write!(f, "session daemon: {}", message),
+            TerminalError::PolicyViolation(violation) => write!(f, "blocked by policy: {}", violation),
+            TerminalError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TerminalError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TerminalError::SpawnFailed { source, .. } => Some(source),
+            TerminalError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TerminalError {
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::PermissionDenied {
+            TerminalError::PermissionDenied(err.to_string())
+        } else {
+            TerminalError::Io(err)
+        }
+    }
+}
+
+impl Serialize for TerminalError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("TerminalError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        // 违反策略时附上具体的规则，前端可以标出命令中被拒绝的部分
+        match self {
+            TerminalError::PolicyViolation(violation) => state.serialize_field("violation", violation)?,
+            _ => state.skip_field("violation")?,
+        }
+        state.end()
+    }
+}
+
+pub type TerminalResult<T> = Result<T, TerminalError>;
@@ -0,0 +1,531 @@
+// 危险命令拦截：执行前分析命令结构，按内置规则和用户规则（app data 目录下的 guard.json）
+// 判断风险。命中规则的命令不会直接运行，而是把原因返回给前端，用户确认后再执行。
+//
+// guard.json 示例：
+//   { "enabled": true,
+//     "rules": [ { "pattern": "^terraform destroy", "reason": "destroys infrastructure", "risk": "high" },
+//                { "pattern": "git push --force origin wip/", "action": "allow" } ] }
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::error::{TerminalError, TerminalResult};
+use super::policy;
+use super::session::{self, lock_recover};
+use super::syntax::{self, CommandNode, Pipeline, Statement};
+
+const CONFIG_FILE: &str = "guard.json";
+
+// 删除或修改这些目录会破坏系统
+const SYSTEM_DIRS: &[&str] = &[
+    "/bin", "/boot", "/dev", "/etc", "/home", "/lib", "/lib64", "/opt", "/proc", "/root", "/sbin", "/sys", "/usr",
+    "/var", "/Applications", "/Library", "/System", "/Users",
+];
+const BLOCK_DEVICES: &[&str] = &["/dev/sd", "/dev/hd", "/dev/vd", "/dev/nvme", "/dev/mmcblk", "/dev/disk"];
+const DOWNLOADERS: &[&str] = &["curl", "wget", "fetch"];
+const INTERPRETERS: &[&str] = &[
+    "sh", "bash", "zsh", "dash", "ksh", "fish", "python", "python3", "perl", "ruby", "node",
+];
+const PROTECTED_BRANCHES: &[&str] = &["main", "master"];
+// sh -c、eval 里的命令最多递归检查几层
+const MAX_NESTING: usize = 4;
+
+static FORK_BOMB_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r":\s*\(\s*\)\s*\{\s*:\s*\|\s*:\s*&\s*\}\s*;\s*:").unwrap());
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Risk {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    // 需要用户确认
+    Confirm,
+    // 始终拒绝执行
+    Block,
+    // 命中时跳过所有检查
+    Allow,
+}
+
+fn default_risk() -> Risk {
+    Risk::High
+}
+
+fn default_action() -> RuleAction {
+    RuleAction::Confirm
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UserRule {
+    // 正则表达式，匹配整条命令
+    pub pattern: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default = "default_risk")]
+    pub risk: Risk,
+    #[serde(default = "default_action")]
+    pub action: RuleAction,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GuardConfig {
+    pub enabled: bool,
+    pub rules: Vec<UserRule>,
+}
+
+impl Default for GuardConfig {
+    fn default() -> Self {
+        GuardConfig {
+            enabled: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+// 命中的规则；[start, end) 为命令中相关部分的字符位置
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GuardMatch {
+    pub rule: String,
+    pub risk: Risk,
+    pub reason: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GuardVerdict {
+    // 所有命中规则中最高的风险
+    pub risk: Risk,
+    pub matches: Vec<GuardMatch>,
+    // 命中了 block 规则，确认也不能执行
+    pub blocked: bool,
+}
+
+impl GuardVerdict {
+    pub fn reason(&self) -> String {
+        self.matches.iter().map(|m| m.reason.as_str()).collect::<Vec<_>>().join("; ")
+    }
+}
+
+// 编译好的用户规则，配置文件读取时编译一次
+struct CompiledRule {
+    rule: UserRule,
+    re: Regex,
+}
+
+#[derive(Default)]
+struct Loaded {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    config: GuardConfig,
+    rules: Arc<Vec<CompiledRule>>,
+}
+
+static CONFIG: Lazy<Mutex<Loaded>> = Lazy::new(|| Mutex::new(Loaded::default()));
+
+pub fn init(data_dir: Option<PathBuf>) {
+    let mut loaded = lock_recover(&CONFIG);
+    loaded.path = data_dir.map(|dir| dir.join(CONFIG_FILE));
+    loaded.modified = None;
+    reload(&mut loaded);
+}
+
+// 配置文件被修改后重新读取，不需要重启应用
+fn reload(loaded: &mut Loaded) {
+    let path = match &loaded.path {
+        Some(path) => path,
+        None => return,
+    };
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    if modified.is_some() && modified == loaded.modified {
+        return;
+    }
+    loaded.config = match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Invalid guard config {}: {}", path.display(), e);
+            GuardConfig::default()
+        }),
+        Err(_) => GuardConfig::default(),
+    };
+    loaded.rules = Arc::new(compile(&loaded.config.rules));
+    loaded.modified = modified;
+}
+
+fn compile(rules: &[UserRule]) -> Vec<CompiledRule> {
+    rules
+        .iter()
+        .filter_map(|rule| match Regex::new(&rule.pattern) {
+            Ok(re) => Some(CompiledRule { rule: rule.clone(), re }),
+            Err(e) => {
+                eprintln!("Invalid guard rule pattern {:?}: {}", rule.pattern, e);
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn config() -> GuardConfig {
+    let mut loaded = lock_recover(&CONFIG);
+    reload(&mut loaded);
+    loaded.config.clone()
+}
+
+// 使用当前配置检查命令，没有风险时返回 None
+pub fn assess(command: &str) -> Option<GuardVerdict> {
+    let (enabled, rules) = {
+        let mut loaded = lock_recover(&CONFIG);
+        reload(&mut loaded);
+        (loaded.config.enabled, Arc::clone(&loaded.rules))
+    };
+    assess_rules(command, enabled, &rules)
+}
+
+// 所有运行命令的入口（前端、控制接口、启动命令）共用的检查：命中 block 规则时返回错误；
+// 需要确认而调用方没有确认时返回判定，命令不能运行；返回 None 表示可以运行。
+// 调用方把 confirmed 设为 true 就是明确跳过确认，block 规则仍然生效
+pub fn check(command: &str, confirmed: bool) -> TerminalResult<Option<GuardVerdict>> {
+    decide(assess(command), confirmed)
+}
+
+pub fn check_with(command: &str, confirmed: bool, config: &GuardConfig) -> TerminalResult<Option<GuardVerdict>> {
+    decide(assess_with(command, config), confirmed)
+}
+
+fn decide(verdict: Option<GuardVerdict>, confirmed: bool) -> TerminalResult<Option<GuardVerdict>> {
+    match verdict {
+        Some(verdict) if verdict.blocked => Err(TerminalError::PermissionDenied(format!(
+            "blocked by guard rule: {}",
+            verdict.reason()
+        ))),
+        Some(verdict) if !confirmed => Ok(Some(verdict)),
+        _ => Ok(None),
+    }
+}
+
+fn char_offset(text: &str, byte: usize) -> usize {
+    text[..byte].chars().count()
+}
+
+pub fn assess_with(command: &str, config: &GuardConfig) -> Option<GuardVerdict> {
+    assess_rules(command, config.enabled, &compile(&config.rules))
+}
+
+fn assess_rules(command: &str, enabled: bool, rules: &[CompiledRule]) -> Option<GuardVerdict> {
+    if !enabled {
+        return None;
+    }
+
+    let mut matches = Vec::new();
+    let mut blocked = false;
+    for CompiledRule { rule, re } in rules {
+        let found = match re.find(command) {
+            Some(found) => found,
+            None => continue,
+        };
+        match rule.action {
+            RuleAction::Allow => return None,
+            RuleAction::Block => blocked = true,
+            RuleAction::Confirm => {}
+        }
+        matches.push(GuardMatch {
+            rule: "user".to_string(),
+            risk: rule.risk,
+            reason: rule
+                .reason
+                .clone()
+                .unwrap_or_else(|| format!("matches rule `{}`", rule.pattern)),
+            start: char_offset(command, found.start()),
+            end: char_offset(command, found.end()),
+        });
+    }
+
+    let (statements, _) = syntax::parse(command);
+    builtin_checks(&statements, &mut matches, 0);
+    if let Some(found) = FORK_BOMB_RE.find(command) {
+        matches.push(GuardMatch {
+            rule: "fork-bomb".to_string(),
+            risk: Risk::Critical,
+            reason: "fork bomb: spawns processes until the system hangs".to_string(),
+            start: char_offset(command, found.start()),
+            end: char_offset(command, found.end()),
+        });
+    }
+
+    let risk = matches.iter().map(|m| m.risk).max()?;
+    Some(GuardVerdict { risk, matches, blocked })
+}
+
+// depth 为 sh -c、eval 等嵌套的层数
+fn builtin_checks(statements: &[Statement], matches: &mut Vec<GuardMatch>, depth: usize) {
+    for statement in statements {
+        for pipeline in &statement.pipelines {
+            check_pipeline(pipeline, matches);
+            for node in &pipeline.commands {
+                match node {
+                    CommandNode::Simple { argv, redirections, start, end, .. } => {
+                        let mut add = |rule: &str, risk: Risk, reason: String| {
+                            matches.push(GuardMatch {
+                                rule: rule.to_string(),
+                                risk,
+                                reason,
+                                start: *start,
+                                end: *end,
+                            })
+                        };
+                        check_command(strip_wrappers(argv), &mut add);
+                        for r in redirections {
+                            if r.operator.contains('>') && is_block_device(&r.target) {
+                                add("device-write", Risk::Critical, format!("overwrites block device {}", r.target));
+                            }
+                        }
+                        check_nested(argv, *start, *end, matches, depth);
+                    }
+                    CommandNode::Subshell { body, .. } => builtin_checks(body, matches, depth),
+                }
+            }
+        }
+    }
+}
+
+// sh -c、eval、env -S 执行的字符串按完整命令检查，命中时报告外层命令的位置
+fn check_nested(argv: &[String], start: usize, end: usize, matches: &mut Vec<GuardMatch>, depth: usize) {
+    let mut stage = argv;
+    while !stage.is_empty() {
+        if let Some(nested) = policy::nested_command(stage) {
+            let mut inner = Vec::new();
+            if depth >= MAX_NESTING {
+                inner.push(GuardMatch {
+                    rule: "nested-command".to_string(),
+                    risk: Risk::High,
+                    reason: "nests commands too deeply to check".to_string(),
+                    start,
+                    end,
+                });
+            } else {
+                let (statements, _) = syntax::parse(&nested);
+                builtin_checks(&statements, &mut inner, depth + 1);
+            }
+            matches.extend(inner.into_iter().map(|m| GuardMatch { start, end, ..m }));
+        }
+        match strip_wrapper(stage) {
+            Some(inner) => stage = inner,
+            None => break,
+        }
+    }
+}
+
+// 去掉 sudo、env、nohup 等只是包装另一条命令的前缀
+pub(crate) fn strip_wrappers(argv: &[String]) -> &[String] {
+    let mut rest = argv;
+    while let Some(inner) = strip_wrapper(rest) {
+        rest = inner;
+    }
+    rest
+}
+
+// 去掉一层包装，返回被包装的命令；argv 不是包装命令时返回 None
+pub(crate) fn strip_wrapper(argv: &[String]) -> Option<&[String]> {
+    let name = argv.first()?.as_str();
+    let mut i = 1;
+    match name {
+        "sudo" | "doas" => {
+            while let Some(arg) = argv.get(i) {
+                if !arg.starts_with('-') {
+                    break;
+                }
+                // 带参数的选项，例如 sudo -u root
+                i += if matches!(arg.as_str(), "-u" | "-g" | "-C" | "-h" | "-p") { 2 } else { 1 };
+            }
+        }
+        "env" => {
+            while let Some(arg) = argv.get(i) {
+                if !arg.starts_with('-') && !arg.contains('=') {
+                    break;
+                }
+                // env -u NAME、env -C DIR
+                i += if matches!(arg.as_str(), "-u" | "--unset" | "-C" | "--chdir") { 2 } else { 1 };
+            }
+        }
+        "xargs" => {
+            while let Some(arg) = argv.get(i) {
+                if !arg.starts_with('-') {
+                    break;
+                }
+                i += if matches!(arg.as_str(), "-a" | "-d" | "-E" | "-I" | "-L" | "-n" | "-P" | "-s") { 2 } else { 1 };
+            }
+        }
+        "timeout" => {
+            while argv.get(i).map(|a| a.starts_with('-')).unwrap_or(false) {
+                i += if matches!(argv[i].as_str(), "-k" | "-s" | "--kill-after" | "--signal") { 2 } else { 1 };
+            }
+            // 时长
+            i += 1;
+        }
+        "nice" => {
+            if argv.get(1).map(|a| a == "-n").unwrap_or(false) {
+                i = 3;
+            }
+        }
+        "nohup" | "time" | "command" | "exec" => {}
+        _ => return None,
+    }
+    Some(argv.get(i..).unwrap_or(&[]))
+}
+
+enum Target {
+    Root,
+    Home,
+    System,
+    CurrentDir,
+    Other,
+}
+
+fn classify(path: &str) -> Target {
+    let trimmed = path.trim_end_matches("/*").trim_end_matches("/.");
+    let trimmed = if trimmed.len() > 1 { trimmed.trim_end_matches('/') } else { trimmed };
+    let home = session::home_dir();
+    match trimmed {
+        "" | "/" => Target::Root,
+        "~" | "$HOME" | "${HOME}" => Target::Home,
+        "*" | "." | ".." | "./" => Target::CurrentDir,
+        _ if home.to_str() == Some(trimmed) => Target::Home,
+        _ if SYSTEM_DIRS.contains(&trimmed) => Target::System,
+        _ => Target::Other,
+    }
+}
+
+fn is_block_device(path: &str) -> bool {
+    BLOCK_DEVICES.iter().any(|prefix| path.starts_with(prefix))
+}
+
+fn is_recursive_flag(arg: &str) -> bool {
+    arg == "--recursive" || (arg.starts_with('-') && !arg.starts_with("--") && (arg.contains('r') || arg.contains('R')))
+}
+
+// 选项之后的参数（-- 之后全部算参数）
+fn operands(args: &[String]) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut options_done = false;
+    for arg in args {
+        if !options_done && arg == "--" {
+            options_done = true;
+        } else if options_done || !arg.starts_with('-') {
+            out.push(arg.as_str());
+        }
+    }
+    out
+}
+
+fn check_command(argv: &[String], add: &mut dyn FnMut(&str, Risk, String)) {
+    let (name, args) = match argv.split_first() {
+        Some((name, args)) => (name.as_str(), args),
+        None => return,
+    };
+    let base = name.rsplit('/').next().unwrap_or(name);
+    match base {
+        "rm" if args.iter().any(|a| is_recursive_flag(a)) => {
+            for target in operands(args) {
+                match classify(target) {
+                    Target::Root => add("rm-root", Risk::Critical, "recursively deletes the entire filesystem".to_string()),
+                    Target::Home => add("rm-home", Risk::Critical, "recursively deletes your home directory".to_string()),
+                    Target::System => add("rm-system", Risk::High, format!("recursively deletes system directory {}", target)),
+                    Target::CurrentDir => {
+                        add("rm-cwd", Risk::High, "recursively deletes everything in the current directory".to_string())
+                    }
+                    Target::Other => {}
+                }
+            }
+        }
+        "chmod" | "chown" | "chgrp" if args.iter().any(|a| a == "-R" || a == "--recursive") => {
+            for target in operands(args) {
+                if matches!(classify(target), Target::Root | Target::Home | Target::System) {
+                    add(
+                        "recursive-permissions",
+                        Risk::High,
+                        format!("recursively changes {} of {}", if base == "chmod" { "permissions" } else { "ownership" }, target),
+                    );
+                }
+            }
+        }
+        "dd" => {
+            for arg in args {
+                if let Some(device) = arg.strip_prefix("of=") {
+                    if is_block_device(device) {
+                        add("device-write", Risk::Critical, format!("writes directly to device {}", device));
+                    }
+                }
+            }
+        }
+        _ if base.starts_with("mkfs") || matches!(base, "wipefs" | "fdisk" | "sfdisk" | "parted" | "shred") => {
+            if let Some(device) = operands(args).into_iter().find(|a| is_block_device(a)) {
+                add("device-format", Risk::Critical, format!("{} modifies device {}", base, device));
+            }
+        }
+        "git" => check_git(args, add),
+        "shutdown" | "reboot" | "halt" | "poweroff" => {
+            add("power", Risk::Medium, format!("{} stops the machine", base));
+        }
+        _ => {}
+    }
+}
+
+fn check_git(args: &[String], add: &mut dyn FnMut(&str, Risk, String)) {
+    let push = match args.iter().position(|a| a == "push") {
+        Some(i) => &args[i + 1..],
+        None => return,
+    };
+    let refspecs: Vec<&str> = operands(push).into_iter().skip(1).collect();
+    let force = push.iter().any(|a| a == "-f" || a == "--force" || a.starts_with("--force-with-lease"))
+        || refspecs.iter().any(|r| r.starts_with('+'));
+    if !force {
+        return;
+    }
+    let protected = refspecs.iter().find_map(|r| {
+        let dest = r.rsplit(':').next().unwrap_or(r).trim_start_matches('+');
+        let dest = dest.trim_start_matches("refs/heads/");
+        PROTECTED_BRANCHES.iter().find(|b| **b == dest)
+    });
+    match protected {
+        Some(branch) => add("force-push", Risk::High, format!("force-pushes to protected branch {}", branch)),
+        None => add("force-push", Risk::Medium, "force-push rewrites history on the remote".to_string()),
+    }
+}
+
+// curl ... | sh：把下载的内容直接交给解释器执行
+fn check_pipeline(pipeline: &Pipeline, matches: &mut Vec<GuardMatch>) {
+    let names: Vec<&str> = pipeline
+        .commands
+        .iter()
+        .map(|node| match node {
+            CommandNode::Simple { argv, .. } => strip_wrappers(argv)
+                .first()
+                .map(|name| name.rsplit('/').next().unwrap_or(name))
+                .unwrap_or(""),
+            CommandNode::Subshell { .. } => "",
+        })
+        .collect();
+    let download = names.iter().position(|name| DOWNLOADERS.contains(name));
+    if let Some(i) = download {
+        if let Some(interpreter) = names[i + 1..].iter().find(|name| INTERPRETERS.contains(name)) {
+            matches.push(GuardMatch {
+                rule: "pipe-to-shell".to_string(),
+                risk: Risk::High,
+                reason: format!("pipes downloaded content straight into {}", interpreter),
+                start: pipeline.start,
+                end: pipeline.end,
+            });
+        }
+    }
+}
@@ -0,0 +1,268 @@
+// 命令历史：每次执行的命令追加写入 app data 目录下的 history.jsonl（每行一条记录），
+// 启动时读回内存，并按工作目录和终端建立索引，供搜索和自动建议使用。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::engine;
+#[cfg(unix)]
+use super::daemon;
+use super::error::TerminalResult;
+#[cfg(unix)]
+use super::error::TerminalError;
+#[cfg(unix)]
+use super::process::run_blocking;
+use super::session::{self, lock_recover};
+
+const HISTORY_FILE: &str = "history.jsonl";
+const DEFAULT_LIMIT: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+    pub command: String,
+    // 执行时的绝对工作目录
+    pub cwd: String,
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+    // 开始时间，Unix 毫秒
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub exit_code: Option<i32>,
+    // 从其他 shell 导入的记录（zsh/bash/fish），没有目录和退出码
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    // 导入的原始记录没有时间戳，started_at 是按文件顺序估算的
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub estimated_time: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryQuery {
+    // 以此开头的命令
+    pub prefix: Option<String>,
+    // 按字符顺序模糊匹配，结果按匹配程度排序
+    pub fuzzy: Option<String>,
+    // 只返回在这个目录下运行的命令，支持 ~
+    pub cwd: Option<String>,
+    pub terminal_id: Option<String>,
+    // 只返回失败（退出码非 0 或被中断）的命令，不包括导入的记录
+    pub failed_only: bool,
+    // 相同的命令只保留最近一条
+    pub unique: bool,
+    pub limit: Option<usize>,
+}
+
+#[derive(Default)]
+struct History {
+    path: Option<PathBuf>,
+    entries: Vec<HistoryEntry>,
+    by_dir: HashMap<String, Vec<usize>>,
+    by_terminal: HashMap<String, Vec<usize>>,
+}
+
+impl History {
+    fn push(&mut self, entry: HistoryEntry) {
+        let index = self.entries.len();
+        self.by_dir.entry(entry.cwd.clone()).or_default().push(index);
+        self.by_terminal.entry(entry.terminal_id.clone()).or_default().push(index);
+        self.entries.push(entry);
+    }
+}
+
+static HISTORY: Lazy<Mutex<History>> = Lazy::new(|| Mutex::new(History::default()));
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// 读取历史文件；没有 data 目录时历史只保存在内存里
+pub fn init(data_dir: Option<PathBuf>) {
+    let path = match data_dir {
+        Some(dir) => dir.join(HISTORY_FILE),
+        None => return,
+    };
+    let mut history = lock_recover(&HISTORY);
+    // 之前已经读过文件时，内存里的记录都已写进文件
+    let previous = if history.path.is_none() { std::mem::take(&mut history.entries) } else { Vec::new() };
+    *history = History::default();
+
+    let mut all: Vec<HistoryEntry> = match fs::read_to_string(&path) {
+        // 跳过写到一半的行
+        Ok(content) => content.lines().filter_map(|line| serde_json::from_str(line).ok()).collect(),
+        Err(_) => Vec::new(),
+    };
+    all.extend(previous);
+    // 导入的旧记录追加在文件末尾，按开始时间排好，最新的记录仍在最后
+    all.sort_by_key(|e| e.started_at);
+    for entry in all {
+        history.push(entry);
+    }
+    history.path = Some(path);
+}
+
+// 追加一条记录，写入失败时仍然保留在内存里
+pub fn record(entry: HistoryEntry) {
+    let mut history = lock_recover(&HISTORY);
+    if let Some(path) = &history.path {
+        if let Err(e) = append(path, std::slice::from_ref(&entry)) {
+            eprintln!("Failed to write history to {}: {}", path.display(), e);
+        }
+    }
+    history.push(entry);
+}
+
+// 批量追加（导入历史时使用），按开始时间插入到合适的位置
+pub fn record_all(mut entries: Vec<HistoryEntry>) {
+    if entries.is_empty() {
+        return;
+    }
+    entries.sort_by_key(|e| e.started_at);
+    let mut history = lock_recover(&HISTORY);
+    if let Some(path) = &history.path {
+        if let Err(e) = append(path, &entries) {
+            eprintln!("Failed to write history to {}: {}", path.display(), e);
+        }
+    }
+    let mut all = std::mem::take(&mut history.entries);
+    all.extend(entries);
+    all.sort_by_key(|e| e.started_at);
+    let path = history.path.take();
+    *history = History::default();
+    history.path = path;
+    for entry in all {
+        history.push(entry);
+    }
+}
+
+fn append(path: &Path, entries: &[HistoryEntry]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut buffer = String::new();
+    for entry in entries {
+        if let Ok(line) = serde_json::to_string(entry) {
+            buffer.push_str(&line);
+            buffer.push('\n');
+        }
+    }
+    file.write_all(buffer.as_bytes())
+}
+
+pub fn entries() -> Vec<HistoryEntry> {
+    lock_recover(&HISTORY).entries.clone()
+}
+
+// 不复制地读取全部记录（从旧到新）
+pub fn with_entries<T>(f: impl FnOnce(&[HistoryEntry]) -> T) -> T {
+    f(&lock_recover(&HISTORY).entries)
+}
+
+// 在 candidate 中按顺序找到 pattern 的每个字符（忽略大小写），
+// 连续匹配和单词开头匹配得分更高，匹配不上返回 None
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for p in pattern.chars().flat_map(char::to_lowercase) {
+        let found = (position..candidate.len())
+            .find(|&i| candidate[i].to_lowercase().eq(std::iter::once(p)))?;
+        score += 1;
+        if previous.map(|prev| prev + 1 == found).unwrap_or(false) {
+            score += 5;
+        }
+        if found == 0 || matches!(candidate[found - 1], ' ' | '/' | '-' | '_' | '.') {
+            score += 3;
+        }
+        // 分散的匹配扣分
+        score -= (found - position) as i64;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+pub fn query(query: &HistoryQuery) -> Vec<HistoryEntry> {
+    let history = lock_recover(&HISTORY);
+    let cwd = query
+        .cwd
+        .as_deref()
+        .map(|dir| engine::resolve_directory(&session::home_dir(), dir).to_string_lossy().into_owned());
+
+    // 有目录或终端条件时只遍历对应的索引
+    let indices: Vec<usize> = match (&cwd, &query.terminal_id) {
+        (Some(dir), _) => history.by_dir.get(dir).cloned().unwrap_or_default(),
+        (None, Some(id)) => history.by_terminal.get(id).cloned().unwrap_or_default(),
+        (None, None) => (0..history.entries.len()).collect(),
+    };
+
+    let mut seen = std::collections::HashSet::new();
+    let mut matches: Vec<(i64, &HistoryEntry)> = Vec::new();
+    for &index in indices.iter().rev() {
+        let entry = &history.entries[index];
+        if let Some(id) = &query.terminal_id {
+            if &entry.terminal_id != id {
+                continue;
+            }
+        }
+        if let Some(prefix) = &query.prefix {
+            if !entry.command.starts_with(prefix.as_str()) {
+                continue;
+            }
+        }
+        if query.failed_only && (entry.exit_code == Some(0) || entry.source.is_some()) {
+            continue;
+        }
+        let score = match &query.fuzzy {
+            Some(pattern) => match fuzzy_score(pattern, &entry.command) {
+                Some(score) => score,
+                None => continue,
+            },
+            None => 0,
+        };
+        if query.unique && !seen.insert(entry.command.as_str()) {
+            continue;
+        }
+        matches.push((score, entry));
+    }
+
+    // 稳定排序：得分相同时保持从新到旧
+    if query.fuzzy.is_some() {
+        matches.sort_by_key(|m| std::cmp::Reverse(m.0));
+    }
+    matches
+        .into_iter()
+        .take(query.limit.unwrap_or(DEFAULT_LIMIT))
+        .map(|(_, entry)| entry.clone())
+        .collect()
+}
+
+// 连接了守护进程时，新的命令记录在守护进程的历史里
+#[tauri::command]
+pub async fn search_history(query: HistoryQuery) -> TerminalResult<Vec<HistoryEntry>> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        let params = serde_json::json!({ "query": query });
+        return run_blocking(move || {
+            let value = client.call("search_history", params)?;
+            serde_json::from_value(value).map_err(|e| TerminalError::Daemon(e.to_string()))
+        })
+        .await;
+    }
+    Ok(self::query(&query))
+}
@@ -0,0 +1,276 @@
+// 从 zsh、bash 和 fish 的历史文件导入命令，保留原始时间戳，去重后写入历史记录。
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use super::error::TerminalResult;
+use super::history::{self, HistoryEntry};
+use super::session;
+
+// zsh 用来转义特殊字节的前缀
+const ZSH_META: u8 = 0x83;
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct ImportReport {
+    // 每种 shell 导入的条数
+    pub imported: BTreeMap<String, usize>,
+    pub duplicates: usize,
+}
+
+fn imported(shell: &str, command: String, started_at: u64, duration_ms: u64) -> HistoryEntry {
+    HistoryEntry {
+        command,
+        cwd: String::new(),
+        terminal_id: String::new(),
+        started_at,
+        duration_ms,
+        exit_code: None,
+        source: Some(shell.to_string()),
+        estimated_time: false,
+    }
+}
+
+// 原始记录没有时间戳，时间由调用方给出
+fn estimated(shell: &str, command: String, started_at: u64) -> HistoryEntry {
+    HistoryEntry {
+        estimated_time: true,
+        ..imported(shell, command, started_at, 0)
+    }
+}
+
+// 还原 zsh 的 metafied 字节：Meta 之后的字节与 32 异或
+fn unmetafy(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter();
+    while let Some(&b) = iter.next() {
+        if b == ZSH_META {
+            if let Some(&next) = iter.next() {
+                out.push(next ^ 32);
+            }
+        } else {
+            out.push(b);
+        }
+    }
+    out
+}
+
+// 解析 ~/.zsh_history，支持 EXTENDED_HISTORY 的 `: 开始时间:耗时;命令` 格式
+// 以及以反斜杠结尾的多行命令。没有时间戳的命令使用 fallback_time。
+pub fn parse_zsh(bytes: &[u8], fallback_time: u64) -> Vec<HistoryEntry> {
+    let text = String::from_utf8_lossy(&unmetafy(bytes)).into_owned();
+    let mut entries = Vec::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let mut record = line.to_string();
+        while record.ends_with('\\') {
+            record.pop();
+            match lines.next() {
+                Some(next) => {
+                    record.push('\n');
+                    record.push_str(next);
+                }
+                None => break,
+            }
+        }
+
+        let entry = match parse_zsh_extended(&record) {
+            Some((ts, duration, command)) => imported("zsh", command.to_string(), ts * 1000, duration * 1000),
+            None => estimated("zsh", record, fallback_time),
+        };
+        if !entry.command.trim().is_empty() {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+fn parse_zsh_extended(record: &str) -> Option<(u64, u64, &str)> {
+    let rest = record.strip_prefix(": ")?;
+    let (meta, command) = rest.split_once(';')?;
+    let (ts, duration) = meta.split_once(':')?;
+    Some((ts.trim().parse().ok()?, duration.trim().parse().unwrap_or(0), command))
+}
+
+// 解析 ~/.bash_history；设置了 HISTTIMEFORMAT 时每条命令前有一行 `#时间戳`
+pub fn parse_bash(text: &str, fallback_time: u64) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut timestamp = None;
+    for line in text.lines() {
+        if let Some(ts) = line.strip_prefix('#').and_then(|ts| ts.trim().parse::<u64>().ok()) {
+            timestamp = Some(ts * 1000);
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(match timestamp.take() {
+            Some(started_at) => imported("bash", line.to_string(), started_at, 0),
+            None => estimated("bash", line.to_string(), fallback_time),
+        });
+    }
+    entries
+}
+
+// 解析 fish_history 的类 YAML 格式：
+//   - cmd: git status
+//     when: 1600000000
+//     paths:
+//       - src
+pub fn parse_fish(text: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut command: Option<String> = None;
+    let mut when = 0;
+    for line in text.lines() {
+        if let Some(cmd) = line.strip_prefix("- cmd: ") {
+            if let Some(command) = command.take() {
+                entries.push(imported("fish", command, when, 0));
+            }
+            command = Some(unescape_fish(cmd));
+            when = 0;
+        } else if let Some(ts) = line.trim_start().strip_prefix("when: ") {
+            when = ts.trim().parse::<u64>().map(|ts| ts * 1000).unwrap_or(0);
+        }
+    }
+    if let Some(command) = command {
+        entries.push(imported("fish", command, when, 0));
+    }
+    entries
+}
+
+// fish 把换行写成 \n，反斜杠写成 \\
+fn unescape_fish(cmd: &str) -> String {
+    let mut out = String::with_capacity(cmd.len());
+    let mut chars = cmd.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+// 去掉重复的命令：同一时间戳的同一命令只保留一条，已经在历史里的也跳过。
+// 没有时间戳的记录时间是估算的，每次导入都不一样，改为按来源和命令计数：
+// 历史里已有 n 条时跳过前 n 次出现，重复导入同一个文件不会产生新条目。
+// 返回保留的条目和重复的数量。
+pub fn dedupe(entries: Vec<HistoryEntry>, existing: &[HistoryEntry]) -> (Vec<HistoryEntry>, usize) {
+    let key = |e: &HistoryEntry| (e.source.clone(), e.command.clone());
+    let mut seen: HashSet<(String, u64)> = HashSet::new();
+    let mut known: HashMap<(Option<String>, String), usize> = HashMap::new();
+    for e in existing {
+        if e.estimated_time {
+            *known.entry(key(e)).or_default() += 1;
+        } else {
+            seen.insert((e.command.clone(), e.started_at / 1000));
+        }
+    }
+    let mut occurrences: HashMap<(Option<String>, String), usize> = HashMap::new();
+    let total = entries.len();
+    let kept: Vec<HistoryEntry> = entries
+        .into_iter()
+        .filter(|e| {
+            if !e.estimated_time {
+                return seen.insert((e.command.clone(), e.started_at / 1000));
+            }
+            let key = key(e);
+            let count = occurrences.entry(key.clone()).or_default();
+            *count += 1;
+            *count > known.get(&key).copied().unwrap_or(0)
+        })
+        .collect();
+    let duplicates = total - kept.len();
+    (kept, duplicates)
+}
+
+fn history_files() -> Vec<(&'static str, PathBuf)> {
+    let home = session::home_dir();
+    let zdotdir = env::var_os("ZDOTDIR").map(PathBuf::from).unwrap_or_else(|| home.clone());
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".local/share"));
+    vec![
+        ("zsh", zdotdir.join(".zsh_history")),
+        ("bash", home.join(".bash_history")),
+        ("fish", data_home.join("fish/fish_history")),
+    ]
+}
+
+// 没有时间戳的命令按文件顺序排在文件修改时间之前
+fn with_fallback_order(mut entries: Vec<HistoryEntry>, mtime: u64) -> Vec<HistoryEntry> {
+    let count = entries.len() as u64;
+    for (i, entry) in entries.iter_mut().enumerate() {
+        if entry.estimated_time {
+            entry.started_at = mtime.saturating_sub(count - i as u64);
+        }
+    }
+    entries
+}
+
+pub fn import(shells: Option<&[String]>) -> ImportReport {
+    let mut report = ImportReport::default();
+    let mut all = Vec::new();
+    for (shell, path) in history_files() {
+        if let Some(shells) = shells {
+            if !shells.iter().any(|s| s == shell) {
+                continue;
+            }
+        }
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(_) => continue,
+        };
+        let mtime = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_else(history::now_millis);
+
+        let entries = match shell {
+            "zsh" => parse_zsh(&bytes, u64::MAX),
+            "bash" => parse_bash(&String::from_utf8_lossy(&bytes), u64::MAX),
+            _ => parse_fish(&String::from_utf8_lossy(&bytes)),
+        };
+        all.extend(with_fallback_order(entries, mtime));
+    }
+
+    let (kept, duplicates) = dedupe(all, &history::entries());
+    for entry in &kept {
+        if let Some(source) = &entry.source {
+            *report.imported.entry(source.clone()).or_default() += 1;
+        }
+    }
+    report.duplicates = duplicates;
+    history::record_all(kept);
+    report
+}
+
+// 历史为空（第一次启动）时自动导入
+pub fn import_if_empty() {
+    if history::entries().is_empty() {
+        let report = import(None);
+        if !report.imported.is_empty() {
+            println!("Imported shell history: {:?}", report.imported);
+        }
+    }
+}
+
+// shells 为空时导入所有能找到的历史文件
+#[tauri::command]
+pub async fn import_shell_history(shells: Option<Vec<String>>) -> TerminalResult<ImportReport> {
+    super::process::run_blocking(move || Ok(import(shells.as_deref()))).await
+}
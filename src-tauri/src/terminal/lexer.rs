@@ -0,0 +1,232 @@
+// 按 shell 规则把命令行切分成单词和操作符，处理引号、反斜杠转义和注释。
+// 位置都以字符（不是字节）计算，方便和前端输入框的光标位置对应。
+
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+    Word,
+    Operator,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Token {
+    pub kind: TokenKind,
+    // 去掉引号和转义之后的内容；操作符为其原文
+    pub value: String,
+    // 在原始输入中的字符位置 [start, end)
+    pub start: usize,
+    pub end: usize,
+    // 输入结束时仍未闭合的引号
+    pub open_quote: Option<char>,
+    // 是否包含引号或转义
+    pub quoted: bool,
+}
+
+impl Token {
+    pub fn is_operator(&self, op: &str) -> bool {
+        self.kind == TokenKind::Operator && self.value == op
+    }
+}
+
+// 按最长匹配排列
+const OPERATORS: &[&str] = &[
+    "<<<", "<<-", "&&", "||", ";;", ">>", "<<", ">&", "<&", "&>", "|&", "|", "&", ";", "(", ")", "<", ">", "\n",
+];
+
+// 单词中以 $ 开头的变量名
+pub fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    tokens: Vec<Token>,
+    word: Option<Token>,
+}
+
+impl Lexer {
+    fn word(&mut self) -> &mut Token {
+        let pos = self.pos;
+        self.word.get_or_insert_with(|| Token {
+            kind: TokenKind::Word,
+            value: String::new(),
+            start: pos,
+            end: pos,
+            open_quote: None,
+            quoted: false,
+        })
+    }
+
+    fn finish_word(&mut self) {
+        if let Some(mut word) = self.word.take() {
+            word.end = self.pos;
+            self.tokens.push(word);
+        }
+    }
+
+    fn operator_at(&self) -> Option<&'static str> {
+        OPERATORS.iter().copied().find(|op| {
+            op.chars()
+                .enumerate()
+                .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+        })
+    }
+
+    fn run(mut self) -> Vec<Token> {
+        while self.pos < self.chars.len() {
+            let c = self.chars[self.pos];
+            match c {
+                ' ' | '\t' => {
+                    self.finish_word();
+                    self.pos += 1;
+                }
+                '#' if self.word.is_none() => {
+                    // 注释一直到行尾
+                    while self.pos < self.chars.len() && self.chars[self.pos] != '\n' {
+                        self.pos += 1;
+                    }
+                }
+                '\\' => {
+                    self.word().quoted = true;
+                    match self.chars.get(self.pos + 1).copied() {
+                        // 行尾续行
+                        Some('\n') => {}
+                        Some(next) => self.word().value.push(next),
+                        None => self.word().value.push('\\'),
+                    }
+                    self.pos += 2;
+                }
+                '\'' => {
+                    self.word().quoted = true;
+                    self.pos += 1;
+                    match self.chars[self.pos..].iter().position(|&c| c == '\'') {
+                        Some(len) => {
+                            let text: String = self.chars[self.pos..self.pos + len].iter().collect();
+                            self.word().value.push_str(&text);
+                            self.pos += len + 1;
+                        }
+                        None => {
+                            let text: String = self.chars[self.pos..].iter().collect();
+                            let word = self.word();
+                            word.value.push_str(&text);
+                            word.open_quote = Some('\'');
+                            self.pos = self.chars.len();
+                        }
+                    }
+                }
+                '"' => {
+                    self.word().quoted = true;
+                    self.pos += 1;
+                    let mut closed = false;
+                    while self.pos < self.chars.len() {
+                        let c = self.chars[self.pos];
+                        if c == '"' {
+                            closed = true;
+                            self.pos += 1;
+                            break;
+                        }
+                        // 双引号里只有这几个字符可以被转义
+                        if c == '\\' {
+                            if let Some(&next) = self.chars.get(self.pos + 1) {
+                                if matches!(next, '"' | '\\' | '$' | '`') {
+                                    self.word().value.push(next);
+                                    self.pos += 2;
+                                    continue;
+                                }
+                                if next == '\n' {
+                                    self.pos += 2;
+                                    continue;
+                                }
+                            }
+                        }
+                        self.word().value.push(c);
+                        self.pos += 1;
+                    }
+                    if !closed {
+                        self.word().open_quote = Some('"');
+                    }
+                }
+                _ => match self.operator_at() {
+                    Some(op) => {
+                        // 紧挨着重定向的数字是文件描述符，例如 2>
+                        let fd = match &self.word {
+                            Some(w)
+                                if (op.starts_with('>') || op.starts_with('<'))
+                                    && !w.quoted
+                                    && w.value.chars().all(|c| c.is_ascii_digit()) =>
+                            {
+                                self.word.take()
+                            }
+                            _ => None,
+                        };
+                        self.finish_word();
+                        let start = fd.as_ref().map(|w| w.start).unwrap_or(self.pos);
+                        let value = match fd {
+                            Some(w) => format!("{}{}", w.value, op),
+                            None => op.to_string(),
+                        };
+                        self.pos += op.chars().count();
+                        self.tokens.push(Token {
+                            kind: TokenKind::Operator,
+                            value,
+                            start,
+                            end: self.pos,
+                            open_quote: None,
+                            quoted: false,
+                        });
+                    }
+                    None => {
+                        self.word().value.push(c);
+                        self.pos += 1;
+                    }
+                },
+            }
+        }
+        self.pos = self.chars.len();
+        self.finish_word();
+        self.tokens
+    }
+}
+
+pub fn tokenize(line: &str) -> Vec<Token> {
+    Lexer {
+        chars: line.chars().collect(),
+        pos: 0,
+        tokens: Vec::new(),
+        word: None,
+    }
+    .run()
+}
+
+// 分隔命令的操作符：之后的第一个单词是命令名
+pub fn is_command_separator(token: &Token) -> bool {
+    token.kind == TokenKind::Operator
+        && matches!(token.value.as_str(), "&&" | "||" | "|" | "|&" | "&" | ";" | ";;" | "(" | ")" | "\n")
+}
+
+// 形如 NAME=value 的环境变量赋值
+pub fn is_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(is_name_char)
+        }
+        None => false,
+    }
+}
+
+// 按需要给单词加上反斜杠转义，使其在 shell 中保持原样
+pub fn escape(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    for c in word.chars() {
+        if c.is_whitespace() || "\\'\"`$&|;<>()[]{}*?!#~".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
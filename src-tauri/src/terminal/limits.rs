@@ -0,0 +1,394 @@
+// 单条命令的资源限制：运行时间、CPU 时间、内存、打开文件数和输出字节数。
+// Linux 上能创建 cgroup v2 子组时用它统计整棵进程树的 CPU 和内存，否则退回到 rlimit
+// （只对单个进程生效）。命令因为超限被终止时，完成事件里带上超出的是哪一项。
+
+use serde::{Deserialize, Serialize};
+use std::process::{Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(unix)]
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+use super::session::lock_recover;
+
+// 没有 cgroup 时只能从输出里判断是否因为内存或文件数不足而失败
+const MEMORY_ERRORS: &[&str] = &["Cannot allocate memory", "out of memory", "MemoryError", "std::bad_alloc"];
+const OPEN_FILES_ERRORS: &[&str] = &["Too many open files"];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ResourceLimits {
+    pub timeout_secs: Option<u64>,
+    pub cpu_secs: Option<u64>,
+    pub memory_bytes: Option<u64>,
+    pub open_files: Option<u64>,
+    pub output_bytes: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        *self == ResourceLimits::default()
+    }
+
+    // 同时有多个超时设置时取较短的一个；不足一秒的部分向上取整，避免 0.5 秒变成不限时
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        if let Some(timeout) = timeout {
+            let secs = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+            self.timeout_secs = Some(self.timeout_secs.map_or(secs, |current| current.min(secs)));
+        }
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKind {
+    Timeout,
+    Cpu,
+    Memory,
+    OpenFiles,
+    Output,
+}
+
+impl LimitKind {
+    // 超限后显示在命令输出里的说明
+    pub fn message(&self, limits: &ResourceLimits) -> String {
+        let value = |v: Option<u64>| v.unwrap_or_default();
+        match self {
+            LimitKind::Timeout => {
+                format!("Command exceeded the maximum runtime of {}s and was stopped", value(limits.timeout_secs))
+            }
+            LimitKind::Cpu => format!("Command exceeded the CPU time limit of {}s", value(limits.cpu_secs)),
+            LimitKind::Memory => format!("Command exceeded the memory limit of {} bytes", value(limits.memory_bytes)),
+            LimitKind::OpenFiles => format!("Command exceeded the limit of {} open files", value(limits.open_files)),
+            LimitKind::Output => {
+                format!("Command exceeded the output limit of {} bytes and was stopped", value(limits.output_bytes))
+            }
+        }
+    }
+}
+
+// 一条命令运行期间的限制状态，由引擎、读取线程和监视线程共享
+pub struct Enforcer {
+    limits: ResourceLimits,
+    #[cfg(target_os = "linux")]
+    cgroup: Option<cgroup::Cgroup>,
+    output: AtomicU64,
+    output_exceeded: AtomicBool,
+    hit: Mutex<Option<LimitKind>>,
+}
+
+impl Enforcer {
+    pub fn new(limits: ResourceLimits) -> Self {
+        Enforcer {
+            #[cfg(target_os = "linux")]
+            cgroup: if limits.cpu_secs.is_some() || limits.memory_bytes.is_some() {
+                cgroup::Cgroup::create(limits.memory_bytes)
+            } else {
+                None
+            },
+            limits,
+            output: AtomicU64::new(0),
+            output_exceeded: AtomicBool::new(false),
+            hit: Mutex::new(None),
+        }
+    }
+
+    pub fn limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    // 是否需要监视线程：超时、输出和按 cgroup 统计的 CPU 时间要在运行中检查
+    pub fn needs_watch(&self) -> bool {
+        self.limits.timeout_secs.is_some() || self.limits.output_bytes.is_some() || self.cgroup_cpu()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn cgroup_cpu(&self) -> bool {
+        self.cgroup.is_some() && self.limits.cpu_secs.is_some()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn cgroup_cpu(&self) -> bool {
+        false
+    }
+
+    // 在子进程里设置 rlimit 并加入 cgroup
+    #[cfg(unix)]
+    pub fn prepare(&self, command: &mut Command) {
+        let cpu = if self.cgroup_cpu() { None } else { self.limits.cpu_secs };
+        #[cfg(target_os = "linux")]
+        let (memory, procs) = match &self.cgroup {
+            Some(cgroup) => (None, Some(cgroup.procs_path())),
+            None => (self.limits.memory_bytes, None),
+        };
+        #[cfg(not(target_os = "linux"))]
+        let memory = self.limits.memory_bytes;
+        let files = self.limits.open_files;
+
+        unsafe {
+            command.pre_exec(move || {
+                // 超过软限制收到 SIGXCPU，再多一秒直接 SIGKILL
+                if let Some(secs) = cpu {
+                    set_rlimit(libc::RLIMIT_CPU as _, secs, secs + 1)?;
+                }
+                if let Some(bytes) = memory {
+                    // Linux 上 RLIMIT_DATA 不会限制只是预留的地址空间
+                    #[cfg(target_os = "linux")]
+                    set_rlimit(libc::RLIMIT_DATA as _, bytes, bytes)?;
+                    #[cfg(not(target_os = "linux"))]
+                    set_rlimit(libc::RLIMIT_AS as _, bytes, bytes)?;
+                }
+                if let Some(count) = files {
+                    set_rlimit(libc::RLIMIT_NOFILE as _, count, count)?;
+                }
+                // 写入 0 表示把当前进程移到这个 cgroup；失败时只是没有限制
+                #[cfg(target_os = "linux")]
+                if let Some(path) = &procs {
+                    let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+                    if fd >= 0 {
+                        libc::write(fd, b"0".as_ptr() as *const libc::c_void, 1);
+                        libc::close(fd);
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn prepare(&self, _command: &mut Command) {}
+
+    // 读取线程每读到一段输出调用一次，超过限制后返回 false，之后的输出不再显示
+    pub fn add_output(&self, bytes: usize) -> bool {
+        let max = match self.limits.output_bytes {
+            Some(max) => max,
+            None => return true,
+        };
+        let total = self.output.fetch_add(bytes as u64, Ordering::SeqCst) + bytes as u64;
+        if total > max {
+            self.output_exceeded.store(true, Ordering::SeqCst);
+            false
+        } else {
+            true
+        }
+    }
+
+    // 监视线程定期调用，返回需要终止命令的原因
+    pub fn check(&self, elapsed: Duration) -> Option<LimitKind> {
+        let kind = if self.output_exceeded.load(Ordering::SeqCst) {
+            Some(LimitKind::Output)
+        } else if self.limits.timeout_secs.map_or(false, |secs| elapsed >= Duration::from_secs(secs)) {
+            Some(LimitKind::Timeout)
+        } else if self.cpu_exceeded() {
+            Some(LimitKind::Cpu)
+        } else {
+            None
+        };
+        if let Some(kind) = kind {
+            self.record(kind);
+        }
+        kind
+    }
+
+    #[cfg(target_os = "linux")]
+    fn cpu_exceeded(&self) -> bool {
+        match (&self.cgroup, self.limits.cpu_secs) {
+            (Some(cgroup), Some(secs)) => cgroup.cpu_usage().map_or(false, |used| used >= Duration::from_secs(secs)),
+            _ => false,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn cpu_exceeded(&self) -> bool {
+        false
+    }
+
+    fn record(&self, kind: LimitKind) {
+        let mut hit = lock_recover(&self.hit);
+        if hit.is_none() {
+            *hit = Some(kind);
+        }
+    }
+
+    // 终止 cgroup 里剩下的进程，包括已经脱离进程组的
+    pub fn kill_all(&self) {
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = &self.cgroup {
+            cgroup.kill();
+        }
+    }
+
+    // 命令结束后判断是否因为超限而结束
+    pub fn finish(&self, status: &ExitStatus, output: &[String]) -> Option<LimitKind> {
+        if let Some(kind) = *lock_recover(&self.hit) {
+            return Some(kind);
+        }
+        if status.success() {
+            return None;
+        }
+        let kind = self.detect(status, output);
+        if let Some(kind) = kind {
+            self.record(kind);
+        }
+        kind
+    }
+
+    fn detect(&self, status: &ExitStatus, output: &[String]) -> Option<LimitKind> {
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = &self.cgroup {
+            if self.limits.memory_bytes.is_some() && cgroup.oom_killed() {
+                return Some(LimitKind::Memory);
+            }
+        }
+        // script/unbuffer 把子进程的信号转成 128 + 信号值的退出码
+        #[cfg(unix)]
+        if self.limits.cpu_secs.is_some()
+            && (status.signal() == Some(libc::SIGXCPU) || status.code() == Some(128 + libc::SIGXCPU))
+        {
+            return Some(LimitKind::Cpu);
+        }
+        let mentions = |patterns: &[&str]| output.iter().any(|line| patterns.iter().any(|p| line.contains(p)));
+        if self.limits.memory_bytes.is_some() && mentions(MEMORY_ERRORS) {
+            return Some(LimitKind::Memory);
+        }
+        if self.limits.open_files.is_some() && mentions(OPEN_FILES_ERRORS) {
+            return Some(LimitKind::OpenFiles);
+        }
+        None
+    }
+}
+
+// 被资源限制终止的命令没有退出码，按 shell 的习惯报告成 128 + 信号值
+pub fn killed_exit_code(status: &ExitStatus) -> Option<i32> {
+    #[cfg(unix)]
+    {
+        status.code().or_else(|| status.signal().map(|signal| 128 + signal))
+    }
+    #[cfg(not(unix))]
+    {
+        status.code()
+    }
+}
+
+#[cfg(unix)]
+fn set_rlimit(resource: i32, soft: u64, hard: u64) -> std::io::Result<()> {
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    let resource = resource as libc::__rlimit_resource_t;
+    let mut current = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::getrlimit(resource, &mut current) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // 普通用户不能提高硬限制，超过时按现有的硬限制设置
+    let limit = libc::rlimit {
+        rlim_cur: (soft as libc::rlim_t).min(current.rlim_max),
+        rlim_max: (hard as libc::rlim_t).min(current.rlim_max),
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod cgroup {
+    use std::ffi::CString;
+    use std::fs;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    const ROOT: &str = "/sys/fs/cgroup";
+
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    pub struct Cgroup {
+        path: PathBuf,
+    }
+
+    // 当前进程所在的 cgroup v2 目录
+    fn own_cgroup() -> Option<PathBuf> {
+        let content = fs::read_to_string("/proc/self/cgroup").ok()?;
+        let relative = content.lines().find_map(|line| line.strip_prefix("0::"))?;
+        Some(Path::new(ROOT).join(relative.trim_start_matches('/')))
+    }
+
+    fn has_controller(dir: &Path, name: &str) -> bool {
+        fs::read_to_string(dir.join("cgroup.controllers"))
+            .map(|c| c.split_whitespace().any(|c| c == name))
+            .unwrap_or(false)
+    }
+
+    impl Cgroup {
+        // 在当前 cgroup 下创建子组；没有 cgroup v2 或没有权限时返回 None
+        pub fn create(memory: Option<u64>) -> Option<Cgroup> {
+            let parent = own_cgroup()?;
+            if !parent.join("cgroup.controllers").exists() {
+                return None;
+            }
+            let path = parent.join(format!(
+                "landingterm-{}-{}",
+                std::process::id(),
+                NEXT_ID.fetch_add(1, Ordering::SeqCst)
+            ));
+            fs::create_dir(&path).ok()?;
+            let cgroup = Cgroup { path };
+            if let Some(bytes) = memory {
+                // 父组有进程时不能再打开控制器，这时放弃使用 cgroup
+                if !has_controller(&cgroup.path, "memory") {
+                    let _ = fs::write(parent.join("cgroup.subtree_control"), "+memory");
+                }
+                if !has_controller(&cgroup.path, "memory")
+                    || fs::write(cgroup.path.join("memory.max"), bytes.to_string()).is_err()
+                {
+                    return None;
+                }
+                let _ = fs::write(cgroup.path.join("memory.swap.max"), "0");
+            }
+            Some(cgroup)
+        }
+
+        pub fn procs_path(&self) -> CString {
+            CString::new(self.path.join("cgroup.procs").as_os_str().as_bytes()).unwrap_or_default()
+        }
+
+        // 整棵进程树（包括已经退出的子进程）累计的 CPU 时间
+        pub fn cpu_usage(&self) -> Option<Duration> {
+            let stat = fs::read_to_string(self.path.join("cpu.stat")).ok()?;
+            stat.lines()
+                .find_map(|line| line.strip_prefix("usage_usec "))
+                .and_then(|v| v.trim().parse().ok())
+                .map(Duration::from_micros)
+        }
+
+        pub fn oom_killed(&self) -> bool {
+            fs::read_to_string(self.path.join("memory.events"))
+                .ok()
+                .and_then(|events| {
+                    events
+                        .lines()
+                        .find_map(|line| line.strip_prefix("oom_kill "))
+                        .and_then(|v| v.trim().parse::<u64>().ok())
+                })
+                .map_or(false, |count| count > 0)
+        }
+
+        // cgroup.kill 需要 5.14 以上的内核，旧内核上由进程组终止
+        pub fn kill(&self) {
+            let _ = fs::write(self.path.join("cgroup.kill"), "1");
+        }
+    }
+
+    impl Drop for Cgroup {
+        fn drop(&mut self) {
+            if fs::remove_dir(&self.path).is_err() {
+                self.kill();
+                std::thread::sleep(Duration::from_millis(50));
+                let _ = fs::remove_dir(&self.path);
+            }
+        }
+    }
+}
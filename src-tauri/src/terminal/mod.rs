@@ -1 +1,29 @@
-pub mod process; 
\ No newline at end of file
+pub mod completion;
+#[cfg(unix)]
+pub mod control;
+pub mod correction;
+#[cfg(unix)]
+pub mod daemon;
+pub mod engine;
+pub mod error;
+pub mod guard;
+pub mod history;
+pub mod history_import;
+pub mod lexer;
+pub mod limits;
+pub mod orphans;
+pub mod persist;
+pub mod policy;
+pub mod ports;
+pub mod process;
+pub mod process_tree;
+pub mod procfs;
+pub mod sandbox;
+pub mod session;
+pub mod shell_completion;
+pub mod shutdown;
+pub mod sink;
+pub mod stats;
+pub mod suggest;
+pub mod syntax;
+pub mod title;
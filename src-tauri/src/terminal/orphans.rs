@@ -0,0 +1,208 @@
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(unix)]
+use super::daemon;
+use super::error::{TerminalError, TerminalResult};
+#[cfg(unix)]
+use super::process::run_blocking;
+use super::session::lock_recover;
+#[cfg(unix)]
+use super::shutdown;
+
+const REGISTRY_FILE: &str = "processes.json";
+
+// 记录到磁盘的进程信息，应用崩溃后下次启动时用来找回仍在运行的进程
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProcessRecord {
+    pub pid: u32,
+    pub pgid: u32,
+    pub command: String,
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+    // 启动时间（秒），用于展示
+    pub started_at: u64,
+    // ps 报告的启动时间，用于判断 pid 是否已被其他进程复用
+    pub process_start: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum OrphanAction {
+    Kill,
+    Ignore,
+}
+
+static REGISTRY_PATH: OnceCell<PathBuf> = OnceCell::new();
+static RECORDS: Lazy<Mutex<Vec<ProcessRecord>>> = Lazy::new(|| Mutex::new(Vec::new()));
+// 启动时发现的、上次运行遗留下来的进程
+static ORPHANS: Lazy<Mutex<Vec<ProcessRecord>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// 读取上次运行留下的注册表，找出仍然存活的进程
+pub fn init(data_dir: Option<PathBuf>) {
+    let dir = match data_dir {
+        Some(dir) => dir,
+        None => return,
+    };
+    if let Err(e) = fs::create_dir_all(&dir) {
+        eprintln!("Failed to create app data dir {}: {}", dir.display(), e);
+        return;
+    }
+    let path = dir.join(REGISTRY_FILE);
+
+    let previous: Vec<ProcessRecord> = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let survivors: Vec<ProcessRecord> = previous.into_iter().filter(is_same_process).collect();
+    if !survivors.is_empty() {
+        println!("Found {} process(es) left over from a previous run", survivors.len());
+    }
+
+    let _ = REGISTRY_PATH.set(path);
+    *lock_recover(&RECORDS) = survivors.clone();
+    *lock_recover(&ORPHANS) = survivors;
+    save();
+}
+
+pub fn record(terminal_id: &str, pid: u32, command: &str) {
+    let started_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    lock_recover(&RECORDS).push(ProcessRecord {
+        pid,
+        // 子进程通过 setpgid(0, 0) 成为自己进程组的组长
+        pgid: pid,
+        command: command.to_string(),
+        terminal_id: terminal_id.to_string(),
+        started_at,
+        process_start: process_start(pid),
+    });
+    save();
+}
+
+pub fn forget(pid: u32) {
+    let removed = {
+        let mut records = lock_recover(&RECORDS);
+        let before = records.len();
+        records.retain(|r| r.pid != pid);
+        before != records.len()
+    };
+    lock_recover(&ORPHANS).retain(|r| r.pid != pid);
+    if removed {
+        save();
+    }
+}
+
+fn save() {
+    let path = match REGISTRY_PATH.get() {
+        Some(path) => path,
+        None => return,
+    };
+    let records = lock_recover(&RECORDS).clone();
+    match serde_json::to_string_pretty(&records) {
+        Ok(content) => {
+            if let Err(e) = fs::write(path, content) {
+                eprintln!("Failed to write process registry: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize process registry: {}", e),
+    }
+}
+
+fn process_start(pid: u32) -> Option<String> {
+    let output = Command::new("ps")
+        .args(["-o", "lstart=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let start = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if start.is_empty() {
+        None
+    } else {
+        Some(start)
+    }
+}
+
+fn is_same_process(record: &ProcessRecord) -> bool {
+    match (process_start(record.pid), &record.process_start) {
+        (Some(now), Some(then)) => &now == then,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+// 遗留进程的输出经由 script 的伪终端写进应用的管道，应用退出后就没有读取端了，
+// 所以只能终止或者忽略它们，不能重新接上输出
+pub fn orphans() -> Vec<ProcessRecord> {
+    lock_recover(&ORPHANS).clone()
+}
+
+pub fn resolve(pid: u32, action: OrphanAction) -> TerminalResult<()> {
+    let record = lock_recover(&ORPHANS)
+        .iter()
+        .find(|r| r.pid == pid)
+        .cloned()
+        .ok_or_else(|| TerminalError::ProcessExited(pid.to_string()))?;
+
+    match action {
+        OrphanAction::Ignore => {
+            forget(pid);
+            Ok(())
+        }
+        OrphanAction::Kill => {
+            #[cfg(unix)]
+            {
+                let report = shutdown::terminate_tree(record.pgid, shutdown::SHUTDOWN_GRACE);
+                if !report.survivors.is_empty() {
+                    return Err(TerminalError::PermissionDenied(format!(
+                        "could not kill processes {:?}",
+                        report.survivors
+                    )));
+                }
+            }
+            #[cfg(not(unix))]
+            let _ = record;
+            forget(pid);
+            Ok(())
+        }
+    }
+}
+
+// 连接了会话守护进程时，守护进程上次崩溃遗留的进程也一起列出来
+#[tauri::command]
+pub async fn list_orphans() -> Vec<ProcessRecord> {
+    let mut records = orphans();
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        let remote = run_blocking(move || {
+            let value = client.call("orphans", Value::Null)?;
+            serde_json::from_value::<Vec<ProcessRecord>>(value).map_err(|e| TerminalError::Daemon(e.to_string()))
+        })
+        .await;
+        match remote {
+            Ok(remote) => records.extend(remote),
+            Err(e) => eprintln!("Failed to list session daemon orphans: {}", e),
+        }
+    }
+    records
+}
+
+#[tauri::command]
+pub async fn resolve_orphan(pid: u32, action: OrphanAction) -> TerminalResult<()> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        if !orphans().iter().any(|r| r.pid == pid) {
+            let params = json!({ "pid": pid, "action": action });
+            return run_blocking(move || client.call("resolve_orphan", params).map(|_| ())).await;
+        }
+    }
+    resolve(pid, action)
+}
@@ -0,0 +1,296 @@
+// 会话快照：退出时和运行期间定期把每个终端的标题、工作目录、环境变量、
+// 命令块历史和裁剪后的输出写入 app data 目录，下次启动时由 restore_sessions 恢复。
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::Runtime;
+
+use super::engine;
+use super::error::TerminalResult;
+use super::guard;
+use super::process::{format_current_dir, run_blocking};
+use super::sandbox::SandboxConfig;
+use super::session::{self, tail, CommandBlock, SessionInfo};
+use super::sink::{CommandStarted, OutputSink, StreamOutput, TerminalEvent, WindowSink};
+
+const SESSIONS_FILE: &str = "sessions.json";
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+// 快照中保留的命令块数量、每个命令块的输出行数以及输出缓冲行数
+const SNAPSHOT_BLOCKS: usize = 50;
+const SNAPSHOT_BLOCK_LINES: usize = 200;
+const SNAPSHOT_SCROLLBACK: usize = 500;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SessionSnapshot {
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+    pub title: Option<String>,
+    pub current_dir: PathBuf,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub startup_command: Option<String>,
+    #[serde(default)]
+    pub blocks: Vec<CommandBlock>,
+    #[serde(default)]
+    pub scrollback: Vec<String>,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub sandbox: Option<SandboxConfig>,
+}
+
+static SNAPSHOT_PATH: OnceCell<PathBuf> = OnceCell::new();
+// 恢复之前不保存，避免启动时用空会话覆盖上次的快照
+static RESTORED: AtomicBool = AtomicBool::new(false);
+
+pub fn init(data_dir: Option<PathBuf>) {
+    let dir = match data_dir {
+        Some(dir) => dir,
+        None => return,
+    };
+    if SNAPSHOT_PATH.set(dir.join(SESSIONS_FILE)).is_err() {
+        return;
+    }
+    thread::spawn(|| loop {
+        thread::sleep(SAVE_INTERVAL);
+        save();
+    });
+}
+
+pub fn snapshot() -> Vec<SessionSnapshot> {
+    let mut snapshots = Vec::new();
+    for id in session::ids() {
+        let handle = match session::get(&id) {
+            Ok(handle) => handle,
+            Err(_) => continue,
+        };
+        let session = match session::lock(&id, &handle) {
+            Ok(session) => session,
+            Err(_) => continue,
+        };
+        let skip = session.blocks.len().saturating_sub(SNAPSHOT_BLOCKS);
+        snapshots.push(SessionSnapshot {
+            terminal_id: id.clone(),
+            title: session.title.clone(),
+            current_dir: session.current_dir.clone(),
+            env: session.env.clone(),
+            startup_command: session.startup_command.clone(),
+            blocks: session
+                .blocks
+                .iter()
+                .skip(skip)
+                .map(|block| CommandBlock {
+                    command: block.command.clone(),
+                    directory: block.directory.clone(),
+                    output: tail(&block.output, SNAPSHOT_BLOCK_LINES),
+                    code: block.code,
+                })
+                .collect(),
+            scrollback: session.recent_output(SNAPSHOT_SCROLLBACK),
+            profile: session.profile.clone(),
+            sandbox: session.sandbox.clone(),
+        });
+    }
+    snapshots.sort_by(|a, b| a.terminal_id.cmp(&b.terminal_id));
+    snapshots
+}
+
+// 写入快照；先写临时文件再改名，中途崩溃不会留下损坏的文件
+pub fn save() {
+    let path = match SNAPSHOT_PATH.get() {
+        Some(path) if RESTORED.load(Ordering::SeqCst) => path,
+        _ => return,
+    };
+    // 会话由守护进程持有时无需快照
+    #[cfg(unix)]
+    if super::daemon::client().is_some() {
+        return;
+    }
+
+    let result = serde_json::to_string_pretty(&snapshot())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .and_then(|content| {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let tmp = path.with_extension("json.tmp");
+            fs::write(&tmp, content)?;
+            fs::rename(&tmp, path)
+        });
+    if let Err(e) = result {
+        eprintln!("Failed to save sessions to {}: {}", path.display(), e);
+    }
+}
+
+fn load() -> Vec<SessionSnapshot> {
+    SNAPSHOT_PATH
+        .get()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// 把快照写回会话注册表；正在运行命令的会话保持不变
+fn apply(snapshot: SessionSnapshot) -> TerminalResult<()> {
+    let current_dir = if snapshot.current_dir.is_dir() {
+        snapshot.current_dir
+    } else {
+        session::home_dir()
+    };
+    session::with_session(&snapshot.terminal_id, |s| {
+        if s.running.is_some() {
+            return;
+        }
+        s.current_dir = current_dir;
+        s.title = snapshot.title;
+        s.env = snapshot.env;
+        s.startup_command = snapshot.startup_command;
+        s.blocks = snapshot.blocks.into_iter().collect();
+        s.scrollback = snapshot.scrollback.into_iter().collect();
+        s.profile = snapshot.profile;
+        s.sandbox = snapshot.sandbox;
+    })
+}
+
+// 把上次保存的快照写回会话注册表，只有第一次调用生效；返回设置了启动命令的终端和命令。
+// 会话守护进程启动时也调用它，守护进程重启后会话仍然保留。
+pub fn restore() -> TerminalResult<Vec<(String, String)>> {
+    let snapshots = if RESTORED.swap(true, Ordering::SeqCst) {
+        Vec::new()
+    } else {
+        load()
+    };
+    let mut startup = Vec::new();
+    for snapshot in snapshots {
+        if let Some(command) = snapshot.startup_command.clone() {
+            startup.push((snapshot.terminal_id.clone(), command));
+        }
+        apply(snapshot)?;
+    }
+    Ok(startup)
+}
+
+// 在后台运行恢复出来的启动命令。启动命令同样经过危险命令检查；
+// 需要确认的不自动运行，在终端里说明原因
+pub fn run_startup_commands(sink: Arc<dyn OutputSink>, startup: Vec<(String, String)>) {
+    for (terminal_id, command) in startup {
+        let skipped = match guard::check(&command, false) {
+            Ok(None) => None,
+            Ok(Some(verdict)) => Some(format!("needs confirmation: {}", verdict.reason())),
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(reason) = skipped {
+            let current_dir = session::with_existing(&terminal_id, |s| format_current_dir(&s.current_dir))
+                .unwrap_or_default();
+            sink.send(TerminalEvent::Output(StreamOutput {
+                content: format!("Startup command `{}` was not run ({})", command, reason),
+                output_type: "stderr".to_string(),
+                current_dir,
+                should_replace_last: false,
+                terminal_id,
+            }));
+            continue;
+        }
+        sink.send(TerminalEvent::Started(CommandStarted {
+            terminal_id: terminal_id.clone(),
+            command: command.clone(),
+        }));
+        let sink = Arc::clone(&sink);
+        thread::spawn(move || {
+            if let Err(e) = engine::execute(sink, &terminal_id, &command) {
+                eprintln!("Startup command in terminal {} failed: {}", terminal_id, e);
+            }
+        });
+    }
+}
+
+// 恢复上次保存的会话，返回恢复后的会话列表供前端重建标签页。
+// run_startup 为 true 时，在每个设置了启动命令的终端里运行它。
+#[tauri::command]
+pub async fn restore_sessions<R: Runtime>(
+    window: tauri::Window<R>,
+    run_startup: Option<bool>,
+) -> TerminalResult<Vec<SessionInfo>> {
+    #[cfg(unix)]
+    if let Some(client) = super::daemon::client() {
+        // 守护进程里的会话本身就还在
+        RESTORED.store(true, Ordering::SeqCst);
+        return run_blocking(move || {
+            let value = client.call("sessions", serde_json::json!({ "lines": SNAPSHOT_SCROLLBACK }))?;
+            serde_json::from_value(value)
+                .map_err(|e| super::error::TerminalError::Daemon(e.to_string()))
+        })
+        .await;
+    }
+
+    let startup = restore()?;
+
+    if run_startup.unwrap_or(false) {
+        run_startup_commands(Arc::new(WindowSink::new(window)), startup);
+    }
+
+    Ok(session::infos(SNAPSHOT_SCROLLBACK))
+}
+
+pub fn set_title(terminal_id: &str, title: Option<String>) -> TerminalResult<()> {
+    session::with_session(terminal_id, |s| s.title = title)
+}
+
+pub fn set_env(terminal_id: &str, env: HashMap<String, String>) -> TerminalResult<()> {
+    session::with_session(terminal_id, |s| s.env = env)
+}
+
+pub fn set_startup(terminal_id: &str, command: Option<String>) -> TerminalResult<()> {
+    let command = command.filter(|c| !c.trim().is_empty());
+    session::with_session(terminal_id, |s| s.startup_command = command)
+}
+
+// 连接了会话守护进程时，这些设置要写到守护进程持有的会话里，由守护进程保存快照
+#[cfg(unix)]
+async fn forward(method: &'static str, params: serde_json::Value) -> Option<TerminalResult<()>> {
+    let client = super::daemon::client()?;
+    Some(run_blocking(move || client.call(method, params).map(|_| ())).await)
+}
+
+#[tauri::command]
+pub async fn set_terminal_title(terminal_id: String, title: Option<String>) -> TerminalResult<()> {
+    #[cfg(unix)]
+    if let Some(result) = forward("title", serde_json::json!({ "terminalId": terminal_id, "title": title })).await {
+        return result;
+    }
+    set_title(&terminal_id, title)
+}
+
+#[tauri::command]
+pub async fn set_terminal_env(
+    terminal_id: String,
+    env: HashMap<String, String>,
+) -> TerminalResult<()> {
+    #[cfg(unix)]
+    if let Some(result) = forward("env", serde_json::json!({ "terminalId": terminal_id, "env": env })).await {
+        return result;
+    }
+    set_env(&terminal_id, env)
+}
+
+#[tauri::command]
+pub async fn set_startup_command(
+    terminal_id: String,
+    command: Option<String>,
+) -> TerminalResult<()> {
+    #[cfg(unix)]
+    if let Some(result) =
+        forward("startup_command", serde_json::json!({ "terminalId": terminal_id, "command": command })).await
+    {
+        return result;
+    }
+    set_startup(&terminal_id, command)
+}
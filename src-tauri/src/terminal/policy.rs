@@ -0,0 +1,517 @@
+// 命令策略：给受限终端配置允许/禁止的可执行文件、禁止访问的路径、是否允许网络工具以及
+// 最长运行时间。策略写在 app data 目录下的 policy.json 里，每个终端使用一个 profile，
+// 命令在启动子进程之前检查，违反策略的命令不会运行，并记录到 policy.log。
+//
+// policy.json 示例：
+//   { "default_profile": "standard",
+//     "locked": false,
+//     "profiles": {
+//       "standard": { "denied_executables": ["shutdown"], "forbidden_paths": ["~/.ssh"] },
+//       "kiosk": { "allowed_executables": ["ls", "cat", "git"], "allow_network": false,
+//                  "max_runtime_secs": 60 } } }
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+#[cfg(unix)]
+use super::daemon;
+use super::error::{TerminalError, TerminalResult};
+use super::guard;
+use super::history;
+use super::process::run_blocking;
+use super::session::{self, lock_recover};
+use super::syntax::{self, CommandNode, Statement};
+
+const CONFIG_FILE: &str = "policy.json";
+const LOG_FILE: &str = "policy.log";
+
+// 访问网络的常用工具
+const NETWORK_TOOLS: &[&str] = &[
+    "curl", "wget", "fetch", "ssh", "scp", "sftp", "rsync", "nc", "ncat", "netcat", "telnet", "ftp", "socat",
+    "nmap", "ping", "mosh", "aria2c",
+];
+// 只影响 shell 自身状态的内建命令，设置了白名单时也总是允许
+const ALWAYS_ALLOWED: &[&str] = &["cd", "pwd", "echo", "printf", "true", "false", "export", "unset", "test", "["];
+// 用 -c 执行字符串的 shell，字符串里的命令同样要检查
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish"];
+// sh -c "sh -c '...'" 这样的嵌套最多检查这么多层
+const MAX_NESTING: usize = 4;
+// 复合命令的关键字，后面跟着的才是真正的命令
+const KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "do", "done", "while", "until", "!", "{", "}", "esac",
+];
+
+fn default_allow_network() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Policy {
+    // 设置后只能运行列表里的命令；带 / 的条目按完整路径匹配
+    pub allowed_executables: Option<Vec<String>>,
+    pub denied_executables: Vec<String>,
+    // 命令参数和重定向都不能指向这些目录（及其子目录）
+    pub forbidden_paths: Vec<String>,
+    #[serde(default = "default_allow_network")]
+    pub allow_network: bool,
+    pub max_runtime_secs: Option<u64>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            allowed_executables: None,
+            denied_executables: Vec::new(),
+            forbidden_paths: Vec::new(),
+            allow_network: true,
+            max_runtime_secs: None,
+        }
+    }
+}
+
+impl Policy {
+    // 是否限制了可以运行的命令或访问的路径
+    pub fn is_restricted(&self) -> bool {
+        self.allowed_executables.is_some()
+            || !self.denied_executables.is_empty()
+            || !self.forbidden_paths.is_empty()
+            || !self.allow_network
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct PolicyConfig {
+    // 没有单独指定 profile 的终端使用它；为空表示不受限制
+    pub default_profile: Option<String>,
+    // 锁定后不能在应用里切换终端的 profile
+    pub locked: bool,
+    pub profiles: HashMap<String, Policy>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    ExecutableNotAllowed,
+    ExecutableDenied,
+    ForbiddenPath,
+    NetworkDenied,
+    CommandSubstitution,
+    VariableCommand,
+    NestedCommand,
+    UnknownProfile,
+}
+
+impl PolicyRule {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PolicyRule::ExecutableNotAllowed => "executable_not_allowed",
+            PolicyRule::ExecutableDenied => "executable_denied",
+            PolicyRule::ForbiddenPath => "forbidden_path",
+            PolicyRule::NetworkDenied => "network_denied",
+            PolicyRule::CommandSubstitution => "command_substitution",
+            PolicyRule::VariableCommand => "variable_command",
+            PolicyRule::NestedCommand => "nested_command",
+            PolicyRule::UnknownProfile => "unknown_profile",
+        }
+    }
+}
+
+// 违反的规则；[start, end) 为命令中相关部分的字符位置
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PolicyViolation {
+    pub profile: String,
+    pub rule: PolicyRule,
+    // 被拒绝的命令名或路径
+    pub target: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.rule {
+            PolicyRule::ExecutableNotAllowed => format!("`{}` is not in the allowed executables", self.target),
+            PolicyRule::ExecutableDenied => format!("`{}` is a denied executable", self.target),
+            PolicyRule::ForbiddenPath => format!("access to {} is forbidden", self.target),
+            PolicyRule::NetworkDenied => format!("network tool `{}` is not allowed", self.target),
+            PolicyRule::CommandSubstitution => "command substitution is not allowed".to_string(),
+            PolicyRule::VariableCommand => format!("command name `{}` comes from a variable", self.target),
+            PolicyRule::NestedCommand => format!("`{}` nests commands too deeply to check", self.target),
+            PolicyRule::UnknownProfile => format!("profile `{}` is not defined", self.target),
+        };
+        write!(f, "{} (profile {})", what, self.profile)
+    }
+}
+
+#[derive(Default)]
+struct Loaded {
+    path: Option<PathBuf>,
+    log: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    config: PolicyConfig,
+}
+
+static CONFIG: Lazy<Mutex<Loaded>> = Lazy::new(|| Mutex::new(Loaded::default()));
+
+pub fn init(data_dir: Option<PathBuf>) {
+    let mut loaded = lock_recover(&CONFIG);
+    loaded.path = data_dir.as_ref().map(|dir| dir.join(CONFIG_FILE));
+    loaded.log = data_dir.map(|dir| dir.join(LOG_FILE));
+    loaded.modified = None;
+    reload(&mut loaded);
+}
+
+// 配置文件被修改后重新读取，不需要重启应用
+fn reload(loaded: &mut Loaded) {
+    let path = match &loaded.path {
+        Some(path) => path,
+        None => return,
+    };
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    if modified.is_some() && modified == loaded.modified {
+        return;
+    }
+    loaded.config = match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Invalid policy config {}: {}", path.display(), e);
+            PolicyConfig::default()
+        }),
+        Err(_) => PolicyConfig::default(),
+    };
+    loaded.modified = modified;
+}
+
+pub fn config() -> PolicyConfig {
+    let mut loaded = lock_recover(&CONFIG);
+    reload(&mut loaded);
+    loaded.config.clone()
+}
+
+// 终端实际使用的 profile 名
+pub fn profile_name(config: &PolicyConfig, profile: Option<&str>) -> Option<String> {
+    profile.map(str::to_string).or_else(|| config.default_profile.clone())
+}
+
+// 检查命令是否符合策略，返回第一个违反的规则
+pub fn check_with(command: &str, cwd: &Path, profile: &str, policy: &Policy) -> Result<(), PolicyViolation> {
+    match find_violation(command, cwd, policy, 0) {
+        Some((rule, target, start, end)) => Err(PolicyViolation {
+            profile: profile.to_string(),
+            rule,
+            target,
+            start,
+            end,
+        }),
+        None => Ok(()),
+    }
+}
+
+// 返回违反的规则、目标以及在 command 中的字符位置；
+// sh -c、eval 等执行的字符串递归检查，违反时报告外层命令的位置
+fn find_violation(
+    command: &str,
+    cwd: &Path,
+    policy: &Policy,
+    depth: usize,
+) -> Option<(PolicyRule, String, usize, usize)> {
+    // $(...)、反引号和 <(...) 里的命令无法事先确定（可能拼出命令名），受限时直接拒绝
+    if policy.is_restricted() {
+        let byte = ["$(", "`", "<(", ">("].iter().filter_map(|pattern| command.find(pattern)).min();
+        if let Some(byte) = byte {
+            let start = command[..byte].chars().count();
+            return Some((PolicyRule::CommandSubstitution, String::new(), start, start + 1));
+        }
+    }
+
+    let (statements, _) = syntax::parse(command);
+    let mut found = None;
+    visit(&statements, &mut |node| {
+        if found.is_some() {
+            return;
+        }
+        if let CommandNode::Simple { argv, redirections, start, end, .. } = node {
+            found = check_simple(argv, cwd, policy, depth)
+                .or_else(|| {
+                    redirections
+                        .iter()
+                        // 2>&1 这样的目标是文件描述符
+                        .filter(|r| !r.operator.ends_with('&'))
+                        .find(|r| is_forbidden(&r.target, cwd, policy))
+                        .map(|r| (PolicyRule::ForbiddenPath, r.target.clone()))
+                })
+                .map(|(rule, target)| (rule, target, *start, *end));
+        }
+    });
+    found
+}
+
+fn visit(statements: &[Statement], f: &mut dyn FnMut(&CommandNode)) {
+    for statement in statements {
+        for pipeline in &statement.pipelines {
+            for node in &pipeline.commands {
+                f(node);
+                if let CommandNode::Subshell { body, .. } = node {
+                    visit(body, f);
+                }
+            }
+        }
+    }
+}
+
+fn base_name(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+fn check_simple(argv: &[String], cwd: &Path, policy: &Policy, depth: usize) -> Option<(PolicyRule, String)> {
+    let mut argv = argv;
+    while argv.first().map(|name| KEYWORDS.contains(&name.as_str())).unwrap_or(false) {
+        argv = &argv[1..];
+    }
+    // for/select 后面只是变量名和列表，命令在 do 之后
+    if argv.first().map(|name| name == "for" || name == "select").unwrap_or(false) {
+        return None;
+    }
+
+    // sudo env curl、xargs rm 这样的包装命令，每一层的命令名都要检查
+    let mut stage = argv;
+    while let Some(name) = stage.first().map(String::as_str) {
+        // $c、${X}rm 这样的命令名运行时才确定，受限时直接拒绝
+        if policy.is_restricted() && name.contains('$') {
+            return Some((PolicyRule::VariableCommand, name.to_string()));
+        }
+        if let Some(violation) = check_name(name, policy) {
+            return Some(violation);
+        }
+        // sh -c、eval、env -S 执行的字符串按完整命令检查
+        if let Some(nested) = nested_command(stage) {
+            if depth >= MAX_NESTING {
+                return Some((PolicyRule::NestedCommand, name.to_string()));
+            }
+            if let Some((rule, target, _, _)) = find_violation(&nested, cwd, policy, depth + 1) {
+                return Some((rule, target));
+            }
+        }
+        match guard::strip_wrapper(stage) {
+            Some(inner) => stage = inner,
+            None => break,
+        }
+    }
+
+    argv.iter()
+        .skip(1)
+        .find(|arg| is_forbidden(arg, cwd, policy))
+        .map(|arg| (PolicyRule::ForbiddenPath, arg.to_string()))
+}
+
+fn check_name(name: &str, policy: &Policy) -> Option<(PolicyRule, String)> {
+    if let Some(allowed) = &policy.allowed_executables {
+        let permitted = ALWAYS_ALLOWED.contains(&name) || allowed.iter().any(|entry| entry == name);
+        if !permitted {
+            return Some((PolicyRule::ExecutableNotAllowed, name.to_string()));
+        }
+    }
+    let base = base_name(name);
+    if policy.denied_executables.iter().any(|entry| entry == name || entry == base) {
+        return Some((PolicyRule::ExecutableDenied, name.to_string()));
+    }
+    if !policy.allow_network && NETWORK_TOOLS.contains(&base) {
+        return Some((PolicyRule::NetworkDenied, base.to_string()));
+    }
+    None
+}
+
+// -c 可以和其他短选项写在一起，例如 -ec、-lc
+fn is_command_flag(arg: &str) -> bool {
+    match arg.strip_prefix('-') {
+        Some(flags) if !flags.starts_with('-') => flags.contains('c') && flags.chars().all(|c| c.is_ascii_alphabetic()),
+        _ => false,
+    }
+}
+
+// 命令要执行的字符串：sh -c 'cmd'、bash -lc 'cmd'、eval cmd、env -S 'cmd args'
+pub(crate) fn nested_command(argv: &[String]) -> Option<String> {
+    let name = base_name(argv.first()?);
+    let args = &argv[1..];
+    if SHELLS.contains(&name) {
+        let flag = args.iter().position(|arg| is_command_flag(arg))?;
+        return args[flag + 1..].iter().find(|arg| !arg.starts_with('-')).cloned();
+    }
+    if name == "eval" {
+        return Some(args.join(" "));
+    }
+    if name == "env" {
+        for (i, arg) in args.iter().enumerate() {
+            if !arg.starts_with('-') && !arg.contains('=') {
+                break;
+            }
+            // 字符串是单独的参数还是写在选项里，决定了剩余参数从哪里开始
+            let split = if arg == "-S" || arg == "--split-string" {
+                args.get(i + 1).map(|value| (value.clone(), i + 2))
+            } else if let Some(value) = arg.strip_prefix("--split-string=") {
+                Some((value.to_string(), i + 1))
+            } else {
+                arg.strip_prefix("-S").filter(|value| !value.is_empty()).map(|value| (value.to_string(), i + 1))
+            };
+            if let Some((split, rest)) = split {
+                let rest = args.get(rest..).unwrap_or(&[]);
+                return Some(std::iter::once(split).chain(rest.iter().cloned()).collect::<Vec<_>>().join(" "));
+            }
+        }
+    }
+    None
+}
+
+// --file=/etc/passwd 这样的选项只看等号后面的部分
+fn path_operand(arg: &str) -> Option<&str> {
+    if arg.starts_with('-') {
+        arg.split_once('=').map(|(_, value)| value)
+    } else {
+        Some(arg)
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    let home = session::home_dir();
+    for prefix in ["~", "$HOME", "${HOME}"] {
+        if path == prefix {
+            return home;
+        }
+        if let Some(rest) = path.strip_prefix(prefix).and_then(|rest| rest.strip_prefix('/')) {
+            return home.join(rest);
+        }
+    }
+    PathBuf::from(path)
+}
+
+// 只在字面上处理 . 和 ..，不要求路径存在
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+fn resolve(path: &str, cwd: &Path) -> Vec<PathBuf> {
+    let lexical = normalize(&cwd.join(expand_home(path)));
+    // 路径存在时再按真实路径比较一次，防止通过符号链接绕过
+    let mut resolved = vec![lexical.clone()];
+    if let Ok(real) = lexical.canonicalize() {
+        if real != lexical {
+            resolved.push(real);
+        }
+    }
+    resolved
+}
+
+fn is_forbidden(arg: &str, cwd: &Path, policy: &Policy) -> bool {
+    if policy.forbidden_paths.is_empty() {
+        return false;
+    }
+    let operand = match path_operand(arg) {
+        Some(operand) if !operand.is_empty() => operand,
+        _ => return false,
+    };
+    let candidates = resolve(operand, cwd);
+    policy.forbidden_paths.iter().any(|forbidden| {
+        let forbidden = normalize(&expand_home(forbidden));
+        candidates.iter().any(|candidate| candidate.starts_with(&forbidden))
+    })
+}
+
+// 执行前调用：按终端的 profile 检查命令，违反策略时记录日志并返回错误；
+// 允许执行时返回策略规定的最长运行时间
+pub fn enforce(terminal_id: &str, profile: Option<&str>, command: &str, cwd: &Path) -> TerminalResult<Option<Duration>> {
+    let config = config();
+    let name = match profile_name(&config, profile) {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let result = match config.profiles.get(&name) {
+        Some(policy) => check_with(command, cwd, &name, policy).map(|_| policy.max_runtime_secs.map(Duration::from_secs)),
+        // 配置里找不到的 profile 按最严格处理
+        None => Err(PolicyViolation {
+            profile: name.clone(),
+            rule: PolicyRule::UnknownProfile,
+            target: name.clone(),
+            start: 0,
+            end: command.chars().count(),
+        }),
+    };
+    result.map_err(|violation| {
+        log_violation(terminal_id, command, &violation);
+        TerminalError::PolicyViolation(violation)
+    })
+}
+
+fn log_violation(terminal_id: &str, command: &str, violation: &PolicyViolation) {
+    eprintln!("Policy violation in terminal {}: {} ({})", terminal_id, violation, command);
+    let path = match lock_recover(&CONFIG).log.clone() {
+        Some(path) => path,
+        None => return,
+    };
+    let line = json!({
+        "time": history::now_millis(),
+        "terminalId": terminal_id,
+        "profile": violation.profile,
+        "rule": violation.rule.as_str(),
+        "target": violation.target,
+        "command": command,
+    });
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = written {
+        eprintln!("Failed to write policy log {}: {}", path.display(), e);
+    }
+}
+
+// 切换终端使用的 profile；配置被锁定时不允许切换
+pub fn set_profile(terminal_id: &str, profile: Option<String>) -> TerminalResult<()> {
+    let config = config();
+    if config.locked {
+        return Err(TerminalError::PermissionDenied("policy profiles are locked".to_string()));
+    }
+    if let Some(name) = &profile {
+        if !config.profiles.contains_key(name) {
+            return Err(TerminalError::NotSupported(format!("unknown policy profile '{}'", name)));
+        }
+    }
+    session::with_session(terminal_id, |s| s.profile = profile)
+}
+
+#[tauri::command]
+pub async fn set_terminal_profile(terminal_id: String, profile: Option<String>) -> TerminalResult<()> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        let params = json!({ "terminalId": terminal_id, "profile": profile });
+        return run_blocking(move || client.call("profile", params).map(|_| ())).await;
+    }
+    set_profile(&terminal_id, profile)
+}
+
+// 可选的 profile 名，按名字排序
+#[tauri::command]
+pub async fn list_policy_profiles() -> TerminalResult<Vec<String>> {
+    let mut names: Vec<String> = config().profiles.into_keys().collect();
+    names.sort();
+    Ok(names)
+}
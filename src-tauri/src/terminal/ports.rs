@@ -0,0 +1,146 @@
+// 发现命令新开的监听端口：读取 /proc/<pid>/net/{tcp,tcp6,udp,udp6} 中处于监听状态的 socket，
+// 再用 /proc/<pid>/fd 中的 inode 找到打开它的进程，发出 terminal-port-opened 事件。
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::procfs::{self, ProcStat};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PortOpened {
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+    pub port: u16,
+    pub protocol: Protocol,
+    pub pid: u32,
+    // 监听的地址，0.0.0.0 或 :: 表示所有网卡
+    pub address: IpAddr,
+}
+
+// /proc/net 中的一行
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListeningSocket {
+    pub inode: u64,
+    pub address: IpAddr,
+    pub port: u16,
+    pub protocol: Protocol,
+}
+
+// TCP 的 LISTEN 状态
+const TCP_LISTEN: &str = "0A";
+// 没有连接对端的 UDP socket 处于 CLOSE 状态
+const UDP_UNCONNECTED: &str = "07";
+
+// 解析 /proc/net/{tcp,udp}[6] 的内容，只保留监听中的 socket
+pub fn parse_sockets(content: &str, protocol: Protocol) -> Vec<ListeningSocket> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (local, remote, state) = (fields.get(1)?, fields.get(2)?, fields.get(3)?);
+            let listening = match protocol {
+                Protocol::Tcp => *state == TCP_LISTEN,
+                Protocol::Udp => *state == UDP_UNCONNECTED && remote.ends_with(":0000"),
+            };
+            if !listening {
+                return None;
+            }
+            let (address, port) = parse_address(local)?;
+            if port == 0 {
+                return None;
+            }
+            Some(ListeningSocket {
+                inode: fields.get(9)?.parse().ok()?,
+                address,
+                port,
+                protocol,
+            })
+        })
+        .collect()
+}
+
+// 地址按 32 位一组、本机字节序的十六进制表示，端口是大端十六进制
+fn parse_address(field: &str) -> Option<(IpAddr, u16)> {
+    let (address, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let words = (0..address.len() / 8)
+        .map(|i| u32::from_str_radix(address.get(i * 8..i * 8 + 8)?, 16).ok())
+        .collect::<Option<Vec<u32>>>()?;
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_ne_bytes()).collect();
+    let address = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&bytes);
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+    Some((address, port))
+}
+
+// 读取 pid 所在网络命名空间中的监听 socket（沙箱里的命令有自己的命名空间）
+fn listening_sockets(pid: u32) -> Vec<ListeningSocket> {
+    [("tcp", Protocol::Tcp), ("tcp6", Protocol::Tcp), ("udp", Protocol::Udp), ("udp6", Protocol::Udp)]
+        .iter()
+        .filter_map(|(file, protocol)| {
+            let content = fs::read_to_string(format!("/proc/{}/net/{}", pid, file)).ok()?;
+            Some(parse_sockets(&content, *protocol))
+        })
+        .flatten()
+        .collect()
+}
+
+// 记住已经报告过的端口，每次只返回新出现的
+pub struct PortWatcher {
+    terminal_id: String,
+    seen: HashSet<(u16, Protocol, u32)>,
+}
+
+impl PortWatcher {
+    pub fn new(terminal_id: &str) -> Self {
+        PortWatcher {
+            terminal_id: terminal_id.to_string(),
+            seen: HashSet::new(),
+        }
+    }
+
+    // tree 是命令的进程树，根在最前面
+    pub fn poll(&mut self, tree: &[ProcStat]) -> Vec<PortOpened> {
+        let owners: HashMap<u64, u32> = tree
+            .iter()
+            .flat_map(|p| procfs::socket_inodes(p.pid).into_iter().map(move |inode| (inode, p.pid)))
+            .collect();
+        if owners.is_empty() {
+            return Vec::new();
+        }
+        let mut opened = Vec::new();
+        for socket in listening_sockets(tree[0].pid) {
+            let pid = match owners.get(&socket.inode) {
+                Some(pid) => *pid,
+                None => continue,
+            };
+            // 同一个端口同时监听 IPv4 和 IPv6 时只报告一次
+            if self.seen.insert((socket.port, socket.protocol, pid)) {
+                opened.push(PortOpened {
+                    terminal_id: self.terminal_id.clone(),
+                    port: socket.port,
+                    protocol: socket.protocol,
+                    pid,
+                    address: socket.address,
+                });
+            }
+        }
+        opened
+    }
+}
@@ -1,46 +1,30 @@
 use serde::Serialize;
+use std::collections::HashMap;
 use std::process::{Child, Command};
 use std::env;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use lazy_static::lazy_static;
-use std::collections::HashMap;
-use once_cell::sync::Lazy;
-use std::io::{BufReader, Read};
+use std::io;
 use tauri::Runtime;
-use regex::Regex;
-use serde_json;
+use super::engine::{self, kill_error};
+use super::error::{TerminalError, TerminalResult};
+use super::guard::{self, GuardVerdict};
+use super::limits::ResourceLimits;
+use super::policy;
+use super::session::{self, lock_recover, SessionInfo};
+use super::shutdown;
+use super::sink::{OutputSink, WindowSink};
 #[cfg(unix)]
-use std::os::unix::process::ExitStatusExt;
-#[cfg(windows)]
-use std::os::windows::process::ExitStatusExt;
+use super::daemon;
+#[cfg(unix)]
+use serde_json::json;
 
 lazy_static! {
-    static ref CURRENT_DIR: Mutex<PathBuf> = Mutex::new(
-        env::var("HOME").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("/"))
-    );
-    static ref RUNNING_PROCESSES: Mutex<HashMap<String, std::process::Child>> = Mutex::new(HashMap::new());
+    // execute_command 没有终端 id，使用这个共享目录
+    static ref CURRENT_DIR: Mutex<PathBuf> = Mutex::new(session::home_dir());
 }
 
-// 存储所有终端进程的全局 HashMap
-static TERMINAL_PROCESSES: Lazy<Mutex<HashMap<String, TerminalProcess>>> = 
-    Lazy::new(|| Mutex::new(HashMap::new()));
-
-// 用于匹配ANSI转义序列的正则表达式
-static ANSI_ESCAPE_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(\x9B|\x1B\[)[0-?]*[ -/]*[@-~]").unwrap()
-});
-
-// 用于提取进度信息的正则表达式
-static PROGRESS_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"([\d.]+\s*(?:GB|MB|KB)/[\d.]+\s*(?:GB|MB|KB)|[\d.]+%|[\d.]+\s*(?:GB|MB|KB)/s)").unwrap()
-});
-
-// 用于匹配状态信息的正则表达式
-static STATUS_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"pulling (?:manifest|[0-9a-f]{12})\.\.\.").unwrap()
-});
-
 #[derive(Serialize)]
 pub struct CommandOutput {
     pub stdout: String,
@@ -48,13 +32,12 @@ pub struct CommandOutput {
     pub current_dir: String,
 }
 
-#[derive(Serialize, Clone)]
-pub struct StreamOutput {
-    pub content: String,
-    pub output_type: String,
-    pub current_dir: String,
-    pub should_replace_last: bool,
-    pub terminalId: String,
+// execute_command_stream 的结果：危险命令在确认之前不会执行
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ExecuteOutcome {
+    Completed,
+    NeedsConfirmation(GuardVerdict),
 }
 
 #[allow(dead_code)]
@@ -64,21 +47,23 @@ pub struct TerminalProcess {
 }
 
 impl TerminalProcess {
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> TerminalResult<Self> {
         let home_dir = env::var("HOME").unwrap_or_default();
         let current_dir = PathBuf::from(&home_dir);
 
         #[cfg(target_os = "macos")]
-        let process = Command::new("zsh")
-            .current_dir(&current_dir)
-            .spawn()
-            .map_err(|e| e.to_string())?;
-
+        let shell = "zsh";
         #[cfg(not(target_os = "macos"))]
-        let process = Command::new("bash")
-            .current_dir(&current_dir)
+        let shell = "bash";
+
+        let mut command = Command::new(shell);
+        command.current_dir(&current_dir);
+        // 每个终端进程单独一个进程组，退出时可以整组终止
+        #[cfg(unix)]
+        engine::new_process_group(&mut command);
+        let process = command
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| TerminalError::spawn(shell, e))?;
 
         Ok(TerminalProcess {
             process,
@@ -86,41 +71,26 @@ impl TerminalProcess {
         })
     }
 
-    pub fn kill(&mut self) -> Result<(), String> {
-        self.process.kill().map_err(|e| e.to_string())
+    // shell 是自己进程组的组长，和命令一样终止整个进程树
+    pub fn kill(&mut self) -> io::Result<()> {
+        shutdown::kill_tree(&mut self.process).map(|_| ())
+    }
+
+    pub fn into_child(self) -> Child {
+        self.process
     }
 }
 
 #[tauri::command]
-pub async fn execute_command(command: &str) -> Result<CommandOutput, String> {
-    let mut current_dir = CURRENT_DIR.lock().unwrap();
+pub async fn execute_command(command: &str) -> TerminalResult<CommandOutput> {
+    let mut current_dir = lock_recover(&CURRENT_DIR);
+    // 没有终端 id，按默认 profile 检查
+    policy::enforce("", None, command, &current_dir)?;
     
     if command.trim().starts_with("cd") {
         let parts: Vec<&str> = command.trim().splitn(2, ' ').collect();
         let new_dir = parts.get(1).map(|s| s.trim()).unwrap_or("~");
-
-        let new_path = if new_dir == "~" {
-            PathBuf::from(env::var("HOME").unwrap_or_default())
-        } else if new_dir.starts_with('~') {
-            let home = env::var("HOME").unwrap_or_default();
-            let without_tilde = &new_dir[1..];
-            if without_tilde.is_empty() {
-                PathBuf::from(home)
-            } else {
-                PathBuf::from(home).join(&without_tilde[1..])
-            }
-        } else if new_dir.starts_with('/') {
-            PathBuf::from(new_dir)
-        } else {
-            // 处理相对路径，包括 .. 和 .
-            let mut new_path = current_dir.clone();
-            new_path.push(new_dir);
-            if let Ok(canonicalized) = new_path.canonicalize() {
-                canonicalized
-            } else {
-                new_path
-            }
-        };
+        let new_path = engine::resolve_directory(&current_dir, new_dir);
 
         if new_path.exists() && new_path.is_dir() {
             *current_dir = new_path;
@@ -138,12 +108,12 @@ pub async fn execute_command(command: &str) -> Result<CommandOutput, String> {
         }
     }
 
-    let output = Command::new("zsh")
+    let output = Command::new(engine::DEFAULT_SHELL.as_str())
         .current_dir(&*current_dir)
         .arg("-c")
         .arg(command)
         .output()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| TerminalError::spawn(&engine::DEFAULT_SHELL, e))?;
 
     Ok(CommandOutput {
         stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
@@ -152,7 +122,7 @@ pub async fn execute_command(command: &str) -> Result<CommandOutput, String> {
     })
 }
 
-fn format_current_dir(path: &Path) -> String {
+pub fn format_current_dir(path: &Path) -> String {
     let home = env::var("HOME").unwrap_or_default();
     if let Ok(canonical_path) = path.canonicalize() {
         let path_str = canonical_path.to_string_lossy();
@@ -167,425 +137,112 @@ fn format_current_dir(path: &Path) -> String {
 }
 
 #[tauri::command]
-pub async fn create_terminal(id: String) -> Result<(), String> {
-    let mut processes = TERMINAL_PROCESSES.lock().unwrap();
-    if !processes.contains_key(&id) {
-        let process = TerminalProcess::new()?;
-        processes.insert(id, process);
+pub async fn create_terminal(id: String) -> TerminalResult<()> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        return run_blocking(move || client.call("create", json!({ "terminalId": id })).map(|_| ())).await;
+    }
+    let handle = session::get_or_create(&id);
+    let mut session = session::lock(&id, &handle)?;
+    if session.shell.is_none() {
+        session.shell = Some(TerminalProcess::new()?);
     }
     Ok(())
 }
 
 #[tauri::command]
-pub async fn close_terminal(id: String) -> Result<(), String> {
-    let mut processes = TERMINAL_PROCESSES.lock().unwrap();
-    if let Some(mut process) = processes.remove(&id) {
-        process.kill()?;
+pub async fn close_terminal(id: String) -> TerminalResult<()> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        return run_blocking(move || client.call("close", json!({ "terminalId": id })).map(|_| ())).await;
     }
-    Ok(())
+    let handle = session::remove(&id)
+        .ok_or_else(|| TerminalError::TerminalNotFound(id.clone()))?;
+    let mut session = session::lock(&id, &handle)?;
+    // 和 stop 一样终止整个进程组，包装程序下面的真正命令及其子进程不会留下来
+    if let Some(mut child) = session.running.take() {
+        let _ = shutdown::kill_tree(&mut child);
+    }
+    match session.shell.take() {
+        Some(mut process) => process.kill().map_err(|e| kill_error(&id, e)),
+        None => Ok(()),
+    }
+}
+
+// 在后台线程运行阻塞的引擎调用，避免占用异步运行时
+pub(crate) async fn run_blocking<T, F>(f: F) -> TerminalResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> TerminalResult<T> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| TerminalError::Io(io::Error::new(io::ErrorKind::Other, e.to_string())))?
 }
 
 #[tauri::command]
 pub async fn execute_command_stream<R: Runtime>(
     window: tauri::Window<R>,
     command: String,
-    terminalId: String,
-) -> Result<(), String> {
-    let current_dir = CURRENT_DIR.lock().unwrap().clone();
-    
-    if command.trim().starts_with("cd") {
-        return handle_cd_command(&command, window, &terminalId).await;
+    terminal_id: String,
+    confirmed: Option<bool>,
+    limits: Option<ResourceLimits>,
+) -> TerminalResult<ExecuteOutcome> {
+    // 危险命令先返回原因，用户确认后前端带上 confirmed 再次调用
+    if let Some(verdict) = guard::check(&command, confirmed.unwrap_or(false))? {
+        return Ok(ExecuteOutcome::NeedsConfirmation(verdict));
     }
 
-    // 检查命令是否是下载相关命令
-    let is_download_command = command.contains("ollama") || command.contains("curl") || command.contains("wget");
-
-    // 检查是否安装了unbuffer
-    let has_unbuffer = Command::new("which")
-        .arg("unbuffer")
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false);
-
-    let mut child = if has_unbuffer {
-        Command::new("unbuffer")
-            .current_dir(&current_dir)
-            .arg("zsh")
-            .arg("-c")
-            .arg(&command)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn()
-    } else {
-        Command::new("script")
-            .current_dir(&current_dir)
-            .arg("-q")
-            .arg("/dev/null")
-            .arg("zsh")
-            .arg("-c")
-            .arg(&command)
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .spawn()
-    }.map_err(|e| e.to_string())?;
-
-    // 在存储到RUNNING_PROCESSES之前，先获取stdout和stderr
-    let stdout = child.stdout.take()
-        .ok_or_else(|| "Failed to capture stdout".to_string())?;
-    let stderr = child.stderr.take()
-        .ok_or_else(|| "Failed to capture stderr".to_string())?;
-
-    // 将进程存储到全局HashMap中
-    RUNNING_PROCESSES.lock().unwrap().insert(terminalId.clone(), child);
-
-    let window_clone = window.clone();
-    let current_dir_str = format_current_dir(&current_dir);
-    let terminal_id_clone = terminalId.clone();
-
-    // Handle stdout in a separate task
-    let stdout_task = {
-        let window = window_clone.clone();
-        let current_dir = current_dir_str.clone();
-        let terminal_id = terminal_id_clone.clone();
-        tauri::async_runtime::spawn(async move {
-            let mut reader = BufReader::new(stdout);
-            let mut buffer = [0u8; 1024];
-            let mut current_line = String::new();
-            let mut progress_info = String::new();
-            let mut status_info = String::new();
-            let mut is_progress_line = false;
-            let mut last_status = String::new();
-
-            loop {
-                match reader.read(&mut buffer) {
-                    Ok(0) => break, // EOF
-                    Ok(n) => {
-                        let chunk = String::from_utf8_lossy(&buffer[..n]);
-                        let cleaned_chunk = ANSI_ESCAPE_RE.replace_all(&chunk, "");
-                        
-                        for c in cleaned_chunk.chars() {
-                            match c {
-                                '\r' | '\n' => {
-                                    if !current_line.is_empty() {
-                                        if is_download_command {
-                                            let mut new_progress = String::new();
-                                            let mut new_status = String::new();
-                                            
-                                            // 提取进度信息
-                                            for cap in PROGRESS_RE.find_iter(&current_line) {
-                                                new_progress.push_str(cap.as_str());
-                                                new_progress.push(' ');
-                                            }
-                                            
-                                            // 提取状态信息
-                                            if let Some(status_match) = STATUS_RE.find(&current_line) {
-                                                new_status = status_match.as_str().to_string();
-                                            }
-                                            
-                                            // 更新状态信息（只有当状态改变时）
-                                            if !new_status.is_empty() && new_status != last_status {
-                                                last_status = new_status.clone();
-                                                let _ = window.emit("terminal-output", StreamOutput {
-                                                    content: new_status,
-                                                    output_type: "stdout".to_string(),
-                                                    current_dir: current_dir.clone(),
-                                                    should_replace_last: false,
-                                                    terminalId: terminal_id.clone(),
-                                                });
-                                            }
-                                            
-                                            // 更新进度信息
-                                            if !new_progress.is_empty() {
-                                                progress_info = new_progress;
-                                                is_progress_line = true;
-                                                
-                                                let _ = window.emit("terminal-output", StreamOutput {
-                                                    content: progress_info.clone(),
-                                                    output_type: "stdout".to_string(),
-                                                    current_dir: current_dir.clone(),
-                                                    should_replace_last: true,
-                                                    terminalId: terminal_id.clone(),
-                                                });
-                                            } else if !current_line.trim().is_empty() && 
-                                                     !STATUS_RE.is_match(&current_line) {
-                                                // 如果不是进度信息也不是状态信息，且不是空行，正常发送
-                                                let _ = window.emit("terminal-output", StreamOutput {
-                                                    content: current_line.clone(),
-                                                    output_type: "stdout".to_string(),
-                                                    current_dir: current_dir.clone(),
-                                                    should_replace_last: false,
-                                                    terminalId: terminal_id.clone(),
-                                                });
-                                            }
-                                        } else {
-                                            // 非下载命令，正常发送
-                                            let _ = window.emit("terminal-output", StreamOutput {
-                                                content: current_line.clone(),
-                                                output_type: "stdout".to_string(),
-                                                current_dir: current_dir.clone(),
-                                                should_replace_last: false,
-                                                terminalId: terminal_id.clone(),
-                                            });
-                                        }
-                                    }
-                                    current_line.clear();
-                                }
-                                _ => current_line.push(c),
-                            }
-                        }
-                        
-                        // 如果还有未发送的内容且不是进度信息，发送它
-                        if !current_line.is_empty() && !is_progress_line && 
-                           !STATUS_RE.is_match(&current_line) {
-                            let _ = window.emit("terminal-output", StreamOutput {
-                                content: current_line.clone(),
-                                output_type: "stdout".to_string(),
-                                current_dir: current_dir.clone(),
-                                should_replace_last: false,
-                                terminalId: terminal_id.clone(),
-                            });
-                        }
-                    }
-                    Err(e) => {
-                        let _ = window.emit("terminal-output", StreamOutput {
-                            content: format!("Error reading stdout: {}", e),
-                            output_type: "stderr".to_string(),
-                            current_dir: current_dir.clone(),
-                            should_replace_last: false,
-                            terminalId: terminal_id.clone(),
-                        });
-                        break;
-                    }
-                }
-            }
-        })
-    };
-
-    // Handle stderr in a similar way
-    let stderr_task = {
-        let window = window_clone;
-        let current_dir = current_dir_str;
-        let terminal_id = terminal_id_clone;
-        tauri::async_runtime::spawn(async move {
-            let mut reader = BufReader::new(stderr);
-            let mut buffer = [0u8; 1024];
-            let mut current_line = String::new();
-            let mut last_status = String::new();
-            let mut is_progress_line = false;
-
-            loop {
-                match reader.read(&mut buffer) {
-                    Ok(0) => break, // EOF
-                    Ok(n) => {
-                        let chunk = String::from_utf8_lossy(&buffer[..n]);
-                        let cleaned_chunk = ANSI_ESCAPE_RE.replace_all(&chunk, "");
-                        
-                        for c in cleaned_chunk.chars() {
-                            match c {
-                                '\r' | '\n' => {
-                                    if !current_line.is_empty() {
-                                        if is_download_command {
-                                            // 检查是否是状态信息
-                                            if let Some(status_match) = STATUS_RE.find(&current_line) {
-                                                let new_status = status_match.as_str().to_string();
-                                                if new_status != last_status {
-                                                    last_status = new_status.clone();
-                                                    let _ = window.emit("terminal-output", StreamOutput {
-                                                        content: new_status,
-                                                        output_type: "stderr".to_string(),
-                                                        current_dir: current_dir.clone(),
-                                                        should_replace_last: false,
-                                                        terminalId: terminal_id.clone(),
-                                                    });
-                                                }
-                                            } else if PROGRESS_RE.is_match(&current_line) {
-                                                is_progress_line = true;
-                                                let _ = window.emit("terminal-output", StreamOutput {
-                                                    content: current_line.clone(),
-                                                    output_type: "stderr".to_string(),
-                                                    current_dir: current_dir.clone(),
-                                                    should_replace_last: true,
-                                                    terminalId: terminal_id.clone(),
-                                                });
-                                            } else if !current_line.trim().is_empty() {
-                                                let _ = window.emit("terminal-output", StreamOutput {
-                                                    content: current_line.clone(),
-                                                    output_type: "stderr".to_string(),
-                                                    current_dir: current_dir.clone(),
-                                                    should_replace_last: false,
-                                                    terminalId: terminal_id.clone(),
-                                                });
-                                            }
-                                        } else {
-                                            let _ = window.emit("terminal-output", StreamOutput {
-                                                content: current_line.clone(),
-                                                output_type: "stderr".to_string(),
-                                                current_dir: current_dir.clone(),
-                                                should_replace_last: false,
-                                                terminalId: terminal_id.clone(),
-                                            });
-                                        }
-                                    }
-                                    current_line.clear();
-                                }
-                                _ => current_line.push(c),
-                            }
-                        }
-                        
-                        if !current_line.is_empty() && !is_progress_line && 
-                           !STATUS_RE.is_match(&current_line) {
-                            let _ = window.emit("terminal-output", StreamOutput {
-                                content: current_line.clone(),
-                                output_type: "stderr".to_string(),
-                                current_dir: current_dir.clone(),
-                                should_replace_last: false,
-                                terminalId: terminal_id.clone(),
-                            });
-                        }
-                    }
-                    Err(e) => {
-                        let _ = window.emit("terminal-output", StreamOutput {
-                            content: format!("Error reading stderr: {}", e),
-                            output_type: "stderr".to_string(),
-                            current_dir: current_dir.clone(),
-                            should_replace_last: false,
-                            terminalId: terminal_id.clone(),
-                        });
-                        break;
-                    }
-                }
-            }
-        })
-    };
-
-    // Wait for output handlers to complete first
-    let _ = stdout_task.await;
-    let _ = stderr_task.await;
-
-    // Then handle the process completion
-    let status = {
-        let mut processes = RUNNING_PROCESSES.lock().unwrap();
-        match processes.remove(&terminalId) {
-            Some(mut child) => {
-                match child.wait().map_err(|e| e.to_string()) {
-                    Ok(status) => Ok(status),
-                    Err(e) => {
-                        // 如果进程已经被终止，静默处理这个错误
-                        if e.contains("No such process") || e.contains("Process not found") {
-                            #[cfg(unix)]
-                            {
-                                Ok(ExitStatusExt::from_raw(1))
-                            }
-                            #[cfg(windows)]
-                            {
-                                Ok(ExitStatusExt::from_raw(1 << 8))
-                            }
-                        } else {
-                            Err(e)
-                        }
-                    }
-                }
-            },
-            None => {
-                // 如果进程不存在（可能已经被停止），返回一个表示中断的状态
-                #[cfg(unix)]
-                {
-                    Ok(ExitStatusExt::from_raw(1))
-                }
-                #[cfg(windows)]
-                {
-                    Ok(ExitStatusExt::from_raw(1 << 8))
-                }
-            }
-        }?
-    };
-
-    // Emit command completion event with terminal ID
-    let _ = window.emit("terminal-command-complete", serde_json::json!({
-        "terminalId": terminalId,
-        "code": status.code()
-    }));
-
-    Ok(())
+    // 连接了会话守护进程时由它运行命令，输出通过守护进程的事件转发
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        let params = json!({ "terminalId": terminal_id, "command": command, "limits": limits });
+        return run_blocking(move || client.call("execute", params).map(|_| ExecuteOutcome::Completed)).await;
+    }
+    let sink: Arc<dyn OutputSink> = Arc::new(WindowSink::new(window));
+    let limits = limits.unwrap_or_default();
+    run_blocking(move || {
+        engine::execute_with_limits(sink, &terminal_id, &command, limits).map(|_| ExecuteOutcome::Completed)
+    })
+    .await
 }
 
-async fn handle_cd_command<R: Runtime>(
-    command: &str,
+#[tauri::command]
+pub async fn stop_command<R: Runtime>(
     window: tauri::Window<R>,
-    terminal_id: &str,
-) -> Result<(), String> {
-    let mut current_dir = CURRENT_DIR.lock().unwrap();
-    let parts: Vec<&str> = command.trim().splitn(2, ' ').collect();
-    let new_dir = parts.get(1).map(|s| s.trim()).unwrap_or("~");
-
-    let new_path = if new_dir == "~" {
-        PathBuf::from(env::var("HOME").unwrap_or_default())
-    } else if new_dir.starts_with('~') {
-        let home = env::var("HOME").unwrap_or_default();
-        let without_tilde = &new_dir[1..];
-        if without_tilde.is_empty() {
-            PathBuf::from(home)
-        } else {
-            PathBuf::from(home).join(&without_tilde[1..])
-        }
-    } else if new_dir.starts_with('/') {
-        PathBuf::from(new_dir)
-    } else {
-        let mut new_path = current_dir.clone();
-        new_path.push(new_dir);
-        if let Ok(canonicalized) = new_path.canonicalize() {
-            canonicalized
-        } else {
-            new_path
-        }
-    };
+    terminal_id: String
+) -> TerminalResult<()> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        return run_blocking(move || client.call("stop", json!({ "terminalId": terminal_id })).map(|_| ())).await;
+    }
+    engine::stop(&WindowSink::new(window), &terminal_id)
+}
 
-    if new_path.exists() && new_path.is_dir() {
-        *current_dir = new_path;
-        let current_dir_str = format_current_dir(&current_dir);
-        let _ = window.emit("terminal-output", StreamOutput {
-            content: String::new(),
-            output_type: "stdout".to_string(),
-            current_dir: current_dir_str,
-            should_replace_last: false,
-            terminalId: terminal_id.to_string(),
-        });
-        Ok(())
-    } else {
-        let error_msg = format!("cd: no such directory: {}", new_dir);
-        let current_dir_str = format_current_dir(&current_dir);
-        let _ = window.emit("terminal-output", StreamOutput {
-            content: error_msg,
-            output_type: "stderr".to_string(),
-            current_dir: current_dir_str,
-            should_replace_last: false,
-            terminalId: terminal_id.to_string(),
-        });
-        Ok(())
+// 终端的工作目录和环境变量；连接了守护进程时会话只存在于守护进程里
+pub(crate) async fn session_context(terminal_id: String) -> TerminalResult<(PathBuf, HashMap<String, String>)> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        return run_blocking(move || {
+            let value = client.call("context", json!({ "terminalId": terminal_id }))?;
+            serde_json::from_value(value).map_err(|e| TerminalError::Daemon(e.to_string()))
+        })
+        .await;
     }
+    session::with_existing(&terminal_id, |s| (s.current_dir.clone(), s.env.clone()))
 }
 
+// 列出现有会话及最近的输出，应用重新连接守护进程后用来恢复标签页
 #[tauri::command]
-pub async fn stop_command<R: Runtime>(
-    window: tauri::Window<R>,
-    terminal_id: String
-) -> Result<(), String> {
-    let mut processes = RUNNING_PROCESSES.lock().map_err(|e| e.to_string())?;
-    if let Some(mut child) = processes.remove(&terminal_id) {
-        child.kill().map_err(|e| e.to_string())?;
-        
-        // 获取当前目录用于输出消息
-        let current_dir = CURRENT_DIR.lock().unwrap();
-        let current_dir_str = format_current_dir(&current_dir);
-        
-        // 发送用户停止的消息
-        let _ = window.emit("terminal-output", StreamOutput {
-            content: "User has stopped the command".to_string(),
-            output_type: "stderr".to_string(),
-            current_dir: current_dir_str,
-            should_replace_last: false,
-            terminalId: terminal_id,
-        });
+pub async fn list_sessions(lines: Option<usize>) -> TerminalResult<Vec<SessionInfo>> {
+    let lines = lines.unwrap_or(0);
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        return run_blocking(move || {
+            let value = client.call("sessions", json!({ "lines": lines }))?;
+            serde_json::from_value(value).map_err(|e| TerminalError::Daemon(e.to_string()))
+        })
+        .await;
     }
-    Ok(())
-} 
\ No newline at end of file
+    Ok(session::infos(lines))
+}
@@ -0,0 +1,142 @@
+// 查看正在运行的命令的进程树（读取 /proc），并向其中的单个进程发送信号。
+// 命令卡住时可以看到它实际在运行什么。
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+#[cfg(unix)]
+use serde_json::json;
+
+#[cfg(unix)]
+use super::daemon;
+use super::error::{TerminalError, TerminalResult};
+use super::procfs::{self, ProcStat};
+use super::process::run_blocking;
+use super::session;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProcessNode {
+    pub pid: u32,
+    pub ppid: u32,
+    pub name: String,
+    pub argv: Vec<String>,
+    // R 运行、S 睡眠、D 不可中断、T 已停止、Z 僵尸
+    pub state: char,
+    pub cwd: Option<PathBuf>,
+    // 启动时间（Unix 秒）
+    pub start_time: Option<u64>,
+    pub children: Vec<ProcessNode>,
+}
+
+// 可以发送的信号，名称可以带 SIG 前缀
+#[cfg(unix)]
+const SIGNALS: &[(&str, libc::c_int)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+    ("TERM", libc::SIGTERM),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+];
+
+#[cfg(unix)]
+pub fn parse_signal(name: &str) -> Option<libc::c_int> {
+    let name = name.trim().to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    SIGNALS.iter().find(|(n, _)| *n == name).map(|(_, sig)| *sig)
+}
+
+// 把 procfs::tree 的结果（根在最前面）组装成嵌套结构
+pub fn build(processes: &[ProcStat]) -> Option<ProcessNode> {
+    let root = processes.first()?;
+    let mut children: HashMap<u32, Vec<&ProcStat>> = HashMap::new();
+    for process in &processes[1..] {
+        children.entry(process.ppid).or_default().push(process);
+    }
+    Some(node(root, &children))
+}
+
+fn node(stat: &ProcStat, children: &HashMap<u32, Vec<&ProcStat>>) -> ProcessNode {
+    ProcessNode {
+        pid: stat.pid,
+        ppid: stat.ppid,
+        name: stat.comm.clone(),
+        argv: procfs::cmdline(stat.pid),
+        state: stat.state,
+        cwd: procfs::cwd(stat.pid),
+        start_time: procfs::started_at(stat),
+        children: children
+            .get(&stat.pid)
+            .map(|kids| kids.iter().map(|kid| node(kid, children)).collect())
+            .unwrap_or_default(),
+    }
+}
+
+// 不存在的终端当作没有正在运行的命令，查询不会创建会话
+fn running_pid(terminal_id: &str) -> TerminalResult<Option<u32>> {
+    match session::with_existing(terminal_id, |s| s.running.as_ref().map(|child| child.id())) {
+        Err(TerminalError::TerminalNotFound(_)) => Ok(None),
+        result => result,
+    }
+}
+
+// 终端当前命令的进程树；没有正在运行的命令时返回 None
+pub fn process_tree(terminal_id: &str) -> TerminalResult<Option<ProcessNode>> {
+    Ok(running_pid(terminal_id)?.and_then(|pid| build(&procfs::tree(pid))))
+}
+
+// 只允许向当前命令进程树中的进程发送信号
+#[cfg(unix)]
+pub fn signal_process(terminal_id: &str, pid: u32, signal: &str) -> TerminalResult<()> {
+    let sig = parse_signal(signal)
+        .ok_or_else(|| TerminalError::NotSupported(format!("unknown signal '{}'", signal)))?;
+    let root = running_pid(terminal_id)?.ok_or_else(|| TerminalError::ProcessExited(terminal_id.to_string()))?;
+    if !procfs::tree(root).iter().any(|p| p.pid == pid) {
+        return Err(TerminalError::PermissionDenied(format!(
+            "process {} does not belong to the command running in terminal {}",
+            pid, terminal_id
+        )));
+    }
+    if unsafe { libc::kill(pid as libc::pid_t, sig) } == 0 {
+        return Ok(());
+    }
+    let err = std::io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::ESRCH) {
+        Err(TerminalError::ProcessExited(terminal_id.to_string()))
+    } else {
+        Err(err.into())
+    }
+}
+
+#[cfg(not(unix))]
+pub fn signal_process(_terminal_id: &str, _pid: u32, _signal: &str) -> TerminalResult<()> {
+    Err(TerminalError::NotSupported("sending signals requires a Unix system".to_string()))
+}
+
+#[tauri::command]
+pub async fn get_process_tree(terminal_id: String) -> TerminalResult<Option<ProcessNode>> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        let params = json!({ "terminalId": terminal_id });
+        return run_blocking(move || {
+            let value = client.call("process_tree", params)?;
+            serde_json::from_value(value).map_err(|e| TerminalError::Daemon(e.to_string()))
+        })
+        .await;
+    }
+    run_blocking(move || process_tree(&terminal_id)).await
+}
+
+#[tauri::command]
+pub async fn send_process_signal(terminal_id: String, pid: u32, signal: String) -> TerminalResult<()> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        let params = json!({ "terminalId": terminal_id, "pid": pid, "signal": signal });
+        return run_blocking(move || client.call("signal", params).map(|_| ())).await;
+    }
+    signal_process(&terminal_id, pid, &signal)
+}
@@ -0,0 +1,186 @@
+// 读取 /proc 下的进程信息（只在 Linux 上有数据，其他平台返回空）。
+// 资源统计、进程树查看等功能共用这里的解析。
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcStat {
+    pub pid: u32,
+    pub ppid: u32,
+    // 可执行文件名，最多 15 个字符
+    pub comm: String,
+    pub state: char,
+    pub pgrp: i32,
+    // 控制终端的前台进程组
+    pub tpgid: i32,
+    // 以下时间单位为时钟周期
+    pub utime: u64,
+    pub stime: u64,
+    // 已经被回收的子进程的 CPU 时间
+    pub cutime: u64,
+    pub cstime: u64,
+    pub num_threads: u64,
+    // 系统启动后多少个时钟周期启动
+    pub start_time: u64,
+    pub rss_pages: u64,
+}
+
+impl ProcStat {
+    // 自身和已回收子进程的 CPU 时间之和
+    pub fn total_ticks(&self) -> u64 {
+        self.utime + self.stime + self.cutime + self.cstime
+    }
+}
+
+// 解析 /proc/<pid>/stat；进程名可能包含空格和括号，以最后一个 ) 为界
+pub fn parse_stat(content: &str) -> Option<ProcStat> {
+    let open = content.find('(')?;
+    let close = content.rfind(')')?;
+    let pid = content[..open].trim().parse().ok()?;
+    let comm = content.get(open + 1..close)?.to_string();
+    let fields: Vec<&str> = content.get(close + 1..)?.split_whitespace().collect();
+    // fields[0] 是 stat 中的第 3 个字段
+    let field = |n: usize| fields.get(n - 3).copied();
+    let number = |n: usize| field(n).and_then(|v| v.parse::<u64>().ok());
+    Some(ProcStat {
+        pid,
+        ppid: number(4)? as u32,
+        comm,
+        state: field(3)?.chars().next()?,
+        pgrp: field(5)?.parse().ok()?,
+        tpgid: field(8)?.parse().ok()?,
+        utime: number(14)?,
+        stime: number(15)?,
+        cutime: number(16)?,
+        cstime: number(17)?,
+        num_threads: number(20)?,
+        start_time: number(22)?,
+        rss_pages: number(24)?,
+    })
+}
+
+pub fn stat(pid: u32) -> Option<ProcStat> {
+    parse_stat(&fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?)
+}
+
+// 当前所有进程
+pub fn all() -> Vec<ProcStat> {
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(stat)
+        .collect()
+}
+
+// root 及其所有后代，root 在最前面；root 不存在时返回空
+pub fn tree(root: u32) -> Vec<ProcStat> {
+    tree_from(root, all())
+}
+
+pub fn tree_from(root: u32, processes: Vec<ProcStat>) -> Vec<ProcStat> {
+    let mut children: HashMap<u32, Vec<ProcStat>> = HashMap::new();
+    let mut result = Vec::new();
+    for process in processes {
+        if process.pid == root {
+            result.push(process);
+        } else {
+            children.entry(process.ppid).or_default().push(process);
+        }
+    }
+    if result.is_empty() {
+        return result;
+    }
+    let mut i = 0;
+    while i < result.len() {
+        if let Some(mut kids) = children.remove(&result[i].pid) {
+            kids.sort_by_key(|p| p.pid);
+            result.extend(kids);
+        }
+        i += 1;
+    }
+    result
+}
+
+// 启动参数；内核线程或者没有权限时为空
+pub fn cmdline(pid: u32) -> Vec<String> {
+    let content = fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+    content
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+pub fn cwd(pid: u32) -> Option<PathBuf> {
+    fs::read_link(format!("/proc/{}/cwd", pid)).ok()
+}
+
+// 进程打开的 socket 的 inode（/proc/<pid>/fd 中的 socket:[N]）
+pub fn socket_inodes(pid: u32) -> Vec<u64> {
+    let entries = match fs::read_dir(format!("/proc/{}/fd", pid)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| {
+            let target = fs::read_link(entry.ok()?.path()).ok()?;
+            let target = target.to_str()?;
+            target.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+        })
+        .collect()
+}
+
+// 系统启动时间（Unix 秒），用来把 start_time 换算成时间戳
+static BOOT_TIME: Lazy<Option<u64>> = Lazy::new(|| {
+    fs::read_to_string("/proc/stat")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|v| v.trim().parse().ok())
+});
+
+// 进程启动的 Unix 时间戳（秒）
+pub fn started_at(stat: &ProcStat) -> Option<u64> {
+    Some((*BOOT_TIME)? + stat.start_time / clock_ticks())
+}
+
+// /proc/<pid>/io 中实际读写磁盘的字节数；没有权限时返回 None
+pub fn io_bytes(pid: u32) -> Option<(u64, u64)> {
+    let content = fs::read_to_string(format!("/proc/{}/io", pid)).ok()?;
+    let value = |name: &str| {
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|v| v.trim().parse::<u64>().ok())
+    };
+    Some((value("read_bytes:")?, value("write_bytes:")?))
+}
+
+// 每秒的时钟周期数
+pub fn clock_ticks() -> u64 {
+    #[cfg(unix)]
+    {
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        if ticks > 0 {
+            return ticks as u64;
+        }
+    }
+    100
+}
+
+pub fn page_size() -> u64 {
+    #[cfg(unix)]
+    {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            return size as u64;
+        }
+    }
+    4096
+}
@@ -0,0 +1,216 @@
+// 沙箱模式：终端开启后，每条命令都在新的 user/mount/pid（可选 network）命名空间里运行，
+// 根目录只读，只有项目目录和额外指定的目录可写，方便试运行克隆下来的仓库里的脚本。
+// 有 bubblewrap 时使用 bwrap，否则使用 util-linux 的 unshare 加一小段挂载脚本。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[cfg(unix)]
+use super::daemon;
+use super::engine;
+use super::error::{TerminalError, TerminalResult};
+use super::process::run_blocking;
+use super::session;
+#[cfg(unix)]
+use serde_json::json;
+
+// unshare 之后执行的挂载脚本。参数：可写目录…… -- 原命令
+// 先把可写目录绑定成独立的挂载点，再把其余挂载点重新挂成只读。
+// 重新挂载时保留原来的 nosuid、nodev 等选项（用户命名空间里这些选项被锁定，去掉会失败）；
+// 任何一个挂载点没能变成只读都终止命令，不在可写的环境里运行
+const UNSHARE_SETUP: &str = r#"
+set -e
+ro=$1; tmp=$2; shift 2
+writable=""
+while [ "$1" != "--" ]; do
+    mount --bind "$1" "$1"
+    writable="$writable
+$1"
+    shift
+done
+shift
+if [ "$tmp" = 1 ]; then
+    mount -t tmpfs tmpfs /tmp
+fi
+if [ "$ro" = 1 ]; then
+    awk '{print $5, $6}' /proc/self/mountinfo | while read -r m opts; do
+        m=$(printf '%b' "$m")
+        case "$m" in /proc|/proc/*|/dev|/dev/*|/sys|/sys/*) continue ;; esac
+        if [ "$tmp" = 1 ] && [ "$m" = /tmp ]; then continue; fi
+        if printf '%s\n' "$writable" | grep -qxF -- "$m"; then continue; fi
+        if ! mount -o "remount,bind,$opts,ro" "$m"; then
+            echo "landingterm sandbox: could not make $m read-only" >&2
+            exit 1
+        fi
+    done
+fi
+# 重新进入工作目录，落到新的挂载点上
+cd "$(pwd -P)"
+exec "$@"
+"#;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxBackend {
+    Bubblewrap,
+    Unshare,
+}
+
+// 优先 bubblewrap；只在 Linux 上可用
+static BACKEND: Lazy<Option<SandboxBackend>> = Lazy::new(|| {
+    if !cfg!(target_os = "linux") {
+        None
+    } else if engine::has_program("bwrap") {
+        Some(SandboxBackend::Bubblewrap)
+    } else if engine::has_program("unshare") {
+        Some(SandboxBackend::Unshare)
+    } else {
+        None
+    }
+});
+
+pub fn backend() -> Option<SandboxBackend> {
+    *BACKEND
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SandboxConfig {
+    // 可写的项目目录，为空时使用命令的工作目录
+    pub project_dir: Option<PathBuf>,
+    // 额外的可写目录，例如 ~/.cache
+    pub writable_paths: Vec<PathBuf>,
+    #[serde(default = "default_true")]
+    pub read_only_root: bool,
+    #[serde(default = "default_true")]
+    pub allow_network: bool,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        SandboxConfig {
+            project_dir: None,
+            writable_paths: Vec::new(),
+            read_only_root: true,
+            allow_network: true,
+        }
+    }
+}
+
+impl SandboxConfig {
+    // 实际可写的目录，已去掉不存在的
+    fn writable(&self, cwd: &Path) -> Vec<PathBuf> {
+        let project = self.project_dir.clone().unwrap_or_else(|| cwd.to_path_buf());
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for dir in std::iter::once(project).chain(self.writable_paths.iter().cloned()) {
+            if let Ok(dir) = dir.canonicalize() {
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        dirs
+    }
+}
+
+// 根目录只读时 /tmp 换成空的 tmpfs；工作目录或可写目录在 /tmp 下时保留原来的 /tmp
+fn private_tmp(config: &SandboxConfig, cwd: &Path, writable: &[PathBuf]) -> bool {
+    let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf());
+    config.read_only_root && !cwd.starts_with("/tmp") && !writable.iter().any(|dir| dir.starts_with("/tmp"))
+}
+
+fn strs<'a>(args: &'a [&str]) -> impl Iterator<Item = OsString> + 'a {
+    args.iter().map(OsString::from)
+}
+
+// 沙箱命令行：把 inner 的程序和参数放到沙箱里运行
+pub fn sandbox_args(
+    backend: SandboxBackend,
+    config: &SandboxConfig,
+    cwd: &Path,
+    program: &str,
+    args: &[OsString],
+) -> (&'static str, Vec<OsString>) {
+    let writable = config.writable(cwd);
+    let tmp = private_tmp(config, cwd, &writable);
+    let mut out: Vec<OsString> = Vec::new();
+
+    let program_name = match backend {
+        SandboxBackend::Bubblewrap => {
+            out.extend(strs(&["--die-with-parent", "--unshare-user", "--unshare-pid", "--unshare-ipc"]));
+            if !config.allow_network {
+                out.extend(strs(&["--unshare-net"]));
+            }
+            let root = if config.read_only_root { "--ro-bind" } else { "--bind" };
+            out.extend(strs(&[root, "/", "/", "--dev", "/dev", "--proc", "/proc"]));
+            if tmp {
+                out.extend(strs(&["--tmpfs", "/tmp"]));
+            }
+            for dir in &writable {
+                out.extend([OsString::from("--bind"), dir.into(), dir.into()]);
+            }
+            out.extend([OsString::from("--chdir"), cwd.into(), OsString::from("--")]);
+            "bwrap"
+        }
+        SandboxBackend::Unshare => {
+            out.extend(strs(&["--user", "--map-root-user", "--mount", "--pid", "--fork", "--mount-proc", "--ipc"]));
+            if !config.allow_network {
+                out.extend(strs(&["--net"]));
+            }
+            let ro = if config.read_only_root { "1" } else { "0" };
+            let tmp = if tmp { "1" } else { "0" };
+            out.extend(strs(&["--", "sh", "-c", UNSHARE_SETUP, "landingterm-sandbox", ro, tmp]));
+            out.extend(writable.iter().map(OsString::from));
+            out.extend(strs(&["--"]));
+            "unshare"
+        }
+    };
+    out.push(program.into());
+    out.extend(args.iter().cloned());
+    (program_name, out)
+}
+
+// 把已经准备好的命令放进沙箱，环境变量和工作目录由调用方之后设置
+pub fn wrap(config: &SandboxConfig, cwd: &Path, inner: &Command) -> TerminalResult<(&'static str, Command)> {
+    let backend = backend().ok_or_else(unavailable)?;
+    let args: Vec<OsString> = inner.get_args().map(OsString::from).collect();
+    let program = inner.get_program().to_string_lossy().into_owned();
+    let (name, args) = sandbox_args(backend, config, cwd, &program, &args);
+    let mut command = Command::new(name);
+    command.args(args);
+    Ok((name, command))
+}
+
+fn unavailable() -> TerminalError {
+    TerminalError::NotSupported("sandbox mode requires Linux with bubblewrap or util-linux unshare".to_string())
+}
+
+// 开启或关闭终端的沙箱模式
+pub fn set_sandbox(terminal_id: &str, sandbox: Option<SandboxConfig>) -> TerminalResult<()> {
+    if let Some(config) = &sandbox {
+        backend().ok_or_else(unavailable)?;
+        if let Some(dir) = &config.project_dir {
+            if !dir.is_dir() {
+                return Err(TerminalError::NotSupported(format!("not a directory: {}", dir.display())));
+            }
+        }
+    }
+    session::with_session(terminal_id, |s| s.sandbox = sandbox)
+}
+
+#[tauri::command]
+pub async fn set_terminal_sandbox(terminal_id: String, sandbox: Option<SandboxConfig>) -> TerminalResult<()> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        let params = json!({ "terminalId": terminal_id, "sandbox": sandbox });
+        return run_blocking(move || client.call("sandbox", params).map(|_| ())).await;
+    }
+    set_sandbox(&terminal_id, sandbox)
+}
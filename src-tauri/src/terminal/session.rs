@@ -0,0 +1,283 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::error::{TerminalError, TerminalResult};
+use super::process::{format_current_dir, TerminalProcess};
+use super::sandbox::SandboxConfig;
+
+// 每个终端保留的输出行数
+pub const SCROLLBACK_LINES: usize = 2000;
+// 每个终端保留的命令块数量
+pub const BLOCK_LIMIT: usize = 200;
+
+// 一条命令及其输出，对应前端的命令块
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CommandBlock {
+    pub command: String,
+    pub directory: String,
+    pub output: Vec<String>,
+    pub code: Option<i32>,
+}
+
+// 单个终端的状态：工作目录、后台 shell 以及正在运行的命令
+pub struct Session {
+    pub current_dir: PathBuf,
+    pub shell: Option<TerminalProcess>,
+    pub running: Option<Child>,
+    // 命令已通过检查、正在启动，还没有存进 running
+    pub starting: bool,
+    // 正在运行的命令文本
+    pub command: Option<String>,
+    pub scrollback: VecDeque<String>,
+    // 标签页标题
+    pub title: Option<String>,
+    // 追加到该终端所有命令上的环境变量
+    pub env: HashMap<String, String>,
+    // 恢复会话时自动运行的命令
+    pub startup_command: Option<String>,
+    pub blocks: VecDeque<CommandBlock>,
+    // 使用的策略 profile，为空时使用配置里的默认 profile
+    pub profile: Option<String>,
+    // 开启沙箱模式时的配置
+    pub sandbox: Option<SandboxConfig>,
+    // 正在运行的程序通过 OSC 序列设置的标题
+    pub program_title: Option<String>,
+    // 上一次采样到的前台进程和它的工作目录
+    pub foreground: Option<(String, String)>,
+    // 最近一次发给前端的自动标题
+    pub auto_title: Option<String>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session {
+            current_dir: home_dir(),
+            shell: None,
+            running: None,
+            starting: false,
+            command: None,
+            scrollback: VecDeque::new(),
+            title: None,
+            env: HashMap::new(),
+            startup_command: None,
+            blocks: VecDeque::new(),
+            profile: None,
+            sandbox: None,
+            program_title: None,
+            foreground: None,
+            auto_title: None,
+        }
+    }
+
+    pub fn push_output(&mut self, content: &str, replace_last: bool) {
+        if replace_last {
+            self.scrollback.pop_back();
+        }
+        self.scrollback.push_back(content.to_string());
+        while self.scrollback.len() > SCROLLBACK_LINES {
+            self.scrollback.pop_front();
+        }
+
+        if let Some(block) = self.blocks.back_mut().filter(|b| b.code.is_none()) {
+            if replace_last {
+                block.output.pop();
+            }
+            block.output.push(content.to_string());
+            if block.output.len() > SCROLLBACK_LINES {
+                block.output.remove(0);
+            }
+        }
+    }
+
+    pub fn start_block(&mut self, command: &str) {
+        self.blocks.push_back(CommandBlock {
+            command: command.to_string(),
+            directory: format_current_dir(&self.current_dir),
+            output: Vec::new(),
+            code: None,
+        });
+        while self.blocks.len() > BLOCK_LIMIT {
+            self.blocks.pop_front();
+        }
+    }
+
+    pub fn finish_block(&mut self, code: Option<i32>) {
+        if let Some(block) = self.blocks.back_mut() {
+            block.code = code;
+        }
+    }
+
+    pub fn recent_output(&self, lines: usize) -> Vec<String> {
+        let skip = self.scrollback.len().saturating_sub(lines);
+        self.scrollback.iter().skip(skip).cloned().collect()
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub type SessionHandle = Arc<Mutex<Session>>;
+
+// 会话概要，重新连接后前端据此恢复标签页和输出
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SessionInfo {
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+    pub current_dir: String,
+    pub command: Option<String>,
+    pub running: bool,
+    pub scrollback: Vec<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub startup_command: Option<String>,
+    #[serde(default)]
+    pub blocks: Vec<CommandBlock>,
+    #[serde(default)]
+    pub sandboxed: bool,
+}
+
+// 每个终端有独立的锁，一个终端出问题不会影响其他终端
+static SESSIONS: Lazy<Mutex<HashMap<String, SessionHandle>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn home_dir() -> PathBuf {
+    env::var("HOME").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("/"))
+}
+
+// 获取锁；如果持有者曾经 panic，直接取回数据继续使用
+pub fn lock_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        eprintln!("Recovered from a poisoned lock");
+        poisoned.into_inner()
+    })
+}
+
+fn registry() -> MutexGuard<'static, HashMap<String, SessionHandle>> {
+    // 注册表本身只在短暂的插入/删除时持锁，HashMap 在 panic 后仍然完整
+    lock_recover(&SESSIONS)
+}
+
+pub fn get_or_create(id: &str) -> SessionHandle {
+    registry()
+        .entry(id.to_string())
+        .or_insert_with(|| Arc::new(Mutex::new(Session::new())))
+        .clone()
+}
+
+pub fn get(id: &str) -> TerminalResult<SessionHandle> {
+    registry()
+        .get(id)
+        .cloned()
+        .ok_or_else(|| TerminalError::TerminalNotFound(id.to_string()))
+}
+
+pub fn remove(id: &str) -> Option<SessionHandle> {
+    registry().remove(id)
+}
+
+pub fn ids() -> Vec<String> {
+    registry().keys().cloned().collect()
+}
+
+// 锁住某个终端的会话。如果该会话的锁被 panic 污染，
+// 终止其中残留的进程并用保留工作目录的新会话替换它，
+// 本次调用返回 SessionPoisoned，之后的调用恢复正常。
+pub fn lock<'a>(id: &str, handle: &'a SessionHandle) -> TerminalResult<MutexGuard<'a, Session>> {
+    match handle.lock() {
+        Ok(guard) => Ok(guard),
+        Err(poisoned) => {
+            let mut stale = poisoned.into_inner();
+            if let Some(mut child) = stale.running.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            let fresh = Session {
+                current_dir: stale.current_dir.clone(),
+                shell: stale.shell.take(),
+                scrollback: std::mem::take(&mut stale.scrollback),
+                title: stale.title.take(),
+                env: std::mem::take(&mut stale.env),
+                startup_command: stale.startup_command.take(),
+                blocks: std::mem::take(&mut stale.blocks),
+                // 重置后仍然受同样的策略限制
+                profile: stale.profile.take(),
+                sandbox: stale.sandbox.take(),
+                ..Session::new()
+            };
+            drop(stale);
+
+            let mut sessions = registry();
+            if let Some(current) = sessions.get(id) {
+                if Arc::ptr_eq(current, handle) {
+                    sessions.insert(id.to_string(), Arc::new(Mutex::new(fresh)));
+                }
+            }
+            eprintln!("Session {} was poisoned and has been reset", id);
+            Err(TerminalError::SessionPoisoned(id.to_string()))
+        }
+    }
+}
+
+// 按 id 查找并锁住会话，不存在时自动创建；只用于打开终端、执行命令等显式操作
+pub fn with_session<T>(id: &str, f: impl FnOnce(&mut Session) -> T) -> TerminalResult<T> {
+    let handle = get_or_create(id);
+    let mut session = lock(id, &handle)?;
+    Ok(f(&mut session))
+}
+
+// 同 with_session，但会话不存在时返回 TerminalNotFound。
+// 引擎的回调（输出、命令结束、标题、统计）用它，标签页关闭后不会把会话重新创建出来
+pub fn with_existing<T>(id: &str, f: impl FnOnce(&mut Session) -> T) -> TerminalResult<T> {
+    let handle = get(id)?;
+    let mut session = lock(id, &handle)?;
+    Ok(f(&mut session))
+}
+
+// 所有会话的概要，每个会话以及每个命令块附带最近 lines 行输出
+pub fn infos(lines: usize) -> Vec<SessionInfo> {
+    let mut infos = Vec::new();
+    for id in ids() {
+        let handle = match get(&id) {
+            Ok(handle) => handle,
+            Err(_) => continue,
+        };
+        let info = match lock(&id, &handle) {
+            Ok(session) => SessionInfo {
+                terminal_id: id.clone(),
+                current_dir: format_current_dir(&session.current_dir),
+                command: session.command.clone(),
+                running: session.running.is_some(),
+                scrollback: session.recent_output(lines),
+                title: session.title.clone(),
+                startup_command: session.startup_command.clone(),
+                blocks: session
+                    .blocks
+                    .iter()
+                    .map(|block| CommandBlock {
+                        output: tail(&block.output, lines),
+                        ..block.clone()
+                    })
+                    .collect(),
+                sandboxed: session.sandbox.is_some(),
+            },
+            Err(_) => continue,
+        };
+        infos.push(info);
+    }
+    infos.sort_by(|a, b| a.terminal_id.cmp(&b.terminal_id));
+    infos
+}
+
+// 最后 count 行
+pub fn tail(lines: &[String], count: usize) -> Vec<String> {
+    lines[lines.len().saturating_sub(count)..].to_vec()
+}
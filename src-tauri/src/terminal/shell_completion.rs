@@ -0,0 +1,313 @@
+// 向用户自己的 shell 询问补全候选，复用已经安装的补全脚本：
+// zsh 在 zpty 伪终端里启动交互 shell，替换 compadd 截获候选；
+// bash 加载 bash-completion 后调用命令对应的补全函数或 compgen。
+// 结果按 shell、目录和输入缓存；shell 不支持或超时时返回 None，由内置补全兜底。
+
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::session::lock_recover;
+use super::shutdown;
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+const CACHE_TTL: Duration = Duration::from_secs(30);
+const CACHE_LIMIT: usize = 200;
+const ZSH_INIT_ENV: &str = "LANDINGTERM_ZSH_INIT";
+
+// 子 zsh 的初始化脚本：加载用户配置和补全系统，替换 compadd，
+// 每个候选输出一行 "候选<TAB>描述"，前后用只含 \0 的行标记
+const ZSH_INIT: &str = r#"
+PROMPT= RPROMPT=
+unsetopt zle_bracketed_paste 2>/dev/null
+autoload -U compinit
+mkdir -p -m 700 "${XDG_CACHE_HOME:-$HOME/.cache}/landingterm"
+compinit -u -d "${XDG_CACHE_HOME:-$HOME/.cache}/landingterm/zcompdump"
+bindkey '^M' undefined
+bindkey '^J' undefined
+bindkey '^I' complete-word
+null-line () { echo -E - $'\0' }
+compprefuncs=( null-line )
+comppostfuncs=( null-line exit )
+zstyle ':completion:*' list-grouped false
+zstyle ':completion:*' insert-tab false
+zstyle ':completion:*' list-separator ''
+zmodload zsh/zutil
+compadd () {
+    if [[ ${@[1,(i)(-|--)]} == *-(O|A|D)\ * ]]; then
+        builtin compadd "$@"
+        return $?
+    fi
+    typeset -a __hits __dscr __tmp
+    if (( $@[(I)-d] )); then
+        __tmp=${@[$[${@[(i)-d]}+1]]}
+        if [[ $__tmp == \(* ]]; then
+            eval "__dscr=$__tmp"
+        else
+            __dscr=( "${(@P)__tmp}" )
+        fi
+    fi
+    builtin compadd -A __hits -D __dscr "$@"
+    setopt localoptions norcexpandparam extendedglob
+    typeset -A apre hpre hsuf asuf
+    zparseopts -E P:=apre p:=hpre S:=asuf s:=hsuf
+    [[ -n $__hits ]] || return
+    local dscr i
+    for i in {1..$#__hits}; do
+        (( $#__dscr >= $i )) && dscr=${${__dscr[$i]}##$__hits[$i] #} || dscr=
+        dscr=${dscr##-- #}
+        echo -E - "$IPREFIX$apre$hpre$__hits[$i]$hsuf$asuf"$'\t'"$dscr"
+    done
+}
+echo landingterm-ready
+"#;
+
+// $1 为光标之前的命令行；初始化脚本放在环境变量里传给子 shell，不写临时文件，
+// 其他用户没有机会替换它
+const ZSH_CAPTURE: &str = r#"
+zmodload zsh/zpty || exit 2
+zpty z zsh -i
+zpty -w z 'eval "$LANDINGTERM_ZSH_INIT"'
+local line
+integer ready=0
+repeat 200; do
+    zpty -r z line || break
+    [[ $line == landingterm-ready* ]] && { ready=1; break }
+done
+(( ready )) || exit 2
+zpty -w z "$1"$'\t'
+integer tog=0
+while zpty -r z line; do
+    line=${line%$'\r'}
+    line=${line%$'\n'}
+    line=${line%$'\r'}
+    if [[ $line == *$'\0' ]]; then
+        (( tog++ )) && exit 0 || continue
+    fi
+    (( tog )) && print -r -- "$line"
+done
+exit 0
+"#;
+
+// $@ 为当前命令的单词，最后一个是正在补全的单词；命令没有补全定义时以 3 退出
+const BASH_CAPTURE: &str = r#"
+PS1='$ '
+for f in /usr/share/bash-completion/bash_completion /etc/bash_completion \
+         /usr/local/etc/profile.d/bash_completion.sh /opt/homebrew/etc/profile.d/bash_completion.sh; do
+    [ -r "$f" ] && { . "$f"; break; }
+done
+[ -r ~/.bashrc ] && . ~/.bashrc >/dev/null 2>&1 </dev/null
+COMP_WORDS=("$@")
+COMP_CWORD=$(( $# - 1 ))
+COMP_LINE="$*"
+COMP_POINT=${#COMP_LINE}
+COMP_TYPE=9
+COMP_KEY=9
+cmd=${COMP_WORDS[0]}
+cur=${COMP_WORDS[COMP_CWORD]}
+prev=${COMP_WORDS[COMP_CWORD-1]}
+spec=$(complete -p -- "$cmd" 2>/dev/null)
+if [ -z "$spec" ]; then
+    if declare -F _completion_loader >/dev/null; then
+        _completion_loader "$cmd" >/dev/null 2>&1
+    else
+        for d in /usr/share/bash-completion/completions /etc/bash_completion.d; do
+            [ -r "$d/$cmd" ] && { . "$d/$cmd" >/dev/null 2>&1; break; }
+        done
+    fi
+    spec=$(complete -p -- "$cmd" 2>/dev/null)
+fi
+[ -z "$spec" ] && exit 3
+spec=${spec% *}
+spec=${spec#complete }
+if [[ $spec == *"-F "* ]]; then
+    func=${spec##*-F }
+    func=${func%% *}
+    COMPREPLY=()
+    "$func" "$cmd" "$cur" "$prev" >/dev/null 2>&1 </dev/null
+    [ ${#COMPREPLY[@]} -gt 0 ] && printf '%s\n' "${COMPREPLY[@]}"
+else
+    eval "compgen $spec -- \"\$cur\"" 2>/dev/null
+fi
+exit 0
+"#;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShellCandidate {
+    // 替换当前单词的文本（未转义）
+    pub value: String,
+    pub description: Option<String>,
+}
+
+type CacheKey = (String, PathBuf, String);
+
+struct Cache {
+    entries: HashMap<CacheKey, (Instant, Option<Vec<ShellCandidate>>)>,
+    // 不支持的 shell（例如没有 zpty 模块），之后不再尝试
+    unsupported: HashSet<String>,
+}
+
+static CACHE: Lazy<Mutex<Cache>> = Lazy::new(|| {
+    Mutex::new(Cache {
+        entries: HashMap::new(),
+        unsupported: HashSet::new(),
+    })
+});
+
+fn shell_name(shell: &str) -> &str {
+    Path::new(shell).file_name().and_then(|n| n.to_str()).unwrap_or(shell)
+}
+
+// line 为光标之前的命令行，words 为当前命令已经切分好的单词（最后一个是正在补全的单词）
+pub fn harvest(
+    shell: &str,
+    line: &str,
+    words: &[String],
+    cwd: &Path,
+    env_overrides: &HashMap<String, String>,
+) -> Option<Vec<ShellCandidate>> {
+    let name = shell_name(shell);
+    if !matches!(name, "zsh" | "bash") || words.len() < 2 {
+        return None;
+    }
+    let key = (shell.to_string(), cwd.to_path_buf(), line.to_string());
+    {
+        let cache = lock_recover(&CACHE);
+        if cache.unsupported.contains(shell) {
+            return None;
+        }
+        if let Some((at, result)) = cache.entries.get(&key) {
+            if at.elapsed() < CACHE_TTL {
+                return result.clone();
+            }
+        }
+    }
+
+    let (result, supported) = match name {
+        "zsh" => capture_zsh(shell, line, cwd, env_overrides),
+        _ => capture_bash(shell, words, cwd, env_overrides),
+    };
+
+    let mut cache = lock_recover(&CACHE);
+    if !supported {
+        cache.unsupported.insert(shell.to_string());
+        return None;
+    }
+    if cache.entries.len() >= CACHE_LIMIT {
+        cache.entries.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+        if cache.entries.len() >= CACHE_LIMIT {
+            cache.entries.clear();
+        }
+    }
+    cache.entries.insert(key, (Instant::now(), result.clone()));
+    result
+}
+
+fn capture_zsh(
+    shell: &str,
+    line: &str,
+    cwd: &Path,
+    env_overrides: &HashMap<String, String>,
+) -> (Option<Vec<ShellCandidate>>, bool) {
+    let mut command = Command::new(shell);
+    command
+        .env(ZSH_INIT_ENV, ZSH_INIT)
+        .arg("-f")
+        .arg("-c")
+        .arg(ZSH_CAPTURE)
+        .arg("zsh")
+        .arg(line);
+    match run(command, cwd, env_overrides) {
+        // 缺少 zpty 模块或者子 shell 初始化失败
+        Some((2, _)) => (None, false),
+        Some((0, output)) => (Some(parse(&output)), true),
+        _ => (None, true),
+    }
+}
+
+fn capture_bash(
+    shell: &str,
+    words: &[String],
+    cwd: &Path,
+    env_overrides: &HashMap<String, String>,
+) -> (Option<Vec<ShellCandidate>>, bool) {
+    let mut command = Command::new(shell);
+    command
+        .arg("--noprofile")
+        .arg("--norc")
+        .arg("-c")
+        .arg(BASH_CAPTURE)
+        .arg("bash")
+        .args(words);
+    match run(command, cwd, env_overrides) {
+        Some((0, output)) => (Some(parse(&output)), true),
+        _ => (None, true),
+    }
+}
+
+// 运行捕获脚本，超时后结束整个进程树；返回退出码和标准输出
+pub(crate) fn run(mut command: Command, cwd: &Path, env_overrides: &HashMap<String, String>) -> Option<(i32, String)> {
+    command
+        .current_dir(cwd)
+        .envs(env_overrides)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    // 放到新的会话里：没有控制终端，交互 shell 不会因为争用终端而被挂起
+    #[cfg(unix)]
+    unsafe {
+        use std::os::unix::process::CommandExt;
+        command.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+
+    let mut child = command.spawn().ok()?;
+    let mut stdout = child.stdout.take()?;
+    let reader = thread::spawn(move || {
+        let mut output = String::new();
+        let _ = stdout.read_to_string(&mut output);
+        output
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < TIMEOUT => thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = shutdown::kill_tree(&mut child);
+                return None;
+            }
+        }
+    };
+    let output = reader.join().ok()?;
+    Some((status.code()?, output))
+}
+
+pub fn parse(output: &str) -> Vec<ShellCandidate> {
+    let mut seen = HashSet::new();
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let (value, description) = match line.split_once('\t') {
+                Some((value, description)) => (value, description.trim()),
+                None => (line, ""),
+            };
+            if !seen.insert(value.to_string()) {
+                return None;
+            }
+            Some(ShellCandidate {
+                value: value.to_string(),
+                description: if description.is_empty() { None } else { Some(description.to_string()) },
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,242 @@
+use std::process::{Child, Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Runtime};
+
+use super::orphans;
+use super::persist;
+use super::session::{self, lock_recover};
+
+// 收到 SIGTERM 后等待进程自行退出的时间，超时后发送 SIGKILL
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+const KILL_GRACE: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static SHUTDOWN_DONE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    pub signalled: usize,
+    pub killed: Vec<u32>,
+    pub survivors: Vec<u32>,
+}
+
+pub fn is_shut_down() -> bool {
+    SHUTDOWN_DONE.load(Ordering::SeqCst)
+}
+
+// 终止所有终端的 shell 和正在运行的命令（包括它们的整个进程树）。
+// 先发送 SIGTERM，超过 grace 后升级为 SIGKILL，最后报告仍然存活的进程。
+pub fn shutdown_all(grace: Duration) -> ShutdownReport {
+    // 清空会话之前先保存快照，下次启动时恢复
+    if !is_shut_down() {
+        persist::save();
+    }
+    let mut children: Vec<Child> = Vec::new();
+    for id in session::ids() {
+        if let Some(handle) = session::remove(&id) {
+            let mut session = lock_recover(&handle);
+            children.extend(session.running.take());
+            if let Some(shell) = session.shell.take() {
+                children.push(shell.into_child());
+            }
+        }
+    }
+
+    let report = terminate_children(children, grace);
+    SHUTDOWN_DONE.store(true, Ordering::SeqCst);
+
+    if report.signalled > 0 {
+        println!(
+            "Shutdown: signalled {} process(es), force-killed {}",
+            report.signalled,
+            report.killed.len()
+        );
+    }
+    if !report.survivors.is_empty() {
+        eprintln!("Shutdown: processes still running after SIGKILL: {:?}", report.survivors);
+    }
+    report
+}
+
+#[cfg(unix)]
+fn terminate_children(mut children: Vec<Child>, grace: Duration) -> ShutdownReport {
+    let roots: Vec<u32> = children.iter().map(|child| child.id()).collect();
+    let report = terminate(&mut children, &roots, grace);
+    for pid in roots {
+        if !report.survivors.contains(&pid) {
+            orphans::forget(pid);
+        }
+    }
+    report
+}
+
+// 立即用 SIGKILL 结束子进程及其所有后代（用户手动停止命令时使用）。
+// 子进程是进程组组长，先整组终止，快照之后才 fork 出来的、已经挂到 init 下的组内进程也一起结束；
+// 再按快照终止换了进程组的后代
+#[cfg(unix)]
+pub fn kill_tree(child: &mut Child) -> std::io::Result<ExitStatus> {
+    let pids = descendants(child.id());
+    signal(child.id(), libc::SIGKILL);
+    child.kill()?;
+    for pid in pids {
+        signal(pid, libc::SIGKILL);
+    }
+    child.wait()
+}
+
+#[cfg(not(unix))]
+pub fn kill_tree(child: &mut Child) -> std::io::Result<ExitStatus> {
+    child.kill()?;
+    child.wait()
+}
+
+// 终止不是我们子进程的进程树（例如上次崩溃遗留的进程）
+#[cfg(unix)]
+pub fn terminate_tree(pid: u32, grace: Duration) -> ShutdownReport {
+    terminate(&mut [], &[pid], grace)
+}
+
+#[cfg(unix)]
+fn terminate(children: &mut [Child], roots: &[u32], grace: Duration) -> ShutdownReport {
+    // 先记录完整的进程树，父进程退出后子进程会被重新挂到 init 下
+    let mut targets: Vec<u32> = Vec::new();
+    for &root in roots {
+        targets.push(root);
+        targets.extend(descendants(root));
+    }
+    targets.sort_unstable();
+    targets.dedup();
+
+    let mut report = ShutdownReport {
+        signalled: targets.len(),
+        ..Default::default()
+    };
+    for &pid in &targets {
+        signal(pid, libc::SIGTERM);
+    }
+
+    let mut alive = wait_for_exit(children, &targets, grace);
+    if !alive.is_empty() {
+        for &pid in &alive {
+            signal(pid, libc::SIGKILL);
+        }
+        report.killed = alive.clone();
+        alive = wait_for_exit(children, &alive, KILL_GRACE);
+    }
+    report.survivors = alive;
+    report
+}
+
+#[cfg(not(unix))]
+fn terminate_children(children: Vec<Child>, _grace: Duration) -> ShutdownReport {
+    let mut report = ShutdownReport {
+        signalled: children.len(),
+        ..Default::default()
+    };
+    for mut child in children {
+        if child.kill().is_err() || child.wait().is_err() {
+            report.survivors.push(child.id());
+        } else {
+            orphans::forget(child.id());
+        }
+    }
+    report
+}
+
+// 向进程所在的进程组和进程本身发送信号
+#[cfg(unix)]
+pub fn signal(pid: u32, sig: libc::c_int) {
+    let pid = pid as libc::pid_t;
+    unsafe {
+        if libc::getpgid(pid) == pid {
+            libc::kill(-pid, sig);
+        }
+        libc::kill(pid, sig);
+    }
+}
+
+#[cfg(unix)]
+pub fn is_alive(pid: u32) -> bool {
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+// 轮询直到所有目标退出或超时，返回仍然存活的 pid
+#[cfg(unix)]
+fn wait_for_exit(children: &mut [Child], targets: &[u32], timeout: Duration) -> Vec<u32> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        // 回收我们自己的子进程，避免它们以僵尸进程的形式被当成存活
+        for child in children.iter_mut() {
+            let _ = child.try_wait();
+        }
+        let alive: Vec<u32> = targets.iter().copied().filter(|&pid| is_alive(pid)).collect();
+        if alive.is_empty() || Instant::now() >= deadline {
+            return alive;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// 通过 ps 获取某个进程的所有后代，macOS 和 Linux 都可用
+#[cfg(unix)]
+fn descendants(root: u32) -> Vec<u32> {
+    let output = match Command::new("ps").args(["-A", "-o", "pid=", "-o", "ppid="]).output() {
+        Ok(output) => output,
+        Err(_) => return Vec::new(),
+    };
+    let table: Vec<(u32, u32)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let ppid = fields.next()?.parse().ok()?;
+            Some((pid, ppid))
+        })
+        .collect();
+
+    let mut result = Vec::new();
+    let mut queue = vec![root];
+    while let Some(parent) = queue.pop() {
+        for &(pid, ppid) in &table {
+            if ppid == parent && !result.contains(&pid) {
+                result.push(pid);
+                queue.push(pid);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(unix)]
+static PENDING_SIGNAL: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+#[cfg(unix)]
+extern "C" fn on_termination_signal(sig: libc::c_int) {
+    PENDING_SIGNAL.store(sig, Ordering::SeqCst);
+}
+
+// 捕获 SIGTERM/SIGHUP：在后台线程里清理所有子进程后退出应用
+#[cfg(unix)]
+pub fn install_signal_handlers<R: Runtime>(app: AppHandle<R>) {
+    unsafe {
+        let handler = on_termination_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::signal(libc::SIGTERM, handler);
+        libc::signal(libc::SIGHUP, handler);
+    }
+
+    thread::spawn(move || loop {
+        let sig = PENDING_SIGNAL.load(Ordering::SeqCst);
+        if sig != 0 {
+            println!("Received signal {}, shutting down", sig);
+            shutdown_all(SHUTDOWN_GRACE);
+            app.exit(0);
+            return;
+        }
+        thread::sleep(Duration::from_millis(100));
+    });
+}
+
+#[cfg(not(unix))]
+pub fn install_signal_handlers<R: Runtime>(_app: AppHandle<R>) {}
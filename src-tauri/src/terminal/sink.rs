@@ -0,0 +1,201 @@
+use serde::Serialize;
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use tauri::Runtime;
+
+use super::correction::Correction;
+use super::limits::LimitKind;
+use super::ports::PortOpened;
+use super::session::lock_recover;
+use super::stats::{ProcessStats, StatsSummary};
+use super::title::TitleChanged;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct StreamOutput {
+    pub content: String,
+    pub output_type: String,
+    pub current_dir: String,
+    pub should_replace_last: bool,
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+}
+
+// 不是由前端发起的命令（例如通过控制接口），前端据此创建命令块
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CommandStarted {
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+    pub command: String,
+}
+
+// 请求前端打开一个新的标签页
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OpenTerminal {
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+    pub current_dir: String,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CommandComplete {
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+    pub code: Option<i32>,
+    // 命令不存在时的改正建议
+    pub suggestions: Vec<Correction>,
+    // 命令因为超出资源限制而结束时，超出的是哪一项
+    pub limit_exceeded: Option<LimitKind>,
+    // 运行期间的资源占用汇总；不是 Linux 或者没有采到数据时为空
+    pub stats: Option<StatsSummary>,
+}
+
+// 终端引擎产生的事件，每个变体对应一个前端事件名
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum TerminalEvent {
+    Output(StreamOutput),
+    Started(CommandStarted),
+    Complete(CommandComplete),
+    Open(OpenTerminal),
+    Stats(ProcessStats),
+    PortOpened(PortOpened),
+    TitleChanged(TitleChanged),
+}
+
+impl TerminalEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TerminalEvent::Output(_) => "terminal-output",
+            TerminalEvent::Started(_) => "terminal-command-started",
+            TerminalEvent::Complete(_) => "terminal-command-complete",
+            TerminalEvent::Open(_) => "terminal-open-requested",
+            TerminalEvent::Stats(_) => "terminal-process-stats",
+            TerminalEvent::PortOpened(_) => "terminal-port-opened",
+            TerminalEvent::TitleChanged(_) => "terminal-title-changed",
+        }
+    }
+
+    pub fn terminal_id(&self) -> &str {
+        match self {
+            TerminalEvent::Output(output) => &output.terminal_id,
+            TerminalEvent::Started(started) => &started.terminal_id,
+            TerminalEvent::Complete(complete) => &complete.terminal_id,
+            TerminalEvent::Open(open) => &open.terminal_id,
+            TerminalEvent::Stats(stats) => &stats.terminal_id,
+            TerminalEvent::PortOpened(port) => &port.terminal_id,
+            TerminalEvent::TitleChanged(title) => &title.terminal_id,
+        }
+    }
+}
+
+// 引擎输出的去处：Tauri 窗口、内存或者 channel
+pub trait OutputSink: Send + Sync {
+    fn send(&self, event: TerminalEvent);
+}
+
+pub struct WindowSink<R: Runtime> {
+    window: tauri::Window<R>,
+}
+
+impl<R: Runtime> WindowSink<R> {
+    pub fn new(window: tauri::Window<R>) -> Self {
+        WindowSink { window }
+    }
+}
+
+impl<R: Runtime> OutputSink for WindowSink<R> {
+    fn send(&self, event: TerminalEvent) {
+        let _ = self.window.emit(event.name(), &event);
+    }
+}
+
+// 把所有事件收集在内存里，主要用于测试
+#[derive(Default)]
+pub struct MemorySink {
+    events: Mutex<Vec<TerminalEvent>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Vec<TerminalEvent> {
+        lock_recover(&self.events).clone()
+    }
+
+    pub fn outputs(&self) -> Vec<StreamOutput> {
+        self.events()
+            .into_iter()
+            .filter_map(|event| match event {
+                TerminalEvent::Output(output) => Some(output),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn stats(&self) -> Vec<ProcessStats> {
+        self.events()
+            .into_iter()
+            .filter_map(|event| match event {
+                TerminalEvent::Stats(stats) => Some(stats),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn ports(&self) -> Vec<PortOpened> {
+        self.events()
+            .into_iter()
+            .filter_map(|event| match event {
+                TerminalEvent::PortOpened(port) => Some(port),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn titles(&self) -> Vec<TitleChanged> {
+        self.events()
+            .into_iter()
+            .filter_map(|event| match event {
+                TerminalEvent::TitleChanged(title) => Some(title),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn completions(&self) -> Vec<CommandComplete> {
+        self.events()
+            .into_iter()
+            .filter_map(|event| match event {
+                TerminalEvent::Complete(complete) => Some(complete),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn send(&self, event: TerminalEvent) {
+        lock_recover(&self.events).push(event);
+    }
+}
+
+// 把事件转发到 channel，接收端可以在另一个线程里实时处理
+pub struct ChannelSink {
+    sender: Mutex<Sender<TerminalEvent>>,
+}
+
+impl ChannelSink {
+    pub fn new(sender: Sender<TerminalEvent>) -> Self {
+        ChannelSink {
+            sender: Mutex::new(sender),
+        }
+    }
+}
+
+impl OutputSink for ChannelSink {
+    fn send(&self, event: TerminalEvent) {
+        let _ = lock_recover(&self.sender).send(event);
+    }
+}
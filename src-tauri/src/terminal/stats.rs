@@ -0,0 +1,114 @@
+// 正在运行的命令的资源占用：定期遍历命令的进程树，汇总 CPU、内存、磁盘 I/O 和线程数，
+// 以 terminal-process-stats 事件发给前端，命令结束时在完成事件里附上汇总。
+
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use super::procfs::{self, ProcStat};
+
+// 某一时刻整棵进程树的资源占用
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProcessStats {
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+    pub pid: u32,
+    pub processes: usize,
+    pub threads: u64,
+    // 100 表示占满一个核
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+// 整个命令运行期间的汇总
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct StatsSummary {
+    pub samples: u32,
+    pub cpu_secs: f64,
+    pub peak_cpu_percent: f64,
+    pub peak_rss_bytes: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub peak_threads: u64,
+    pub peak_processes: usize,
+}
+
+pub struct Sampler {
+    terminal_id: String,
+    pid: u32,
+    // 上一次采样的时间和累计 CPU 时钟周期；刚启动的命令还没有用过 CPU
+    last: (Instant, u64),
+    // 已经退出的进程不再出现在树里，累计值只增不减
+    ticks: u64,
+    summary: StatsSummary,
+}
+
+impl Sampler {
+    pub fn new(terminal_id: &str, pid: u32) -> Self {
+        Sampler {
+            terminal_id: terminal_id.to_string(),
+            pid,
+            last: (Instant::now(), 0),
+            ticks: 0,
+            summary: StatsSummary::default(),
+        }
+    }
+
+    // 采样一次；进程已经退出时返回 None
+    pub fn sample(&mut self) -> Option<ProcessStats> {
+        let tree = procfs::tree(self.pid);
+        if tree.is_empty() {
+            return None;
+        }
+        Some(self.record(&tree, Instant::now()))
+    }
+
+    pub fn record(&mut self, tree: &[ProcStat], now: Instant) -> ProcessStats {
+        let ticks = tree.iter().map(ProcStat::total_ticks).sum::<u64>().max(self.ticks);
+        let (at, previous) = self.last;
+        let elapsed = now.saturating_duration_since(at).as_secs_f64();
+        let cpu_percent = if elapsed > 0.0 {
+            (ticks - previous) as f64 / procfs::clock_ticks() as f64 / elapsed * 100.0
+        } else {
+            0.0
+        };
+        self.last = (now, ticks);
+        self.ticks = ticks;
+
+        let (read_bytes, write_bytes) = tree
+            .iter()
+            .filter_map(|p| procfs::io_bytes(p.pid))
+            .fold((0, 0), |(r, w), (pr, pw)| (r + pr, w + pw));
+        let stats = ProcessStats {
+            terminal_id: self.terminal_id.clone(),
+            pid: self.pid,
+            processes: tree.len(),
+            threads: tree.iter().map(|p| p.num_threads).sum(),
+            cpu_percent,
+            rss_bytes: tree.iter().map(|p| p.rss_pages).sum::<u64>() * procfs::page_size(),
+            read_bytes,
+            write_bytes,
+        };
+
+        let summary = &mut self.summary;
+        summary.samples += 1;
+        summary.cpu_secs = ticks as f64 / procfs::clock_ticks() as f64;
+        summary.peak_cpu_percent = summary.peak_cpu_percent.max(stats.cpu_percent);
+        summary.peak_rss_bytes = summary.peak_rss_bytes.max(stats.rss_bytes);
+        summary.read_bytes = summary.read_bytes.max(stats.read_bytes);
+        summary.write_bytes = summary.write_bytes.max(stats.write_bytes);
+        summary.peak_threads = summary.peak_threads.max(stats.threads);
+        summary.peak_processes = summary.peak_processes.max(stats.processes);
+        stats
+    }
+
+    // 没有采到数据（命令很快结束或者不是 Linux）时返回 None
+    pub fn summary(&self) -> Option<StatsSummary> {
+        if self.summary.samples == 0 {
+            None
+        } else {
+            Some(self.summary.clone())
+        }
+    }
+}
@@ -0,0 +1,121 @@
+// 根据命令历史给出类似 fish 的行内建议：输入的前缀之后显示最可能的剩余部分。
+// 每次按键都会调用，只扫描最近的记录。
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+#[cfg(unix)]
+use super::daemon;
+use super::error::TerminalResult;
+#[cfg(unix)]
+use super::error::TerminalError;
+#[cfg(unix)]
+use super::process::run_blocking;
+use super::history::{self, HistoryEntry};
+use super::session;
+
+// 最多扫描的历史条数
+const SCAN_LIMIT: usize = 20_000;
+const HOUR_MS: f64 = 3_600_000.0;
+// 一周前运行的命令权重约为刚运行时的一半
+const HALF_WEIGHT_HOURS: f64 = 168.0;
+const SAME_DIR_BOOST: f64 = 3.0;
+const FOLLOWS_PREVIOUS_BOOST: f64 = 4.0;
+const FAILED_PENALTY: f64 = 0.3;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Suggestion {
+    // 完整的命令
+    pub command: String,
+    // 需要追加在输入之后的部分
+    pub completion: String,
+    pub score: f64,
+}
+
+// 按最近使用、使用次数、所在目录以及是否常跟在上一条命令之后给候选命令打分
+pub fn rank(
+    entries: &[HistoryEntry],
+    prefix: &str,
+    cwd: &str,
+    previous: Option<&str>,
+    now: u64,
+) -> Option<Suggestion> {
+    if prefix.trim().is_empty() {
+        return None;
+    }
+    let entries = &entries[entries.len().saturating_sub(SCAN_LIMIT)..];
+
+    let mut scores: HashMap<&str, f64> = HashMap::new();
+    // 每个终端里上一条命令，用来判断命令之间的先后关系
+    let mut last_in_terminal: HashMap<&str, &str> = HashMap::new();
+    for entry in entries {
+        let preceding = last_in_terminal.insert(&entry.terminal_id, &entry.command);
+        if entry.command.len() <= prefix.len()
+            || !entry.command.starts_with(prefix)
+            || entry.command.contains('\n')
+        {
+            continue;
+        }
+
+        let age_hours = now.saturating_sub(entry.started_at) as f64 / HOUR_MS;
+        let mut weight = 1.0 / (1.0 + age_hours / HALF_WEIGHT_HOURS);
+        if !cwd.is_empty() && entry.cwd == cwd {
+            weight *= SAME_DIR_BOOST;
+        }
+        if previous.is_some() && preceding == previous {
+            weight *= FOLLOWS_PREVIOUS_BOOST;
+        }
+        if matches!(entry.exit_code, Some(code) if code != 0) {
+            weight *= FAILED_PENALTY;
+        }
+        *scores.entry(entry.command.as_str()).or_default() += weight;
+    }
+
+    // 分数相同时选择较短的命令
+    scores
+        .into_iter()
+        .max_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| b.0.len().cmp(&a.0.len()))
+        })
+        .map(|(command, score)| Suggestion {
+            command: command.to_string(),
+            completion: command[prefix.len()..].to_string(),
+            score,
+        })
+}
+
+pub fn suggest_for(terminal_id: &str, prefix: &str) -> TerminalResult<Option<Suggestion>> {
+    let cwd = match session::get(terminal_id) {
+        Ok(handle) => session::lock(terminal_id, &handle)?
+            .current_dir
+            .to_string_lossy()
+            .into_owned(),
+        Err(_) => String::new(),
+    };
+    Ok(history::with_entries(|entries| {
+        let previous = entries
+            .iter()
+            .rev()
+            .find(|e| e.terminal_id == terminal_id)
+            .map(|e| e.command.as_str());
+        rank(entries, prefix, &cwd, previous, history::now_millis())
+    }))
+}
+
+// 连接了守护进程时，会话和新的历史记录都在守护进程里
+#[tauri::command]
+pub async fn suggest(terminal_id: String, prefix: String) -> TerminalResult<Option<Suggestion>> {
+    #[cfg(unix)]
+    if let Some(client) = daemon::client() {
+        let params = serde_json::json!({ "terminalId": terminal_id, "prefix": prefix });
+        return run_blocking(move || {
+            let value = client.call("suggest", params)?;
+            serde_json::from_value(value).map_err(|e| TerminalError::Daemon(e.to_string()))
+        })
+        .await;
+    }
+    suggest_for(&terminal_id, &prefix)
+}
@@ -0,0 +1,490 @@
+// 执行前的语法检查：用 lexer 的结果做一个简化的 shell 语法分析（列表、&&/||、管道、
+// 子 shell、重定向、here-document），报告带位置的错误并给出命令的结构；
+// 同时让用户的 shell 以 -n（只解析不执行）模式检查，以 shell 的结论为准。
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::engine;
+use super::error::TerminalResult;
+use super::lexer::{self, Token, TokenKind};
+use super::process::{self, run_blocking};
+
+// bash: "bash: -c: line 1: syntax error near unexpected token `|'"
+// zsh: "zsh:1: parse error near `|'"
+static SHELL_ERROR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:line (\d+)|^[^:\s]+:(\d+)): (.+)$").unwrap());
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Redirection {
+    // 操作符，包含文件描述符，例如 2>
+    pub operator: String,
+    pub target: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CommandNode {
+    Simple {
+        assignments: Vec<String>,
+        argv: Vec<String>,
+        redirections: Vec<Redirection>,
+        start: usize,
+        end: usize,
+    },
+    Subshell {
+        body: Vec<Statement>,
+        redirections: Vec<Redirection>,
+        start: usize,
+        end: usize,
+    },
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Pipeline {
+    pub commands: Vec<CommandNode>,
+    pub start: usize,
+    pub end: usize,
+}
+
+// 用 && 或 || 连接的管道；operators[i] 连接 pipelines[i] 和 pipelines[i + 1]
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Statement {
+    pub pipelines: Vec<Pipeline>,
+    pub operators: Vec<String>,
+    // 以 & 结尾，在后台运行
+    pub background: bool,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSource {
+    Parser,
+    Shell,
+}
+
+// 语法错误；[start, end) 为字符位置
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SyntaxIssue {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+    pub source: IssueSource,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CommandCheck {
+    pub valid: bool,
+    pub errors: Vec<SyntaxIssue>,
+    pub statements: Vec<Statement>,
+    // 做 -n 检查的 shell，没有检查时为 None
+    pub shell: Option<String>,
+}
+
+struct Parser<'a> {
+    text: Vec<char>,
+    tokens: &'a [Token],
+    pos: usize,
+    errors: Vec<SyntaxIssue>,
+    // 等待读取正文的 here-document：(结束标记, 是否为 <<-)
+    heredocs: Vec<(String, bool)>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_operator(&self, ops: &[&str]) -> Option<&'a Token> {
+        self.peek()
+            .filter(|t| t.kind == TokenKind::Operator && ops.contains(&t.value.as_str()))
+    }
+
+    fn error(&mut self, message: String, start: usize, end: usize) {
+        self.errors.push(SyntaxIssue {
+            message,
+            start,
+            end,
+            source: IssueSource::Parser,
+        });
+    }
+
+    fn unexpected(&mut self, token: Option<&Token>) {
+        match token {
+            Some(t) => {
+                let name = if t.value == "\n" { "newline" } else { t.value.as_str() };
+                self.error(format!("syntax error near unexpected token `{}`", name), t.start, t.end);
+            }
+            None => {
+                let end = self.text.len();
+                self.error("unexpected end of input".to_string(), end, end);
+            }
+        }
+    }
+
+    // 读过一个换行；有未读的 here-document 时跳过它们的正文
+    fn newline(&mut self) {
+        let token = &self.tokens[self.pos];
+        self.pos += 1;
+        if self.heredocs.is_empty() {
+            return;
+        }
+        let mut index = token.end;
+        for (delimiter, strip_tabs) in std::mem::take(&mut self.heredocs) {
+            loop {
+                if index >= self.text.len() {
+                    break;
+                }
+                let line_end = self.text[index..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .map(|i| index + i)
+                    .unwrap_or(self.text.len());
+                let line: String = self.text[index..line_end].iter().collect();
+                index = (line_end + 1).min(self.text.len());
+                let line = if strip_tabs { line.trim_start_matches('\t') } else { line.as_str() };
+                if line == delimiter {
+                    break;
+                }
+            }
+        }
+        while self.peek().map(|t| t.start < index).unwrap_or(false) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek_operator(&["\n"]).is_some() {
+            self.newline();
+        }
+    }
+
+    fn list(&mut self, in_subshell: bool) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        loop {
+            self.skip_newlines();
+            let token = match self.peek() {
+                Some(token) => token,
+                None => break,
+            };
+            if token.is_operator(")") {
+                if in_subshell {
+                    break;
+                }
+                self.unexpected(Some(token));
+                self.pos += 1;
+                continue;
+            }
+            if token.kind == TokenKind::Operator && matches!(token.value.as_str(), ";" | "&" | ";;" | "&&" | "||" | "|" | "|&") {
+                self.unexpected(Some(token));
+                self.pos += 1;
+                continue;
+            }
+
+            let mut statement = self.and_or();
+            if let Some(sep) = self.peek_operator(&[";", "&", "\n"]) {
+                statement.background = sep.value == "&";
+                if sep.value == "\n" {
+                    self.newline();
+                } else {
+                    self.pos += 1;
+                }
+            }
+            statements.push(statement);
+        }
+        statements
+    }
+
+    fn and_or(&mut self) -> Statement {
+        let mut statement = Statement {
+            pipelines: vec![self.pipeline()],
+            operators: Vec::new(),
+            background: false,
+        };
+        while let Some(op) = self.peek_operator(&["&&", "||"]) {
+            self.pos += 1;
+            self.skip_newlines();
+            if !self.command_follows() {
+                self.error(format!("missing command after `{}`", op.value), op.start, op.end);
+                break;
+            }
+            statement.operators.push(op.value.clone());
+            statement.pipelines.push(self.pipeline());
+        }
+        statement
+    }
+
+    // 下一个记号能否开始一条命令
+    fn command_follows(&self) -> bool {
+        match self.peek() {
+            None => false,
+            Some(t) if t.kind == TokenKind::Word => true,
+            Some(t) => t.value == "(" || is_redirection(&t.value),
+        }
+    }
+
+    fn pipeline(&mut self) -> Pipeline {
+        let first = self.command();
+        let start = first.as_ref().map(node_start).unwrap_or(0);
+        let mut commands: Vec<CommandNode> = first.into_iter().collect();
+        while let Some(op) = self.peek_operator(&["|", "|&"]) {
+            self.pos += 1;
+            self.skip_newlines();
+            if !self.command_follows() {
+                self.error(format!("missing command after `{}`", op.value), op.start, op.end);
+                break;
+            }
+            commands.extend(self.command());
+        }
+        let end = commands.last().map(node_end).unwrap_or(start);
+        Pipeline { commands, start, end }
+    }
+
+    fn redirection(&mut self, op: &Token) -> Option<Redirection> {
+        self.pos += 1;
+        match self.peek() {
+            Some(target) if target.kind == TokenKind::Word => {
+                self.pos += 1;
+                if op.value.ends_with("<<") {
+                    self.heredocs.push((target.value.clone(), false));
+                } else if op.value.ends_with("<<-") {
+                    self.heredocs.push((target.value.clone(), true));
+                }
+                Some(Redirection {
+                    operator: op.value.clone(),
+                    target: target.value.clone(),
+                    start: op.start,
+                    end: target.end,
+                })
+            }
+            _ => {
+                self.error(format!("missing target for redirection `{}`", op.value), op.start, op.end);
+                None
+            }
+        }
+    }
+
+    fn command(&mut self) -> Option<CommandNode> {
+        let first = self.peek();
+        if let Some(open) = first.filter(|t| t.is_operator("(")) {
+            self.pos += 1;
+            let body = self.list(true);
+            let mut end = self.text.len();
+            match self.peek_operator(&[")"]) {
+                Some(close) => {
+                    end = close.end;
+                    self.pos += 1;
+                }
+                None => self.error("unclosed `(`".to_string(), open.start, open.end),
+            }
+            let mut redirections = Vec::new();
+            while let Some(op) = self.peek().filter(|t| t.kind == TokenKind::Operator && is_redirection(&t.value)) {
+                if let Some(r) = self.redirection(op) {
+                    end = r.end;
+                    redirections.push(r);
+                }
+            }
+            return Some(CommandNode::Subshell {
+                body,
+                redirections,
+                start: open.start,
+                end,
+            });
+        }
+
+        let start = first.map(|t| t.start).unwrap_or(self.text.len());
+        let mut end = start;
+        let mut assignments = Vec::new();
+        let mut argv: Vec<String> = Vec::new();
+        let mut redirections = Vec::new();
+        while let Some(token) = self.peek() {
+            if token.kind == TokenKind::Word {
+                self.pos += 1;
+                end = token.end;
+                if argv.is_empty() && lexer::is_assignment(&token.value) {
+                    assignments.push(token.value.clone());
+                } else if argv.is_empty() && token.value == "case" && !token.quoted {
+                    // case 语句里的 ) 和 ;; 不做分析，整体作为一条命令
+                    argv.push(token.value.clone());
+                    end = self.skip_case(token);
+                } else {
+                    argv.push(token.value.clone());
+                }
+            } else if is_redirection(&token.value) {
+                if let Some(r) = self.redirection(token) {
+                    end = r.end;
+                    redirections.push(r);
+                }
+            } else {
+                break;
+            }
+        }
+
+        if assignments.is_empty() && argv.is_empty() && redirections.is_empty() {
+            let token = self.peek();
+            self.unexpected(token);
+            if token.is_some() {
+                self.pos += 1;
+            }
+            return None;
+        }
+        Some(CommandNode::Simple {
+            assignments,
+            argv,
+            redirections,
+            start,
+            end,
+        })
+    }
+
+    // 跳到匹配的 esac 之后，返回结束位置
+    fn skip_case(&mut self, case: &Token) -> usize {
+        let mut depth = 1;
+        while let Some(token) = self.peek() {
+            self.pos += 1;
+            if token.kind == TokenKind::Word && !token.quoted {
+                match token.value.as_str() {
+                    "case" => depth += 1,
+                    "esac" => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return token.end;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.error("`case` without matching `esac`".to_string(), case.start, case.end);
+        self.text.len()
+    }
+}
+
+fn is_redirection(op: &str) -> bool {
+    op.contains('<') || op.contains('>')
+}
+
+fn node_start(node: &CommandNode) -> usize {
+    match node {
+        CommandNode::Simple { start, .. } | CommandNode::Subshell { start, .. } => *start,
+    }
+}
+
+fn node_end(node: &CommandNode) -> usize {
+    match node {
+        CommandNode::Simple { end, .. } | CommandNode::Subshell { end, .. } => *end,
+    }
+}
+
+// 分析命令的结构，返回语句列表和发现的语法错误
+pub fn parse(command: &str) -> (Vec<Statement>, Vec<SyntaxIssue>) {
+    let tokens = lexer::tokenize(command);
+    let mut parser = Parser {
+        text: command.chars().collect(),
+        tokens: &tokens,
+        pos: 0,
+        errors: Vec::new(),
+        heredocs: Vec::new(),
+    };
+    for token in &tokens {
+        if let Some(quote) = token.open_quote {
+            let kind = if quote == '\'' { "single" } else { "double" };
+            parser.error(format!("unterminated {} quote", kind), token.start, token.end);
+        }
+    }
+    let statements = parser.list(false);
+    let mut errors = parser.errors;
+    errors.sort_by_key(|e| e.start);
+    (statements, errors)
+}
+
+// 第 line 行（从 1 开始）在命令中的字符范围
+fn line_range(command: &str, line: usize) -> (usize, usize) {
+    let mut start = 0;
+    for (i, text) in command.split('\n').enumerate() {
+        let len = text.chars().count();
+        if i + 1 == line {
+            return (start, start + len);
+        }
+        start += len + 1;
+    }
+    (0, command.chars().count())
+}
+
+// 解析 shell -n 的错误输出
+pub fn parse_shell_errors(command: &str, stderr: &str) -> Vec<SyntaxIssue> {
+    stderr
+        .lines()
+        .filter_map(|line| {
+            let caps = SHELL_ERROR_RE.captures(line.trim())?;
+            let message = caps.get(3)?.as_str().trim();
+            // bash 会在第二行回显出错的源码
+            if message.starts_with('`') {
+                return None;
+            }
+            let number = caps.get(1).or_else(|| caps.get(2))?.as_str().parse().ok()?;
+            let (start, end) = line_range(command, number);
+            Some(SyntaxIssue {
+                message: message.to_string(),
+                start,
+                end,
+                source: IssueSource::Shell,
+            })
+        })
+        .collect()
+}
+
+// 用 shell 的 -n 模式检查；shell 无法运行时返回 None
+pub fn shell_check(shell: &str, command: &str, cwd: &Path) -> Option<Vec<SyntaxIssue>> {
+    let output = Command::new(shell)
+        .arg("-n")
+        .arg("-c")
+        .arg(command)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if output.status.success() {
+        return Some(Vec::new());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut errors = parse_shell_errors(command, &stderr);
+    if errors.is_empty() {
+        let message = stderr.lines().next().unwrap_or("syntax error").trim().to_string();
+        errors.push(SyntaxIssue {
+            message,
+            start: 0,
+            end: command.chars().count(),
+            source: IssueSource::Shell,
+        });
+    }
+    Some(errors)
+}
+
+// shell 检查通过时忽略内置分析器的错误（它不认识所有语法）；
+// 没有 shell 可用时以分析器的结果为准
+pub fn check(command: &str, shell: Option<&str>, cwd: &Path) -> CommandCheck {
+    let (statements, parser_errors) = parse(command);
+    let shell_errors = shell.and_then(|shell| shell_check(shell, command, cwd));
+    let errors = match shell_errors {
+        Some(shell_errors) if shell_errors.is_empty() => Vec::new(),
+        Some(shell_errors) => parser_errors.into_iter().chain(shell_errors).collect(),
+        None => parser_errors,
+    };
+    CommandCheck {
+        valid: errors.is_empty(),
+        errors,
+        statements,
+        shell: shell.map(str::to_string),
+    }
+}
+
+#[tauri::command]
+pub async fn check_command(terminal_id: String, command: String) -> TerminalResult<CommandCheck> {
+    let (cwd, _) = process::session_context(terminal_id).await?;
+    run_blocking(move || Ok(check(&command, Some(engine::DEFAULT_SHELL.as_str()), &cwd))).await
+}
@@ -0,0 +1,161 @@
+// 自动生成标签页标题：程序用 OSC 0/1/2 设置的标题优先，
+// 否则是前台进程和它的工作目录（例如 "cargo test — ~/proj"），空闲时只显示工作目录。
+// 命令运行在 script/unbuffer 创建的伪终端里，应用不持有它的 fd，无法直接调用 tcgetpgrp，
+// 改为读取 /proc/<pid>/stat 中的 tpgid（同一个值），前台进程取该进程组中第一个不是 shell/包装程序的进程。
+
+use serde::Serialize;
+use std::path::Path;
+
+use super::error::TerminalResult;
+use super::process::format_current_dir;
+use super::procfs::{self, ProcStat};
+use super::session;
+use super::sink::{OutputSink, TerminalEvent};
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TitleChanged {
+    #[serde(rename = "terminalId")]
+    pub terminal_id: String,
+    pub title: String,
+    // 前台进程，空闲时为空
+    pub process: Option<String>,
+    pub cwd: String,
+    // 标题是否由程序通过 OSC 序列设置
+    pub from_program: bool,
+}
+
+// 这些进程只是用来启动真正的命令
+const WRAPPERS: &[&str] = &[
+    "script", "unbuffer", "expect", "tclsh", "sh", "bash", "zsh", "dash", "fish", "unshare", "bwrap", "env", "nohup",
+    "timeout",
+];
+
+// 未结束的 OSC 序列最多缓存这么多字节，超过后当作普通输出
+const MAX_PENDING_OSC: usize = 4096;
+
+// 从输出中去掉 OSC 序列（标题、超链接等），记录其中设置的标题；序列可能跨多次读取
+#[derive(Default)]
+pub struct OscParser {
+    pending: String,
+}
+
+impl OscParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 返回去掉 OSC 序列后的文本，以及这段输出中最后设置的标题
+    pub fn strip(&mut self, chunk: &str) -> (String, Option<String>) {
+        let mut input = std::mem::take(&mut self.pending);
+        input.push_str(chunk);
+        let mut text = String::with_capacity(input.len());
+        let mut title = None;
+        let mut rest = input.as_str();
+        while let Some(start) = rest.find("\x1b]") {
+            text.push_str(&rest[..start]);
+            let body = &rest[start + 2..];
+            let (end, terminator) = match (body.find('\x07'), body.find("\x1b\\")) {
+                (Some(bel), Some(st)) if st < bel => (st, 2),
+                (Some(bel), _) => (bel, 1),
+                (None, Some(st)) => (st, 2),
+                (None, None) => {
+                    if rest.len() - start <= MAX_PENDING_OSC {
+                        self.pending = rest[start..].to_string();
+                    } else {
+                        text.push_str(&rest[start..]);
+                    }
+                    return (text, title);
+                }
+            };
+            if let Some((code, value)) = body[..end].split_once(';') {
+                if matches!(code, "0" | "1" | "2") {
+                    title = Some(value.to_string());
+                }
+            }
+            rest = &body[end + terminator..];
+        }
+        text.push_str(rest);
+        (text, title)
+    }
+}
+
+// 进程树中的前台进程，tree 的根在最前面；没有伪终端的前台进程组时在整棵树里找
+pub fn foreground(tree: &[ProcStat]) -> Option<&ProcStat> {
+    let interesting = |p: &&ProcStat| p.state != 'Z' && !WRAPPERS.contains(&p.comm.as_str());
+    let tpgid = tree.iter().map(|p| p.tpgid).find(|tpgid| *tpgid > 0);
+    tpgid
+        .and_then(|tpgid| tree.iter().filter(|p| p.pgrp == tpgid).find(interesting))
+        .or_else(|| tree.iter().find(interesting))
+        .or_else(|| tree.first())
+}
+
+// 进程显示的名字：程序名，加上看起来像子命令的第一个参数（cargo test、npm run）
+pub fn display_name(stat: &ProcStat, argv: &[String]) -> String {
+    let program = argv
+        .first()
+        .and_then(|arg| Path::new(arg).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| stat.comm.clone());
+    match argv.get(1) {
+        Some(arg) if !arg.starts_with('-') && !arg.contains('/') && !arg.is_empty() => format!("{} {}", program, arg),
+        _ => program,
+    }
+}
+
+pub fn format_title(process: Option<&str>, cwd: &str) -> String {
+    match process {
+        Some(process) => format!("{} — {}", process, cwd),
+        None => cwd.to_string(),
+    }
+}
+
+// 程序设置了标题（或命令结束时清除）
+pub fn set_program_title(sink: &dyn OutputSink, terminal_id: &str, title: Option<String>) -> TerminalResult<()> {
+    let title = title.filter(|t| !t.trim().is_empty());
+    session::with_existing(terminal_id, |s| s.program_title = title)?;
+    refresh(sink, terminal_id, None)
+}
+
+// 重新计算标题，变化时发送 terminal-title-changed；tree 为正在运行的命令的进程树
+pub fn refresh(sink: &dyn OutputSink, terminal_id: &str, tree: Option<&[ProcStat]>) -> TerminalResult<()> {
+    let (current_dir, program_title, running, previous) = session::with_existing(terminal_id, |s| {
+        (s.current_dir.clone(), s.program_title.clone(), s.running.is_some(), s.foreground.clone())
+    })?;
+    let foreground = match tree.filter(|_| running).and_then(foreground) {
+        Some(stat) => {
+            let name = display_name(stat, &procfs::cmdline(stat.pid));
+            let cwd = procfs::cwd(stat.pid).unwrap_or_else(|| current_dir.clone());
+            Some((name, format_current_dir(&cwd)))
+        }
+        // 没有传入进程树时沿用上一次采样到的前台进程
+        None if running => previous,
+        None => None,
+    };
+    let (process, cwd) = match foreground.clone() {
+        Some((name, cwd)) => (Some(name), cwd),
+        None => (None, format_current_dir(&current_dir)),
+    };
+    let (title, from_program) = match &program_title {
+        Some(title) => (title.clone(), true),
+        None => (format_title(process.as_deref(), &cwd), false),
+    };
+    let changed = session::with_existing(terminal_id, |s| {
+        s.foreground = foreground;
+        if s.auto_title.as_deref() == Some(title.as_str()) {
+            false
+        } else {
+            s.auto_title = Some(title.clone());
+            true
+        }
+    })?;
+    if changed {
+        sink.send(TerminalEvent::TitleChanged(TitleChanged {
+            terminal_id: terminal_id.to_string(),
+            title,
+            process,
+            cwd,
+            from_program,
+        }));
+    }
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use app::terminal::completion::{complete_line, complete_line_with, CompletionKind};
+use app::terminal::shell_completion::parse;
+use app::terminal::lexer::{tokenize, TokenKind};
+
+fn fixture(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("landingterm-completion-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("src dir")).unwrap();
+    fs::create_dir_all(dir.join("scripts")).unwrap();
+    fs::write(dir.join("setup.sh"), "").unwrap();
+    fs::write(dir.join(".hidden"), "").unwrap();
+    fs::write(dir.join("src dir/main.rs"), "").unwrap();
+    dir
+}
+
+fn values(line: &str, dir: &Path) -> Vec<String> {
+    complete_line(line, line.chars().count(), dir, &HashMap::new())
+        .candidates
+        .into_iter()
+        .map(|c| c.value)
+        .collect()
+}
+
+#[test]
+fn tokenizer_handles_quotes_and_operators() {
+    let tokens = tokenize(r#"echo "a b" 'c'\ d 2>err | grep x"#);
+    let values: Vec<&str> = tokens.iter().map(|t| t.value.as_str()).collect();
+    assert_eq!(values, vec!["echo", "a b", "c d", "2>", "err", "|", "grep", "x"]);
+    assert_eq!(tokens[3].kind, TokenKind::Operator);
+    assert_eq!((tokens[1].start, tokens[1].end), (5, 10));
+
+    let open = tokenize("cat \"my fi");
+    assert_eq!(open[1].value, "my fi");
+    assert_eq!(open[1].open_quote, Some('"'));
+}
+
+#[test]
+fn files_complete_relative_to_cwd() {
+    let dir = fixture("files");
+    assert_eq!(values("cat s", &dir), vec!["scripts/", "setup.sh", "src\\ dir/"]);
+    assert_eq!(values("cat src\\ dir/", &dir), vec!["src\\ dir/main.rs"]);
+    // 引号内补全保持引号
+    assert_eq!(values("cat \"src d", &dir), vec!["\"src dir/"]);
+    // 隐藏文件只在以 . 开头时出现
+    assert_eq!(values("cat .h", &dir), vec![".hidden"]);
+    // cd 只补全目录
+    assert_eq!(values("cd s", &dir), vec!["scripts/", "src\\ dir/"]);
+
+    let result = complete_line("ls s && cat x", 4, &dir, &HashMap::new());
+    assert_eq!((result.start, result.end), (3, 4));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn command_position_completes_builtins_and_executables() {
+    let dir = fixture("commands");
+    let result = complete_line("FOO=1 exp", 9, &dir, &HashMap::new());
+    let export = result.candidates.iter().find(|c| c.value == "export").unwrap();
+    assert_eq!(export.kind, CompletionKind::Builtin);
+    assert!(export.description.is_some());
+
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    fs::write(bin.join("landing-tool"), "").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(bin.join("landing-tool"), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let mut env = HashMap::new();
+    env.insert("PATH".to_string(), bin.to_string_lossy().into_owned());
+    let result = complete_line("echo hi | landing", 17, &dir, &env);
+    assert_eq!(result.candidates.len(), 1);
+    assert_eq!(result.candidates[0].value, "landing-tool");
+    assert_eq!(result.candidates[0].kind, CompletionKind::Command);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn variables_complete_from_session_env() {
+    let dir = std::env::temp_dir();
+    let mut env = HashMap::new();
+    env.insert("LANDING_COMPLETION_VAR".to_string(), "value".to_string());
+    let result = complete_line("echo ${LANDING_COMP", 19, &dir, &env);
+    assert_eq!(result.candidates.len(), 1);
+    assert_eq!(result.candidates[0].value, "${LANDING_COMPLETION_VAR}");
+    assert_eq!(result.candidates[0].description.as_deref(), Some("value"));
+    assert_eq!(result.start, 5);
+}
+
+#[test]
+fn git_subcommands_complete() {
+    let dir = std::env::temp_dir();
+    let result = complete_line("git che", 7, &dir, &HashMap::new());
+    let names: Vec<&str> = result.candidates.iter().map(|c| c.value.as_str()).collect();
+    assert_eq!(names, vec!["checkout", "cherry-pick"]);
+    assert!(result.candidates.iter().all(|c| c.kind == CompletionKind::Subcommand));
+}
+
+#[test]
+fn bash_completion_specs_are_harvested() {
+    let dir = fixture("bash");
+    fs::write(
+        dir.join(".bashrc"),
+        "complete -W 'alpha beta albatross' landing-words\n\
+         _landing_fn() { COMPREPLY=( $(compgen -W 'one two' -- \"$2\") ); }\n\
+         complete -F _landing_fn landing-fn\n",
+    )
+    .unwrap();
+    let mut env = HashMap::new();
+    env.insert("HOME".to_string(), dir.to_string_lossy().into_owned());
+
+    let result = complete_line_with("landing-words al", 16, &dir, &env, Some("bash"));
+    let names: Vec<&str> = result.candidates.iter().map(|c| c.value.as_str()).collect();
+    assert_eq!(names, vec!["albatross", "alpha"]);
+    assert!(result.candidates.iter().all(|c| c.kind == CompletionKind::Argument));
+
+    let result = complete_line_with("landing-fn t", 12, &dir, &env, Some("bash"));
+    assert_eq!(result.candidates[0].value, "two");
+
+    // 没有补全定义时退回内置的文件补全
+    let result = complete_line_with("landing-none se", 15, &dir, &env, Some("bash"));
+    assert_eq!(result.candidates[0].value, "setup.sh");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn shell_output_is_parsed_with_descriptions() {
+    let parsed = parse("--all\tshow all\n--all\tagain\nmain\n\n");
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].description.as_deref(), Some("show all"));
+    assert_eq!(parsed[1].value, "main");
+    assert_eq!(parsed[1].description, None);
+}
@@ -0,0 +1,68 @@
+use std::fs;
+use std::sync::Arc;
+
+use app::terminal::correction::{edit_distance, missing_command, parse_aliases, suggest, CorrectionSource};
+use app::terminal::engine;
+use app::terminal::session;
+use app::terminal::sink::MemorySink;
+
+#[test]
+fn not_found_messages_are_recognized() {
+    let lines = |line: &str| vec!["other output".to_string(), line.to_string()];
+    assert_eq!(missing_command(Some(127), &lines("zsh:1: command not found: gti")).as_deref(), Some("gti"));
+    assert_eq!(
+        missing_command(Some(127), &lines("bash: line 1: gti: command not found")).as_deref(),
+        Some("gti")
+    );
+    assert_eq!(missing_command(Some(127), &lines("sh: 1: gti: not found")).as_deref(), Some("gti"));
+    assert_eq!(missing_command(Some(1), &lines("zsh: command not found: gti")), None);
+    assert_eq!(missing_command(Some(127), &lines("no such file")), None);
+}
+
+#[test]
+fn corrections_rank_by_distance_and_source() {
+    assert_eq!(edit_distance("gti", "git"), 1);
+    assert_eq!(edit_distance("dokcer", "docker"), 1);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+
+    let candidates = vec![
+        ("gtk".to_string(), CorrectionSource::Path),
+        ("git".to_string(), CorrectionSource::Path),
+        ("git".to_string(), CorrectionSource::History),
+        ("grep".to_string(), CorrectionSource::Path),
+    ];
+    let corrections = suggest("FOO=1 gti status | less", "gti", &candidates);
+    assert_eq!(corrections.len(), 2);
+    assert_eq!(corrections[0].replacement, "git");
+    assert_eq!(corrections[0].source, CorrectionSource::History);
+    assert_eq!(corrections[0].command, "FOO=1 git status | less");
+    assert_eq!(corrections[1].replacement, "gtk");
+
+    assert_eq!(parse_aliases("alias ll='ls -l'\ngst='git status'\n"), vec!["ll", "gst"]);
+}
+
+#[test]
+fn command_not_found_completion_includes_suggestions() {
+    let dir = std::env::temp_dir().join(format!("landingterm-correction-{}", std::process::id()));
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    fs::write(bin.join("landing-hello"), "#!/bin/sh\necho hello\n").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(bin.join("landing-hello"), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let path = format!("{}:{}", bin.display(), std::env::var("PATH").unwrap_or_default());
+    session::with_session("typo", |s| {
+        s.env.insert("PATH".to_string(), path);
+    })
+    .unwrap();
+
+    let sink = Arc::new(MemorySink::new());
+    engine::execute(sink.clone(), "typo", "landing-helo world").unwrap();
+    let complete = &sink.completions()[0];
+    assert_eq!(complete.code, Some(127));
+    assert_eq!(complete.suggestions[0].command, "landing-hello world");
+    assert_eq!(complete.suggestions[0].source, CorrectionSource::Path);
+    let _ = fs::remove_dir_all(&dir);
+}
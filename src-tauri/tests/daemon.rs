@@ -0,0 +1,113 @@
+#![cfg(unix)]
+
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use app::terminal::daemon::{self, DaemonClient};
+use app::terminal::session::SessionInfo;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// 在临时目录里启动守护进程，返回 socket 路径和令牌
+fn start_daemon(name: &str) -> (PathBuf, String) {
+    let dir = std::env::temp_dir().join(format!("landingterm-daemon-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let socket = dir.join("sessiond.sock");
+    {
+        let (socket, dir) = (socket.clone(), dir.clone());
+        thread::spawn(move || daemon::serve(&socket, &dir));
+    }
+    let deadline = Instant::now() + TIMEOUT;
+    while !socket.exists() {
+        assert!(Instant::now() < deadline, "daemon never started listening");
+        thread::sleep(Duration::from_millis(20));
+    }
+    let token = fs::read_to_string(daemon::token_path(&dir)).unwrap();
+    (socket, token.trim().to_string())
+}
+
+fn connect(socket: &Path, token: &str) -> (Arc<DaemonClient>, Receiver<(String, Value)>) {
+    let (tx, rx) = mpsc::channel();
+    let client = DaemonClient::connect(socket, token, move |event, payload| {
+        let _ = tx.send((event.to_string(), payload));
+    })
+    .unwrap();
+    (client, rx)
+}
+
+fn sessions(client: &DaemonClient) -> Vec<SessionInfo> {
+    serde_json::from_value(client.call("sessions", json!({ "lines": 10 })).unwrap()).unwrap()
+}
+
+// 等到某个终端的命令完成，返回期间收到的输出
+fn wait_for_completion(events: &Receiver<(String, Value)>, terminal_id: &str) -> String {
+    let mut output = String::new();
+    loop {
+        let (event, payload) = events.recv_timeout(TIMEOUT).expect("command never completed");
+        if payload["terminalId"] != terminal_id {
+            continue;
+        }
+        match event.as_str() {
+            "terminal-output" => output.push_str(payload["content"].as_str().unwrap_or_default()),
+            "terminal-command-complete" => return output,
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn wrong_token_is_rejected() {
+    let (socket, token) = start_daemon("token");
+    let err = DaemonClient::connect(&socket, "not-the-token", |_, _| {}).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+
+    let (client, _) = connect(&socket, &token);
+    assert!(client.call("sessions", json!({})).is_ok());
+}
+
+#[test]
+fn sessions_survive_reconnect_until_closed() {
+    let (socket, token) = start_daemon("sessions");
+    let cwd = std::env::temp_dir().canonicalize().unwrap();
+    let (first, first_events) = connect(&socket, &token);
+    let (second, second_events) = connect(&socket, &token);
+
+    first
+        .call("create", json!({ "terminalId": "daemon-session", "cwd": cwd }))
+        .unwrap();
+    first
+        .call("execute", json!({ "terminalId": "daemon-session", "command": "echo from-daemon" }))
+        .unwrap();
+    // 事件广播给所有已连接的客户端
+    assert!(wait_for_completion(&first_events, "daemon-session").contains("from-daemon"));
+    assert!(wait_for_completion(&second_events, "daemon-session").contains("from-daemon"));
+
+    // 应用断开后会话仍然保留在守护进程里
+    first.disconnect();
+    second.disconnect();
+    let deadline = Instant::now() + TIMEOUT;
+    while first.is_connected() {
+        assert!(Instant::now() < deadline, "client never noticed the disconnect");
+        thread::sleep(Duration::from_millis(20));
+    }
+    let (client, _) = connect(&socket, &token);
+    let info = sessions(&client)
+        .into_iter()
+        .find(|info| info.terminal_id == "daemon-session")
+        .expect("session should survive the reconnect");
+    assert_eq!(Path::new(&info.current_dir), cwd);
+    assert!(info.scrollback.iter().any(|line| line.contains("from-daemon")), "{:?}", info.scrollback);
+    // 补全和语法检查用的工作目录来自守护进程里的会话
+    let context = client.call("context", json!({ "terminalId": "daemon-session" })).unwrap();
+    assert_eq!(context[0], json!(cwd));
+
+    client.call("close", json!({ "terminalId": "daemon-session" })).unwrap();
+    assert!(!sessions(&client).iter().any(|info| info.terminal_id == "daemon-session"));
+    assert!(client.call("close", json!({ "terminalId": "daemon-session" })).is_err());
+}
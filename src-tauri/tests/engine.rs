@@ -0,0 +1,264 @@
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use app::terminal::engine::{self, LineDecoder, StreamKind};
+use app::terminal::error::TerminalError;
+use app::terminal::session;
+use app::terminal::shutdown;
+use app::terminal::sink::{ChannelSink, MemorySink, OutputSink, TerminalEvent};
+
+fn run(terminal_id: &str, command: &str) -> Arc<MemorySink> {
+    let sink = Arc::new(MemorySink::new());
+    engine::execute(sink.clone(), terminal_id, command).expect("command should run");
+    sink
+}
+
+fn output_text(sink: &MemorySink) -> String {
+    sink.outputs()
+        .into_iter()
+        .map(|o| o.content)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn echo_emits_output_and_completion() {
+    let sink = run("test-echo", "echo hello-engine");
+
+    assert!(output_text(&sink).contains("hello-engine"));
+    assert!(sink.outputs().iter().all(|o| o.terminal_id == "test-echo"));
+    let completions = sink.completions();
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].code, Some(0));
+    assert!(matches!(sink.events().last(), Some(TerminalEvent::Complete(_))));
+}
+
+#[test]
+fn exit_code_is_reported() {
+    let sink = run("test-exit-code", "echo before; exit 3");
+
+    assert!(output_text(&sink).contains("before"));
+    assert_eq!(sink.completions()[0].code, Some(3));
+}
+
+#[test]
+fn stderr_output_is_forwarded() {
+    let sink = run("test-stderr", "echo oops-on-stderr >&2");
+
+    assert!(output_text(&sink).contains("oops-on-stderr"));
+}
+
+#[test]
+fn cd_changes_directory_for_that_terminal_only() {
+    let dir = std::env::temp_dir().canonicalize().unwrap();
+    let cd = run("test-cd", &format!("cd {}", dir.display()));
+    assert_eq!(cd.outputs()[0].content, "");
+    assert!(cd.completions().is_empty());
+
+    let pwd = run("test-cd", "pwd");
+    assert!(output_text(&pwd).contains(&dir.display().to_string()));
+
+    let other = session::with_session("test-cd-other", |s| s.current_dir.clone()).unwrap();
+    assert_eq!(other, session::home_dir());
+}
+
+#[test]
+fn cd_to_missing_directory_reports_error() {
+    let sink = run("test-cd-missing", "cd /definitely/not/here");
+
+    let outputs = sink.outputs();
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0].output_type, "stderr");
+    assert_eq!(outputs[0].content, "cd: no such directory: /definitely/not/here");
+}
+
+#[test]
+fn channel_sink_streams_events_in_order() {
+    let (tx, rx) = mpsc::channel();
+    let sink: Arc<dyn OutputSink> = Arc::new(ChannelSink::new(tx));
+    engine::execute(sink, "test-channel", "echo one; echo two").unwrap();
+
+    let events: Vec<TerminalEvent> = rx.try_iter().collect();
+    let lines: Vec<String> = events
+        .iter()
+        .filter_map(|e| match e {
+            TerminalEvent::Output(o) => Some(o.content.clone()),
+            _ => None,
+        })
+        .collect();
+    let one = lines.iter().position(|l| l == "one").expect("first line");
+    let two = lines.iter().position(|l| l == "two").expect("second line");
+    assert!(one < two);
+    assert_eq!(events.last().map(|e| e.name()), Some("terminal-command-complete"));
+}
+
+#[test]
+fn stop_interrupts_running_command() {
+    let sink = Arc::new(MemorySink::new());
+    let worker = {
+        let sink: Arc<dyn OutputSink> = sink.clone();
+        thread::spawn(move || engine::execute(sink, "test-stop", "sleep 30"))
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !session::with_session("test-stop", |s| s.running.is_some()).unwrap() {
+        assert!(Instant::now() < deadline, "command never started");
+        thread::sleep(Duration::from_millis(20));
+    }
+    engine::stop(sink.as_ref(), "test-stop").unwrap();
+    worker.join().unwrap().unwrap();
+
+    assert!(output_text(&sink).contains("User has stopped the command"));
+    // 被停止的命令没有退出码
+    assert_eq!(sink.completions()[0].code, None);
+}
+
+#[test]
+fn kill_tree_kills_background_processes_left_in_the_group() {
+    // 用不会重复的时长区分这个 sleep
+    let seconds = format!("30.{}", std::process::id());
+    // 子 shell 退出后 sleep 被挂到 init 下，不再是后代，但仍在命令的进程组里
+    let mut command = std::process::Command::new("sh");
+    command.args(["-c", &format!("(nohup sleep {} >/dev/null 2>&1 &); sleep 30", seconds)]);
+    unsafe {
+        command.pre_exec(|| {
+            libc::setpgid(0, 0);
+            Ok(())
+        });
+    }
+    let mut child = command.spawn().unwrap();
+
+    let running = || {
+        std::process::Command::new("pgrep")
+            // 锚定整条命令行，不匹配包含这段文字的 shell
+            .args(["-f", &format!("^sleep {}$", seconds.replace('.', "\\."))])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    };
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !running() {
+        assert!(Instant::now() < deadline, "background process never started");
+        thread::sleep(Duration::from_millis(20));
+    }
+    shutdown::kill_tree(&mut child).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while running() {
+        assert!(Instant::now() < deadline, "background process survived kill_tree");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn only_one_of_concurrent_commands_starts() {
+    let barrier = Arc::new(Barrier::new(4));
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                engine::execute(Arc::new(MemorySink::new()), "test-concurrent", "sleep 0.5")
+            })
+        })
+        .collect();
+    let results: Vec<_> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+
+    assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1, "{:?}", results);
+    assert!(results
+        .iter()
+        .filter_map(|r| r.as_ref().err())
+        .all(|e| matches!(e, TerminalError::CommandRunning(_))));
+}
+
+#[test]
+fn failed_spawn_releases_the_terminal() {
+    session::with_session("test-spawn-fail", |s| s.current_dir = PathBuf::from("/nonexistent-landingterm-dir")).unwrap();
+    assert!(engine::execute(Arc::new(MemorySink::new()), "test-spawn-fail", "echo hi").is_err());
+
+    session::with_session("test-spawn-fail", |s| s.current_dir = std::env::temp_dir()).unwrap();
+    let sink = run("test-spawn-fail", "echo after-failure");
+    assert!(output_text(&sink).contains("after-failure"));
+}
+
+#[test]
+fn closed_terminal_is_not_recreated_by_running_command() {
+    let sink = Arc::new(MemorySink::new());
+    let worker = {
+        let sink: Arc<dyn OutputSink> = sink.clone();
+        thread::spawn(move || engine::execute(sink, "test-closed", "sleep 0.5; echo late"))
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !session::with_session("test-closed", |s| s.running.is_some()).unwrap() {
+        assert!(Instant::now() < deadline, "command never started");
+        thread::sleep(Duration::from_millis(20));
+    }
+    // 像关闭标签页一样移除会话，命令仍然在跑
+    let _handle = session::remove("test-closed").unwrap();
+    let _ = worker.join().unwrap();
+
+    assert!(output_text(&sink).contains("late"));
+    assert!(!session::ids().contains(&"test-closed".to_string()));
+}
+
+#[test]
+fn cd_in_closed_terminal_fails_without_recreating_it() {
+    let sink = MemorySink::new();
+    let result = engine::change_directory(&sink, "test-closed-cd", "cd /");
+    assert!(matches!(result, Err(TerminalError::TerminalNotFound(_))));
+    assert!(!session::ids().contains(&"test-closed-cd".to_string()));
+}
+
+#[test]
+fn download_progress_replaces_previous_line() {
+    let mut decoder = LineDecoder::new(StreamKind::Stdout, "ollama pull llama3");
+
+    let lines = decoder.feed(b"pulling manifest...\r\n");
+    assert_eq!(lines, vec![("pulling manifest...".to_string(), false)]);
+
+    let lines = decoder.feed(b"pulling 0123456789ab... 12% 1.2 GB/4.7 GB 30 MB/s\r");
+    assert_eq!(
+        lines,
+        vec![
+            ("pulling 0123456789ab...".to_string(), false),
+            ("12% 1.2 GB/4.7 GB 30 MB/s ".to_string(), true),
+        ]
+    );
+}
+
+#[test]
+fn ansi_sequences_are_stripped() {
+    let mut decoder = LineDecoder::new(StreamKind::Stdout, "ls --color");
+
+    let lines = decoder.feed(b"\x1b[1;34msrc\x1b[0m\n");
+    assert_eq!(lines, vec![("src".to_string(), false)]);
+}
+
+#[test]
+fn command_blocks_record_output_and_exit_code() {
+    run("test-blocks", "echo first-block");
+    run("test-blocks", "echo second-block; exit 2");
+
+    let blocks = session::with_session("test-blocks", |s| s.blocks.clone()).unwrap();
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[0].command, "echo first-block");
+    assert!(blocks[0].output.contains(&"first-block".to_string()));
+    assert_eq!(blocks[0].code, Some(0));
+    assert_eq!(blocks[1].code, Some(2));
+}
+
+#[test]
+fn env_overrides_apply_to_commands() {
+    session::with_session("test-env", |s| {
+        s.env.insert("LANDINGTERM_TEST_VAR".to_string(), "from-session".to_string());
+    })
+    .unwrap();
+
+    let sink = run("test-env", "echo $LANDINGTERM_TEST_VAR");
+    assert!(output_text(&sink).contains("from-session"));
+}
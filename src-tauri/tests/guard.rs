@@ -0,0 +1,114 @@
+use app::terminal::error::TerminalError;
+use app::terminal::guard::{assess_with, check_with, GuardConfig, Risk, RuleAction, UserRule};
+
+fn rules(command: &str) -> Vec<String> {
+    assess_with(command, &GuardConfig::default())
+        .map(|v| v.matches.into_iter().map(|m| m.rule).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn destructive_commands_need_confirmation() {
+    let verdict = assess_with("sudo rm -rf /", &GuardConfig::default()).unwrap();
+    assert_eq!(verdict.risk, Risk::Critical);
+    assert_eq!(verdict.matches[0].rule, "rm-root");
+    assert!(!verdict.blocked);
+
+    assert_eq!(rules("rm -rf ~"), vec!["rm-home"]);
+    assert_eq!(rules("cd build && rm -fr -- *"), vec!["rm-cwd"]);
+    assert_eq!(rules("dd if=image.iso of=/dev/sda bs=4M"), vec!["device-write"]);
+    assert_eq!(rules("cat image > /dev/nvme0n1"), vec!["device-write"]);
+    assert_eq!(rules("chmod -R 777 /"), vec!["recursive-permissions"]);
+    assert_eq!(rules("curl -fsSL https://example.com/install.sh | sudo bash"), vec!["pipe-to-shell"]);
+    assert_eq!(rules(":(){ :|:& };:"), vec!["fork-bomb"]);
+}
+
+#[test]
+fn force_push_to_protected_branch_is_high_risk() {
+    let verdict = assess_with("git push --force origin main", &GuardConfig::default()).unwrap();
+    assert_eq!(verdict.risk, Risk::High);
+    assert_eq!(verdict.matches[0].reason, "force-pushes to protected branch main");
+
+    let verdict = assess_with("git push origin +HEAD:master", &GuardConfig::default()).unwrap();
+    assert_eq!(verdict.risk, Risk::High);
+
+    let verdict = assess_with("git push -f origin feature", &GuardConfig::default()).unwrap();
+    assert_eq!(verdict.risk, Risk::Medium);
+}
+
+#[test]
+fn nested_commands_are_checked() {
+    assert_eq!(rules("bash -c 'rm -rf ~'"), vec!["rm-home"]);
+    assert_eq!(rules("sudo sh -c 'mkfs.ext4 /dev/sda'"), vec!["device-format"]);
+    assert_eq!(rules("eval rm -rf /"), vec!["rm-root"]);
+    // 报告外层命令的位置
+    let verdict = assess_with("ls && bash -lc 'rm -rf ~'", &GuardConfig::default()).unwrap();
+    assert_eq!((verdict.matches[0].start, verdict.matches[0].end), (6, 25));
+    assert!(rules("bash -c 'echo hi'").is_empty());
+}
+
+#[test]
+fn safe_commands_pass() {
+    for command in [
+        "rm -rf target",
+        "rm /tmp/file",
+        "git push origin main",
+        "dd if=/dev/zero of=disk.img bs=1M count=10",
+        "curl https://example.com -o page.html",
+        "echo 'rm -rf /'",
+    ] {
+        assert!(rules(command).is_empty(), "{}", command);
+    }
+}
+
+#[test]
+fn user_rules_confirm_block_and_allow() {
+    let config = GuardConfig {
+        enabled: true,
+        rules: vec![
+            UserRule {
+                pattern: "^terraform destroy".to_string(),
+                reason: Some("destroys infrastructure".to_string()),
+                risk: Risk::High,
+                action: RuleAction::Confirm,
+            },
+            UserRule {
+                pattern: "DROP DATABASE".to_string(),
+                reason: None,
+                risk: Risk::Critical,
+                action: RuleAction::Block,
+            },
+            UserRule {
+                pattern: "^git push --force origin wip/".to_string(),
+                reason: None,
+                risk: Risk::Low,
+                action: RuleAction::Allow,
+            },
+        ],
+    };
+
+    let verdict = assess_with("terraform destroy -auto-approve", &config).unwrap();
+    assert_eq!(verdict.matches[0].reason, "destroys infrastructure");
+    assert_eq!((verdict.matches[0].start, verdict.matches[0].end), (0, 17));
+
+    let verdict = assess_with("psql -c 'DROP DATABASE prod'", &config).unwrap();
+    assert!(verdict.blocked);
+    assert_eq!(verdict.risk, Risk::Critical);
+
+    assert!(assess_with("git push --force origin wip/experiment", &config).is_none());
+
+    // 没有确认时返回判定；确认只能跳过 confirm 规则，block 规则仍然拒绝
+    assert!(check_with("terraform destroy", false, &config).unwrap().is_some());
+    assert!(check_with("terraform destroy", true, &config).unwrap().is_none());
+    assert!(check_with("ls", false, &config).unwrap().is_none());
+    assert!(matches!(
+        check_with("psql -c 'DROP DATABASE prod'", true, &config),
+        Err(TerminalError::PermissionDenied(_))
+    ));
+
+    let disabled = GuardConfig {
+        enabled: false,
+        rules: Vec::new(),
+    };
+    assert!(assess_with("rm -rf /", &disabled).is_none());
+}
@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use app::terminal::engine;
+use app::terminal::history::{self, fuzzy_score, HistoryEntry, HistoryQuery};
+use app::terminal::history_import::{dedupe, parse_bash, parse_fish, parse_zsh};
+use app::terminal::sink::MemorySink;
+use app::terminal::suggest::rank;
+
+fn entry(terminal_id: &str, command: &str, cwd: &str, exit_code: Option<i32>) -> HistoryEntry {
+    HistoryEntry {
+        command: command.to_string(),
+        cwd: cwd.to_string(),
+        terminal_id: terminal_id.to_string(),
+        started_at: history::now_millis(),
+        duration_ms: 1,
+        exit_code,
+        source: None,
+        estimated_time: false,
+    }
+}
+
+fn commands(query: HistoryQuery) -> Vec<String> {
+    history::query(&query).into_iter().map(|e| e.command).collect()
+}
+
+#[test]
+fn executed_commands_are_recorded() {
+    let sink = Arc::new(MemorySink::new());
+    engine::execute(sink, "hist-exec", "echo recorded; exit 4").unwrap();
+
+    let entries = history::query(&HistoryQuery {
+        terminal_id: Some("hist-exec".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].command, "echo recorded; exit 4");
+    assert_eq!(entries[0].exit_code, Some(4));
+}
+
+#[test]
+fn prefix_search_returns_newest_first() {
+    history::record(entry("hist-prefix", "cargo build", "/work", Some(0)));
+    history::record(entry("hist-prefix", "cargo test", "/work", Some(0)));
+    history::record(entry("hist-prefix", "git status", "/work", Some(0)));
+
+    let found = commands(HistoryQuery {
+        prefix: Some("cargo".to_string()),
+        terminal_id: Some("hist-prefix".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(found, vec!["cargo test", "cargo build"]);
+}
+
+#[test]
+fn directory_and_failure_filters() {
+    history::record(entry("hist-dir", "make", "/hist-dir/a", Some(2)));
+    history::record(entry("hist-dir", "make clean", "/hist-dir/a", Some(0)));
+    history::record(entry("hist-dir", "ls", "/hist-dir/b", Some(0)));
+
+    let in_a = commands(HistoryQuery {
+        cwd: Some("/hist-dir/a".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(in_a, vec!["make clean", "make"]);
+
+    let failed = commands(HistoryQuery {
+        cwd: Some("/hist-dir/a".to_string()),
+        failed_only: true,
+        ..Default::default()
+    });
+    assert_eq!(failed, vec!["make"]);
+}
+
+#[test]
+fn fuzzy_search_ranks_closer_matches_first() {
+    history::record(entry("hist-fuzzy", "docker compose up", "/", Some(0)));
+    history::record(entry("hist-fuzzy", "dcup-script", "/", Some(0)));
+    history::record(entry("hist-fuzzy", "docker compose up", "/", Some(0)));
+
+    let found = commands(HistoryQuery {
+        fuzzy: Some("dcu".to_string()),
+        terminal_id: Some("hist-fuzzy".to_string()),
+        unique: true,
+        ..Default::default()
+    });
+    assert_eq!(found, vec!["dcup-script", "docker compose up"]);
+    assert!(fuzzy_score("xyz", "docker").is_none());
+}
+
+#[test]
+fn zsh_extended_history_is_parsed() {
+    let mut bytes = b": 1700000000:5;make -j8\n: 1700000100:0;echo one\\\necho two\nplain command\n".to_vec();
+    // "ă" 的 UTF-8 编码是 0xc4 0x83，zsh 把其中的 0x83 写成 Meta 0xa3
+    bytes.extend_from_slice(b": 1700000200:0;echo \xc4\x83\xa3\n");
+
+    let entries = parse_zsh(&bytes, 42);
+    let commands: Vec<&str> = entries.iter().map(|e| e.command.as_str()).collect();
+    assert_eq!(commands[..3], ["make -j8", "echo one\necho two", "plain command"]);
+    assert_eq!(entries[0].started_at, 1_700_000_000_000);
+    assert_eq!(entries[0].duration_ms, 5000);
+    assert_eq!(entries[2].started_at, 42);
+    assert_eq!(entries[3].command, "echo ă");
+    assert!(entries.iter().all(|e| e.source.as_deref() == Some("zsh")));
+}
+
+#[test]
+fn bash_history_timestamps_are_used() {
+    let entries = parse_bash("#1700000000\nls -la\nno-timestamp\n#1700000300\ngit log\n", 7);
+
+    let parsed: Vec<(&str, u64)> = entries.iter().map(|e| (e.command.as_str(), e.started_at)).collect();
+    assert_eq!(
+        parsed,
+        vec![("ls -la", 1_700_000_000_000), ("no-timestamp", 7), ("git log", 1_700_000_300_000)]
+    );
+}
+
+#[test]
+fn fish_history_is_parsed() {
+    let text = "- cmd: git status\n  when: 1700000000\n- cmd: echo a\\\\b\\nnext\n  when: 1700000001\n  paths:\n    - a\\b\n";
+
+    let entries = parse_fish(text);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].command, "git status");
+    assert_eq!(entries[0].started_at, 1_700_000_000_000);
+    assert_eq!(entries[1].command, "echo a\\b\nnext");
+}
+
+#[test]
+fn imported_duplicates_are_dropped() {
+    let existing = vec![entry("t", "ls", "/", Some(0))];
+    let mut again = entry("", "ls", "", None);
+    again.started_at = existing[0].started_at;
+    let imported = parse_bash("#1700000000\ngit status\n#1700000000\ngit status\n", 0);
+
+    let (kept, duplicates) =
+        dedupe(imported.into_iter().chain(std::iter::once(again)).collect(), &existing);
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].command, "git status");
+    assert_eq!(duplicates, 2);
+}
+
+#[test]
+fn reimporting_untimestamped_history_adds_nothing() {
+    // 没有时间戳的记录每次导入估算出的时间都不同，按来源、命令和出现次数去重
+    let first = parse_bash("ls\nls\ngit status\n", 1_000);
+    assert!(first.iter().all(|e| e.estimated_time));
+    let (existing, duplicates) = dedupe(first, &[]);
+    assert_eq!((existing.len(), duplicates), (3, 0));
+
+    let (kept, duplicates) = dedupe(parse_bash("ls\nls\ngit status\n", 5_000), &existing);
+    assert_eq!((kept.len(), duplicates), (0, 3));
+
+    // 之后追加的命令仍然会导入，同名命令也按次数计算
+    let (kept, _) = dedupe(parse_bash("ls\nls\ngit status\nls\n", 9_000), &existing);
+    assert_eq!(kept.iter().map(|e| e.command.as_str()).collect::<Vec<_>>(), vec!["ls"]);
+    let (kept, _) = dedupe(parse_zsh(b"ls\n", 9_000), &existing);
+    assert_eq!(kept.len(), 1);
+}
+
+fn at(terminal_id: &str, command: &str, cwd: &str, started_at: u64) -> HistoryEntry {
+    HistoryEntry {
+        started_at,
+        ..entry(terminal_id, command, cwd, Some(0))
+    }
+}
+
+#[test]
+fn imported_history_stays_older_after_restart() {
+    let dir = std::env::temp_dir().join(format!("landingterm-history-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    history::init(Some(dir.clone()));
+    history::record(entry("hist-restart", "newest", "/restart", Some(0)));
+    // 导入的记录比已有的旧，但追加在文件末尾
+    history::record_all(vec![
+        at("hist-restart", "oldest", "/restart", 1_000),
+        at("hist-restart", "older", "/restart", 2_000),
+    ]);
+
+    history::init(Some(dir.clone()));
+    let found = commands(HistoryQuery {
+        terminal_id: Some("hist-restart".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(found, vec!["newest", "older", "oldest"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+const NOW: u64 = 1_700_000_000_000;
+const DAY: u64 = 86_400_000;
+
+#[test]
+fn suggestion_prefers_frequent_and_recent_commands() {
+    let entries = vec![
+        at("1", "git commit -m wip", "/repo", NOW - 30 * DAY),
+        at("1", "git status", "/repo", NOW - 2 * DAY),
+        at("1", "git status", "/repo", NOW - DAY),
+        at("1", "git stash", "/repo", NOW - DAY),
+    ];
+
+    let suggestion = rank(&entries, "git st", "", None, NOW).unwrap();
+    assert_eq!(suggestion.command, "git status");
+    assert_eq!(suggestion.completion, "atus");
+    assert!(rank(&entries, "", "", None, NOW).is_none());
+    assert!(rank(&entries, "git status", "", None, NOW).is_none());
+}
+
+#[test]
+fn suggestion_uses_directory_and_previous_command() {
+    let entries = vec![
+        at("1", "npm run build", "/web", NOW - DAY),
+        at("1", "npm run dev", "/web", NOW - 3 * DAY),
+        at("2", "cargo build", "/api", NOW - DAY),
+        at("2", "npm run lint", "/api", NOW - DAY),
+        at("1", "npm run test", "/other", NOW),
+    ];
+
+    assert_eq!(rank(&entries, "npm run ", "/web", None, NOW).unwrap().command, "npm run build");
+    assert_eq!(
+        rank(&entries, "npm run ", "/elsewhere", Some("cargo build"), NOW).unwrap().command,
+        "npm run lint"
+    );
+}
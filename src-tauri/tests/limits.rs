@@ -0,0 +1,103 @@
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use app::terminal::engine;
+use app::terminal::limits::{LimitKind, ResourceLimits};
+use app::terminal::sink::{CommandComplete, MemorySink};
+
+fn run(terminal_id: &str, command: &str, limits: ResourceLimits) -> (CommandComplete, String) {
+    let sink = Arc::new(MemorySink::new());
+    engine::execute_with_limits(sink.clone(), terminal_id, command, limits).expect("command should run");
+    let output = sink.outputs().into_iter().map(|o| o.content).collect::<Vec<_>>().join("\n");
+    (sink.completions().remove(0), output)
+}
+
+#[test]
+fn timeout_stops_the_command() {
+    let started = Instant::now();
+    let (complete, output) = run(
+        "limits-timeout",
+        "echo started; sleep 10",
+        ResourceLimits {
+            timeout_secs: Some(1),
+            ..ResourceLimits::default()
+        },
+    );
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(complete.limit_exceeded, Some(LimitKind::Timeout));
+    // 进程树被 SIGKILL 终止，128 + 9
+    assert_eq!(complete.code, Some(137));
+    assert!(output.contains("started"));
+    assert!(output.contains("exceeded the maximum runtime of 1s"), "{}", output);
+}
+
+#[test]
+fn sub_second_timeouts_round_up() {
+    let limits = ResourceLimits::default().with_timeout(Some(Duration::from_millis(500)));
+    assert_eq!(limits.timeout_secs, Some(1));
+    let limits = limits.with_timeout(Some(Duration::from_secs(3)));
+    assert_eq!(limits.timeout_secs, Some(1));
+}
+
+#[test]
+fn output_limit_stops_noisy_commands() {
+    let (complete, output) = run(
+        "limits-output",
+        "yes landingterm",
+        ResourceLimits {
+            output_bytes: Some(64 * 1024),
+            ..ResourceLimits::default()
+        },
+    );
+    assert_eq!(complete.limit_exceeded, Some(LimitKind::Output));
+    assert!(output.len() < 128 * 1024);
+    assert!(output.contains("output limit of 65536 bytes"));
+}
+
+#[test]
+fn cpu_and_open_file_rlimits_apply() {
+    let (complete, output) = run(
+        "limits-files",
+        "ulimit -n",
+        ResourceLimits {
+            open_files: Some(64),
+            ..ResourceLimits::default()
+        },
+    );
+    assert_eq!(complete.code, Some(0));
+    assert_eq!(complete.limit_exceeded, None);
+    assert!(output.lines().any(|l| l.trim() == "64"), "{}", output);
+
+    let (complete, _) = run(
+        "limits-cpu",
+        "while :; do :; done",
+        ResourceLimits {
+            cpu_secs: Some(1),
+            timeout_secs: Some(20),
+            ..ResourceLimits::default()
+        },
+    );
+    assert_eq!(complete.limit_exceeded, Some(LimitKind::Cpu));
+}
+
+#[test]
+fn memory_limit_is_reported() {
+    if Command::new("python3").arg("-c").arg("pass").status().map(|s| !s.success()).unwrap_or(true) {
+        eprintln!("python3 not available, skipping");
+        return;
+    }
+    let (complete, _) = run(
+        "limits-memory",
+        "python3 -c 'x = bytearray(512 * 1024 * 1024)'",
+        ResourceLimits {
+            memory_bytes: Some(128 * 1024 * 1024),
+            ..ResourceLimits::default()
+        },
+    );
+    assert_ne!(complete.code, Some(0));
+    assert_eq!(complete.limit_exceeded, Some(LimitKind::Memory));
+
+    let (complete, _) = run("limits-none", "echo fine", ResourceLimits::default());
+    assert_eq!(complete.limit_exceeded, None);
+}
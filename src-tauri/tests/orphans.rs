@@ -0,0 +1,84 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use app::terminal::error::TerminalError;
+use app::terminal::orphans::{self, OrphanAction, ProcessRecord};
+
+// 在后台启动一个不是测试进程子进程的 sleep（会被 init 回收），返回它的 pid
+fn detached_sleep() -> u32 {
+    let output = Command::new("sh")
+        .args(["-c", "sleep 30 >/dev/null 2>&1 & echo $!"])
+        .output()
+        .unwrap();
+    String::from_utf8_lossy(&output.stdout).trim().parse().unwrap()
+}
+
+fn alive(pid: u32) -> bool {
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+fn pids(records: &[ProcessRecord]) -> Vec<u32> {
+    let mut pids: Vec<u32> = records.iter().map(|r| r.pid).collect();
+    pids.sort_unstable();
+    pids
+}
+
+// 注册表路径是全局状态，整个流程放在一个测试里按顺序检查
+#[test]
+fn leftover_processes_are_found_and_resolved() {
+    let dir = std::env::temp_dir().join(format!("landingterm-orphans-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let registry = dir.join("processes.json");
+    orphans::init(Some(dir.clone()));
+    assert!(orphans::orphans().is_empty());
+
+    let killed = detached_sleep();
+    let ignored = detached_sleep();
+    orphans::record("orphans-a", killed, "sleep 30");
+    orphans::record("orphans-b", ignored, "sleep 30");
+    let mut expected = vec![killed, ignored];
+    expected.sort_unstable();
+    let mut saved: Vec<ProcessRecord> = serde_json::from_str(&fs::read_to_string(&registry).unwrap()).unwrap();
+    assert_eq!(pids(&saved), expected);
+    assert!(saved.iter().all(|r| r.process_start.is_some()));
+
+    // pid 还在但启动时间对不上：已经被别的进程复用了
+    saved.push(ProcessRecord {
+        pid: std::process::id(),
+        pgid: std::process::id(),
+        command: "reused".to_string(),
+        terminal_id: "orphans-c".to_string(),
+        started_at: 0,
+        process_start: Some("Thu Jan  1 00:00:00 1970".to_string()),
+    });
+    fs::write(&registry, serde_json::to_string(&saved).unwrap()).unwrap();
+
+    // 像下次启动一样重新读取注册表
+    orphans::init(Some(dir.clone()));
+    assert_eq!(pids(&orphans::orphans()), expected);
+
+    orphans::resolve(killed, OrphanAction::Kill).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while alive(killed) {
+        assert!(Instant::now() < deadline, "orphan survived kill");
+        thread::sleep(Duration::from_millis(20));
+    }
+    orphans::resolve(ignored, OrphanAction::Ignore).unwrap();
+    assert!(alive(ignored));
+    assert!(orphans::orphans().is_empty());
+    let saved: Vec<ProcessRecord> = serde_json::from_str(&fs::read_to_string(&registry).unwrap()).unwrap();
+    assert!(saved.is_empty());
+    assert!(matches!(
+        orphans::resolve(killed, OrphanAction::Kill),
+        Err(TerminalError::ProcessExited(_))
+    ));
+
+    unsafe {
+        libc::kill(ignored as libc::pid_t, libc::SIGKILL);
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
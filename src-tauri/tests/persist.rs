@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use app::terminal::persist::{self, SessionSnapshot};
+use app::terminal::session::{self, CommandBlock};
+use app::terminal::sink::MemorySink;
+
+fn snapshot(terminal_id: &str, startup_command: Option<&str>) -> SessionSnapshot {
+    SessionSnapshot {
+        terminal_id: terminal_id.to_string(),
+        title: Some(format!("{} title", terminal_id)),
+        current_dir: std::env::temp_dir(),
+        env: HashMap::from([("PERSIST_TEST".to_string(), terminal_id.to_string())]),
+        startup_command: startup_command.map(str::to_string),
+        blocks: vec![CommandBlock {
+            command: "make".to_string(),
+            directory: "/tmp".to_string(),
+            output: vec!["built".to_string()],
+            code: Some(0),
+        }],
+        scrollback: vec!["$ make".to_string(), "built".to_string()],
+        profile: None,
+        sandbox: None,
+    }
+}
+
+// 快照路径和 RESTORED 都是全局状态，整个流程放在一个测试里按顺序检查
+#[test]
+fn sessions_round_trip_through_the_snapshot_file() {
+    let dir = std::env::temp_dir().join(format!("landingterm-persist-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("sessions.json");
+    let saved = vec![
+        snapshot("persist-a", Some("echo started")),
+        snapshot("persist-b", Some("rm -rf ~")),
+        snapshot("persist-c", None),
+    ];
+    let content = serde_json::to_string_pretty(&saved).unwrap();
+    fs::write(&file, &content).unwrap();
+    persist::init(Some(dir.clone()));
+
+    // 恢复之前不保存，启动时的空会话不能覆盖上次的快照
+    persist::save();
+    assert_eq!(fs::read_to_string(&file).unwrap(), content);
+
+    let startup = persist::restore().unwrap();
+    assert_eq!(
+        startup,
+        vec![
+            ("persist-a".to_string(), "echo started".to_string()),
+            ("persist-b".to_string(), "rm -rf ~".to_string()),
+        ]
+    );
+    // 只有第一次调用生效
+    assert!(persist::restore().unwrap().is_empty());
+
+    let restored: Vec<SessionSnapshot> =
+        persist::snapshot().into_iter().filter(|s| s.terminal_id.starts_with("persist-")).collect();
+    assert_eq!(restored, saved);
+
+    session::with_existing("persist-c", |s| s.title = Some("renamed".to_string())).unwrap();
+    persist::save();
+    let written: Vec<SessionSnapshot> = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+    let renamed = written.iter().find(|s| s.terminal_id == "persist-c").unwrap();
+    assert_eq!(renamed.title.as_deref(), Some("renamed"));
+
+    // 危险的启动命令不会自动运行，其余的照常运行
+    let sink = Arc::new(MemorySink::new());
+    persist::run_startup_commands(sink.clone(), startup);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while sink.completions().is_empty() {
+        assert!(Instant::now() < deadline, "startup command never completed");
+        thread::sleep(Duration::from_millis(20));
+    }
+    let completions = sink.completions();
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].terminal_id, "persist-a");
+    let outputs = sink.outputs();
+    assert!(outputs.iter().any(|o| o.terminal_id == "persist-a" && o.content.contains("started")));
+    let skipped = outputs.iter().find(|o| o.terminal_id == "persist-b").unwrap();
+    assert!(skipped.content.contains("was not run (needs confirmation"), "{}", skipped.content);
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use app::terminal::engine;
+use app::terminal::error::TerminalError;
+use app::terminal::policy::{self, check_with, Policy, PolicyRule};
+use app::terminal::sink::MemorySink;
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("landingterm-policy-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir.canonicalize().unwrap()
+}
+
+fn violation(command: &str, cwd: &Path, policy: &Policy) -> Option<(PolicyRule, String)> {
+    check_with(command, cwd, "test", policy).err().map(|v| (v.rule, v.target))
+}
+
+#[test]
+fn allowlist_only_runs_listed_executables() {
+    let policy = Policy {
+        allowed_executables: Some(vec!["ls".to_string(), "git".to_string(), "/usr/bin/make".to_string()]),
+        ..Policy::default()
+    };
+    let cwd = Path::new("/tmp");
+
+    assert_eq!(violation("cd src && ls -la | git status", cwd, &policy), None);
+    assert_eq!(violation("if true; then /usr/bin/make; fi", cwd, &policy), None);
+    assert_eq!(
+        violation("ls | grep foo", cwd, &policy),
+        Some((PolicyRule::ExecutableNotAllowed, "grep".to_string()))
+    );
+    // 只写命令名的条目不匹配任意路径下的同名文件
+    assert_eq!(
+        violation("./ls", cwd, &policy),
+        Some((PolicyRule::ExecutableNotAllowed, "./ls".to_string()))
+    );
+    assert_eq!(
+        violation("(ls; python3 -c 1)", cwd, &policy),
+        Some((PolicyRule::ExecutableNotAllowed, "python3".to_string()))
+    );
+    assert_eq!(
+        violation("ls $(cat list)", cwd, &policy).map(|v| v.0),
+        Some(PolicyRule::CommandSubstitution)
+    );
+}
+
+#[test]
+fn denied_and_network_tools_are_rejected_behind_wrappers() {
+    let policy = Policy {
+        denied_executables: vec!["rm".to_string()],
+        allow_network: false,
+        ..Policy::default()
+    };
+    let cwd = Path::new("/tmp");
+
+    assert_eq!(violation("sudo /bin/rm -f x", cwd, &policy), Some((PolicyRule::ExecutableDenied, "/bin/rm".to_string())));
+    assert_eq!(
+        violation("echo hi && env FOO=1 curl https://example.com", cwd, &policy),
+        Some((PolicyRule::NetworkDenied, "curl".to_string()))
+    );
+    assert_eq!(violation("ssh host", cwd, &policy).map(|v| v.0), Some(PolicyRule::NetworkDenied));
+    assert_eq!(violation("echo curl", cwd, &policy), None);
+    assert_eq!(violation("curl x", cwd, &Policy::default()), None);
+
+    let err = check_with("nc -l 80", cwd, "kiosk", &policy).unwrap_err();
+    assert_eq!((err.start, err.end), (0, 8));
+    assert_eq!(err.to_string(), "network tool `nc` is not allowed (profile kiosk)");
+}
+
+#[test]
+fn nested_commands_and_substitutions_are_checked() {
+    let policy = Policy {
+        denied_executables: vec!["rm".to_string()],
+        allow_network: false,
+        ..Policy::default()
+    };
+    let cwd = Path::new("/tmp");
+    let rule = |command: &str| violation(command, cwd, &policy).map(|v| v.0);
+
+    // sh -c、eval、env -S 执行的字符串
+    assert_eq!(rule("sh -c 'curl https://example.com'"), Some(PolicyRule::NetworkDenied));
+    assert_eq!(rule("bash -lc \"echo hi; rm -rf x\""), Some(PolicyRule::ExecutableDenied));
+    assert_eq!(rule("sudo bash -c 'sh -c \"wget x\"'"), Some(PolicyRule::NetworkDenied));
+    assert_eq!(rule("eval curl example.com"), Some(PolicyRule::NetworkDenied));
+    assert_eq!(rule("env -S 'curl example.com'"), Some(PolicyRule::NetworkDenied));
+    assert_eq!(rule("bash -c 'echo hi'"), None);
+
+    // 带参数的选项后面才是真正的命令
+    assert_eq!(rule("env -u HOME curl example.com"), Some(PolicyRule::NetworkDenied));
+    assert_eq!(rule("find . -name '*.tmp' | xargs -n 1 rm"), Some(PolicyRule::ExecutableDenied));
+    assert_eq!(rule("timeout -s KILL 5 ssh host"), Some(PolicyRule::NetworkDenied));
+
+    // 只要有限制，替换里的命令就无法事先检查
+    assert_eq!(rule("echo $(curl example.com)"), Some(PolicyRule::CommandSubstitution));
+    assert_eq!(rule("`echo cu`rl example.com"), Some(PolicyRule::CommandSubstitution));
+    assert_eq!(rule("diff <(ls a) <(ls b)"), Some(PolicyRule::CommandSubstitution));
+    assert_eq!(violation("echo $(date)", cwd, &Policy::default()), None);
+
+    // 命令名里的变量同样无法事先确定
+    assert_eq!(rule("c=curl; $c example.com"), Some(PolicyRule::VariableCommand));
+    assert_eq!(rule("${X}rm -rf /forbidden"), Some(PolicyRule::VariableCommand));
+    assert_eq!(rule("sudo $c example.com"), Some(PolicyRule::VariableCommand));
+    assert_eq!(rule("echo $HOME"), None);
+    assert_eq!(violation("$EDITOR notes.txt", cwd, &Policy::default()), None);
+
+    let deep = "sh -c 'sh -c \"sh -c ls\"'";
+    let mut nested = deep.to_string();
+    for _ in 0..4 {
+        nested = format!("sh -c {:?}", nested);
+    }
+    assert_eq!(rule(deep), None);
+    assert_eq!(rule(&nested), Some(PolicyRule::NestedCommand));
+}
+
+#[test]
+fn forbidden_paths_cover_arguments_and_redirections() {
+    let root = scratch("paths");
+    let secret = root.join("secret");
+    fs::create_dir_all(&secret).unwrap();
+    let work = root.join("work");
+    fs::create_dir_all(&work).unwrap();
+    let policy = Policy {
+        forbidden_paths: vec![secret.to_string_lossy().into_owned()],
+        ..Policy::default()
+    };
+
+    assert_eq!(violation("cat notes.txt > out.txt", &work, &policy), None);
+    assert_eq!(violation("cat ../secret/key", &work, &policy).map(|v| v.0), Some(PolicyRule::ForbiddenPath));
+    assert_eq!(violation("cd ../secret", &work, &policy).map(|v| v.0), Some(PolicyRule::ForbiddenPath));
+    assert_eq!(violation("echo x 2>&1 >../secret/log", &work, &policy).map(|v| v.0), Some(PolicyRule::ForbiddenPath));
+    assert_eq!(violation("tar --file=../secret/a.tar -c .", &work, &policy).map(|v| v.0), Some(PolicyRule::ForbiddenPath));
+    assert_eq!(violation("cat ../secretive", &work, &policy), None);
+
+    // 通过符号链接访问同样被拒绝
+    #[cfg(unix)]
+    {
+        let link = work.join("link");
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&secret, &link).unwrap();
+        assert_eq!(violation("ls link", &work, &policy).map(|v| v.0), Some(PolicyRule::ForbiddenPath));
+    }
+
+    let home = Policy {
+        forbidden_paths: vec!["~/.ssh".to_string()],
+        ..Policy::default()
+    };
+    assert_eq!(violation("cat ~/.ssh/id_rsa", &work, &home).map(|v| v.0), Some(PolicyRule::ForbiddenPath));
+    assert_eq!(violation("cat $HOME/.ssh/config", &work, &home).map(|v| v.0), Some(PolicyRule::ForbiddenPath));
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn engine_enforces_profiles_and_max_runtime() {
+    let data = scratch("engine");
+    fs::write(
+        data.join("policy.json"),
+        r#"{ "profiles": {
+              "kiosk": { "allowed_executables": ["sleep"], "allow_network": false, "max_runtime_secs": 1 } } }"#,
+    )
+    .unwrap();
+    policy::init(Some(data.clone()));
+
+    // 没有默认 profile 的终端不受限制
+    let sink = Arc::new(MemorySink::new());
+    engine::execute(sink.clone(), "policy-free", "echo free").unwrap();
+    assert_eq!(sink.completions()[0].code, Some(0));
+
+    policy::set_profile("policy-kiosk", Some("kiosk".to_string())).unwrap();
+    assert!(policy::set_profile("policy-kiosk-2", Some("missing".to_string())).is_err());
+
+    let sink = Arc::new(MemorySink::new());
+    match engine::execute(sink.clone(), "policy-kiosk", "curl https://example.com") {
+        Err(TerminalError::PolicyViolation(v)) => {
+            assert_eq!(v.profile, "kiosk");
+            assert_eq!(v.rule, PolicyRule::ExecutableNotAllowed);
+        }
+        other => panic!("expected a policy violation, got {:?}", other),
+    }
+    assert!(sink.events().is_empty());
+    let log = fs::read_to_string(data.join("policy.log")).unwrap();
+    assert!(log.contains("\"rule\":\"executable_not_allowed\""), "{}", log);
+    assert!(log.contains("policy-kiosk"));
+
+    let sink = Arc::new(MemorySink::new());
+    let started = Instant::now();
+    engine::execute(sink.clone(), "policy-kiosk", "sleep 10").unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_ne!(sink.completions()[0].code, Some(0));
+    assert!(sink
+        .outputs()
+        .iter()
+        .any(|o| o.content.contains("exceeded the maximum runtime of 1s")));
+
+    // 锁定后不能再切换 profile
+    fs::write(data.join("policy.json"), r#"{ "locked": true, "default_profile": "kiosk", "profiles": { "kiosk": {} } }"#)
+        .unwrap();
+    policy::init(Some(data.clone()));
+    assert!(matches!(
+        policy::set_profile("policy-kiosk", None),
+        Err(TerminalError::PermissionDenied(_))
+    ));
+    let _ = fs::remove_dir_all(&data);
+}
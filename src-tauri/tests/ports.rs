@@ -0,0 +1,62 @@
+use std::net::IpAddr;
+use std::process::Command;
+use std::sync::Arc;
+
+use app::terminal::engine;
+use app::terminal::ports::{self, Protocol};
+use app::terminal::procfs;
+use app::terminal::sink::MemorySink;
+
+const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4101 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:D2F0 0100007F:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 4102 1 0000000000000000 20 4 30 10 -1";
+
+const TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000000000000:1435 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4103 1 0000000000000000 100 0 0 10 0";
+
+const UDP: &str = "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  100: 00000000:14E9 00000000:0000 07 00000000:00000000 00:00000000 00000000  1000        0 4104 2 0000000000000000 0
+  101: 0100007F:9C40 0100007F:0035 01 00000000:00000000 00:00000000 00000000  1000        0 4105 2 0000000000000000 0";
+
+#[test]
+fn parses_listening_sockets() {
+    let tcp = ports::parse_sockets(TCP, Protocol::Tcp);
+    assert_eq!(tcp.len(), 1);
+    assert_eq!(tcp[0].port, 8080);
+    assert_eq!(tcp[0].inode, 4101);
+    if cfg!(target_endian = "little") {
+        assert_eq!(tcp[0].address, "127.0.0.1".parse::<IpAddr>().unwrap());
+    }
+
+    let tcp6 = ports::parse_sockets(TCP6, Protocol::Tcp);
+    assert_eq!(tcp6[0].port, 5173);
+    assert_eq!(tcp6[0].address, "::".parse::<IpAddr>().unwrap());
+
+    // 已连接的 UDP socket 不算监听
+    let udp = ports::parse_sockets(UDP, Protocol::Udp);
+    assert_eq!(udp.len(), 1);
+    assert_eq!(udp[0].port, 5353);
+}
+
+#[test]
+fn reports_ports_opened_by_commands() {
+    let python = Command::new("python3").arg("-c").arg("pass").status().map(|s| s.success()).unwrap_or(false);
+    if !python || procfs::stat(std::process::id()).is_none() {
+        eprintln!("python3 or /proc not available, skipping");
+        return;
+    }
+    let sink = Arc::new(MemorySink::new());
+    let script = "import socket, time\n\
+                  s = socket.socket(); s.bind(('127.0.0.1', 0)); s.listen()\n\
+                  print(s.getsockname()[1], flush=True)\n\
+                  time.sleep(2.5)";
+    engine::execute(sink.clone(), "ports-listen", &format!("python3 -c \"{}\"", script)).unwrap();
+
+    let port: u16 = sink.outputs()[0].content.trim().parse().expect("script prints its port");
+    let opened = sink.ports();
+    assert_eq!(opened.len(), 1, "{:?}", opened);
+    assert_eq!(opened[0].port, port);
+    assert_eq!(opened[0].protocol, Protocol::Tcp);
+    assert_eq!(opened[0].terminal_id, "ports-listen");
+    assert!(opened[0].pid > 0);
+}
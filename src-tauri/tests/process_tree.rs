@@ -0,0 +1,91 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use app::terminal::engine;
+use app::terminal::error::TerminalError;
+use app::terminal::process_tree::{self, ProcessNode};
+use app::terminal::procfs::{self, ProcStat};
+use app::terminal::sink::MemorySink;
+
+fn stat(pid: u32, ppid: u32, comm: &str) -> ProcStat {
+    procfs::parse_stat(&format!(
+        "{} ({}) S {} {} 0 0 -1 0 0 0 0 0 1 1 0 0 20 0 1 0 100 1000 10",
+        pid, comm, ppid, pid
+    ))
+    .unwrap()
+}
+
+fn find<'a>(node: &'a ProcessNode, name: &str) -> Option<&'a ProcessNode> {
+    if node.name == name {
+        return Some(node);
+    }
+    node.children.iter().find_map(|child| find(child, name))
+}
+
+#[test]
+fn builds_nested_tree() {
+    // pid 不存在，argv 和 cwd 为空
+    let processes = vec![
+        stat(999_990, 1, "zsh"),
+        stat(999_991, 999_990, "make"),
+        stat(999_992, 999_991, "cc"),
+        stat(999_993, 999_990, "tee"),
+    ];
+    let root = process_tree::build(&processes).unwrap();
+    assert_eq!(root.name, "zsh");
+    assert_eq!(root.children.len(), 2);
+    assert_eq!(root.children[0].children[0].name, "cc");
+    assert!(root.children[0].children[0].argv.is_empty());
+    assert_eq!(process_tree::build(&[]), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn parses_signal_names() {
+    assert_eq!(process_tree::parse_signal("TERM"), Some(libc::SIGTERM));
+    assert_eq!(process_tree::parse_signal("sigkill"), Some(libc::SIGKILL));
+    assert_eq!(process_tree::parse_signal("stop"), Some(libc::SIGSTOP));
+    assert_eq!(process_tree::parse_signal("BOGUS"), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn inspects_and_signals_running_command() {
+    if procfs::stat(std::process::id()).is_none() {
+        eprintln!("/proc not available, skipping");
+        return;
+    }
+    let terminal_id = "tree-running";
+    assert_eq!(process_tree::process_tree(terminal_id).unwrap(), None);
+
+    let sink = Arc::new(MemorySink::new());
+    let runner = {
+        let sink = sink.clone();
+        thread::spawn(move || engine::execute(sink, terminal_id, "sleep 30 | cat"))
+    };
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let sleep = loop {
+        let tree = process_tree::process_tree(terminal_id).unwrap();
+        if let Some(sleep) = tree.as_ref().and_then(|root| find(root, "sleep")) {
+            break sleep.clone();
+        }
+        assert!(Instant::now() < deadline, "sleep never showed up: {:?}", tree);
+        thread::sleep(Duration::from_millis(50));
+    };
+    assert_eq!(sleep.argv, vec!["sleep", "30"]);
+    assert!(sleep.cwd.is_some());
+    assert!(sleep.start_time.is_some());
+
+    // 不属于这个命令的进程不能发信号
+    let err = process_tree::signal_process(terminal_id, std::process::id(), "TERM").unwrap_err();
+    assert!(matches!(err, TerminalError::PermissionDenied(_)), "{:?}", err);
+    assert!(process_tree::signal_process(terminal_id, sleep.pid, "NOPE").is_err());
+
+    let started = Instant::now();
+    process_tree::signal_process(terminal_id, sleep.pid, "TERM").unwrap();
+    runner.join().unwrap().unwrap();
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert_eq!(sink.completions().len(), 1);
+}
@@ -0,0 +1,135 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use app::terminal::engine;
+use app::terminal::sandbox::{self, sandbox_args, SandboxBackend, SandboxConfig};
+use app::terminal::session;
+use app::terminal::sink::MemorySink;
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("landingterm-sandbox-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir.canonicalize().unwrap()
+}
+
+fn strings(args: &[OsString]) -> Vec<String> {
+    args.iter().map(|a| a.to_string_lossy().into_owned()).collect()
+}
+
+fn run(terminal_id: &str, command: &str) -> (Option<i32>, String) {
+    let sink = Arc::new(MemorySink::new());
+    engine::execute(sink.clone(), terminal_id, command).unwrap();
+    let output = sink.outputs().into_iter().map(|o| o.content).collect::<Vec<_>>().join("\n");
+    (sink.completions()[0].code, output)
+}
+
+#[test]
+fn bubblewrap_arguments() {
+    let project = scratch("bwrap");
+    let config = SandboxConfig {
+        allow_network: false,
+        ..SandboxConfig::default()
+    };
+    let (program, args) = sandbox_args(
+        SandboxBackend::Bubblewrap,
+        &config,
+        &project,
+        "bash",
+        &[OsString::from("-c"), OsString::from("make")],
+    );
+    let args = strings(&args);
+    let project = project.to_string_lossy().into_owned();
+
+    assert_eq!(program, "bwrap");
+    assert!(args.contains(&"--unshare-net".to_string()));
+    let ro = args.iter().position(|a| a == "--ro-bind").unwrap();
+    assert_eq!(&args[ro + 1..ro + 3], &["/", "/"]);
+    let bind = args.iter().position(|a| a == "--bind").unwrap();
+    assert_eq!(&args[bind + 1..bind + 3], &[project.clone(), project.clone()]);
+    // 项目目录在 /tmp 下时不替换 /tmp
+    assert!(!args.contains(&"--tmpfs".to_string()));
+    assert_eq!(&args[args.len() - 4..], &["--", "bash", "-c", "make"]);
+
+    let (_, args) = sandbox_args(SandboxBackend::Bubblewrap, &SandboxConfig::default(), Path::new("/"), "sh", &[]);
+    let args = strings(&args);
+    assert!(!args.contains(&"--unshare-net".to_string()));
+    assert!(args.contains(&"--tmpfs".to_string()));
+}
+
+#[test]
+fn commands_run_with_read_only_root_and_no_network() {
+    if sandbox::backend().is_none() {
+        eprintln!("no sandbox backend available, skipping");
+        return;
+    }
+    let project = scratch("run");
+    let id = "sandbox-run";
+    session::with_session(id, |s| s.current_dir = project.clone()).unwrap();
+    sandbox::set_sandbox(
+        id,
+        Some(SandboxConfig {
+            allow_network: false,
+            ..SandboxConfig::default()
+        }),
+    )
+    .unwrap();
+
+    let (code, _) = run(id, "echo built > artifact.txt");
+    assert_eq!(code, Some(0));
+    assert_eq!(fs::read_to_string(project.join("artifact.txt")).unwrap().trim(), "built");
+
+    let outside = project.parent().unwrap().join(format!("landingterm-sandbox-escape-{}", std::process::id()));
+    let (code, _) = run(id, &format!("touch {}", outside.display()));
+    assert_ne!(code, Some(0));
+    assert!(!outside.exists());
+
+    // 新的网络命名空间里只有 lo
+    let (_, output) = run(id, "cat /proc/net/dev");
+    assert!(output.contains("lo:"), "{}", output);
+    assert!(output.lines().filter(|l| l.contains(':')).count() == 1, "{}", output);
+
+    sandbox::set_sandbox(id, None).unwrap();
+    let (code, _) = run(id, &format!("touch {} && rm {}", outside.display(), outside.display()));
+    assert_eq!(code, Some(0));
+    let _ = fs::remove_dir_all(&project);
+}
+
+#[test]
+fn writes_outside_writable_dirs_fail() {
+    if sandbox::backend().is_none() {
+        eprintln!("no sandbox backend available, skipping");
+        return;
+    }
+    let project = scratch("readonly");
+    // 不在 /tmp 下的目录，/tmp 可能被换成 tmpfs
+    let base = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("landingterm-sandbox-{}", std::process::id()));
+    let extra = base.join("extra");
+    let outside = base.join("outside");
+    fs::create_dir_all(&extra).unwrap();
+    fs::create_dir_all(&outside).unwrap();
+    let id = "sandbox-readonly";
+    session::with_session(id, |s| s.current_dir = project.clone()).unwrap();
+    sandbox::set_sandbox(
+        id,
+        Some(SandboxConfig {
+            writable_paths: vec![extra.clone()],
+            ..SandboxConfig::default()
+        }),
+    )
+    .unwrap();
+
+    let (code, output) = run(id, &format!("touch {}/file", outside.display()));
+    assert_ne!(code, Some(0), "{}", output);
+    assert!(!outside.join("file").exists());
+    assert!(!output.contains("could not make"), "{}", output);
+
+    let (code, output) = run(id, &format!("touch {}/file", extra.display()));
+    assert_eq!(code, Some(0), "{}", output);
+    assert!(extra.join("file").exists());
+
+    sandbox::set_sandbox(id, None).unwrap();
+    let _ = fs::remove_dir_all(&base);
+    let _ = fs::remove_dir_all(&project);
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use app::terminal::engine;
+use app::terminal::procfs::{self, ProcStat};
+use app::terminal::sink::MemorySink;
+
+const STAT: &str = "4242 (my (odd) prog) S 4200 4242 4200 34816 4242 4194304 120 0 0 0 \
+                    150 30 7 3 20 0 3 0 98765 12345678 512 18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 17 2 0 0 0 0 0";
+
+#[test]
+fn parses_proc_stat() {
+    let stat = procfs::parse_stat(STAT).expect("stat should parse");
+    assert_eq!(stat.pid, 4242);
+    assert_eq!(stat.comm, "my (odd) prog");
+    assert_eq!(stat.state, 'S');
+    assert_eq!(stat.ppid, 4200);
+    assert_eq!(stat.tpgid, 4242);
+    assert_eq!(stat.total_ticks(), 190);
+    assert_eq!(stat.num_threads, 3);
+    assert_eq!(stat.start_time, 98765);
+    assert_eq!(stat.rss_pages, 512);
+    assert_eq!(procfs::parse_stat("garbage"), None);
+}
+
+#[test]
+fn builds_process_tree() {
+    let process = |pid, ppid| ProcStat {
+        pid,
+        ppid,
+        ..procfs::parse_stat(STAT).unwrap()
+    };
+    let all = vec![process(1, 0), process(10, 1), process(12, 10), process(11, 10), process(20, 1)];
+    let pids: Vec<u32> = procfs::tree_from(10, all.clone()).iter().map(|p| p.pid).collect();
+    assert_eq!(pids, vec![10, 11, 12]);
+    assert!(procfs::tree_from(99, all).is_empty());
+}
+
+#[test]
+fn running_commands_report_resource_usage() {
+    if procfs::stat(std::process::id()).is_none() {
+        eprintln!("/proc not available, skipping");
+        return;
+    }
+    let sink = Arc::new(MemorySink::new());
+    // 一个子进程占满 CPU 大约 2.5 秒
+    engine::execute(sink.clone(), "stats-busy", "timeout 2.5 sh -c 'while :; do :; done'; true").unwrap();
+
+    let stats = sink.stats();
+    assert!(stats.len() >= 2, "{:?}", stats);
+    assert!(stats.iter().all(|s| s.terminal_id == "stats-busy" && s.rss_bytes > 0));
+    assert!(stats.iter().any(|s| s.processes >= 2));
+    assert!(stats.iter().any(|s| s.cpu_percent > 50.0), "{:?}", stats);
+
+    let summary = sink.completions().remove(0).stats.expect("summary should be attached");
+    assert!(summary.cpu_secs > 1.5, "{:?}", summary);
+    assert!(summary.peak_processes >= 2);
+    assert!(summary.samples as usize > stats.len());
+}
@@ -0,0 +1,112 @@
+use std::env;
+
+use app::terminal::syntax::{check, parse, parse_shell_errors, CommandNode, IssueSource};
+
+fn argv(node: &CommandNode) -> Vec<&str> {
+    match node {
+        CommandNode::Simple { argv, .. } => argv.iter().map(String::as_str).collect(),
+        CommandNode::Subshell { .. } => vec!["(subshell)"],
+    }
+}
+
+#[test]
+fn pipelines_lists_and_redirections_are_broken_down() {
+    let (statements, errors) = parse("FOO=1 cat <in.txt | grep -v x 2>/dev/null && (cd src; make) || echo fail &");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(statements.len(), 1);
+
+    let statement = &statements[0];
+    assert_eq!(statement.operators, vec!["&&", "||"]);
+    assert!(statement.background);
+
+    let first = &statement.pipelines[0];
+    assert_eq!(first.commands.len(), 2);
+    match &first.commands[0] {
+        CommandNode::Simple { assignments, argv, redirections, .. } => {
+            assert_eq!(assignments, &vec!["FOO=1".to_string()]);
+            assert_eq!(argv, &vec!["cat".to_string()]);
+            assert_eq!(redirections[0].operator, "<");
+            assert_eq!(redirections[0].target, "in.txt");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match &first.commands[1] {
+        CommandNode::Simple { redirections, .. } => assert_eq!(redirections[0].operator, "2>"),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    match &statement.pipelines[1].commands[0] {
+        CommandNode::Subshell { body, .. } => {
+            assert_eq!(body.len(), 2);
+            assert_eq!(argv(&body[0].pipelines[0].commands[0]), vec!["cd", "src"]);
+            assert_eq!(argv(&body[1].pipelines[0].commands[0]), vec!["make"]);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    assert_eq!(argv(&statement.pipelines[2].commands[0]), vec!["echo", "fail"]);
+}
+
+#[test]
+fn syntax_errors_have_positions() {
+    let (_, errors) = parse("ls | | wc");
+    assert_eq!(errors[0].message, "missing command after `|`");
+    assert_eq!((errors[0].start, errors[0].end), (3, 4));
+
+    let (_, errors) = parse("echo 'unterminated");
+    assert_eq!(errors[0].message, "unterminated single quote");
+    assert_eq!(errors[0].start, 5);
+
+    let (_, errors) = parse("(echo hi");
+    assert_eq!(errors[0].message, "unclosed `(`");
+
+    let (_, errors) = parse("echo hi >");
+    assert_eq!(errors[0].message, "missing target for redirection `>`");
+
+    let (_, errors) = parse("; ls");
+    assert_eq!(errors[0].message, "syntax error near unexpected token `;`");
+    assert_eq!(errors[0].source, IssueSource::Parser);
+}
+
+#[test]
+fn heredocs_and_case_are_not_errors() {
+    let (statements, errors) = parse("cat <<-EOF | wc -l\n\tone | two\n\tEOF\necho done");
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(statements.len(), 2);
+    assert_eq!(argv(&statements[1].pipelines[0].commands[0]), vec!["echo", "done"]);
+
+    let (_, errors) = parse("case $x in a) echo a;; *) echo b;; esac");
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn shell_errors_are_mapped_to_lines() {
+    let command = "echo ok\nls | | wc";
+    let errors = parse_shell_errors(
+        command,
+        "bash: -c: line 2: syntax error near unexpected token `|'\nbash: -c: line 2: `ls | | wc'\n",
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "syntax error near unexpected token `|'");
+    assert_eq!((errors[0].start, errors[0].end), (8, 17));
+
+    let errors = parse_shell_errors(command, "zsh:2: parse error near `|'\n");
+    assert_eq!(errors[0].message, "parse error near `|'");
+    assert_eq!(errors[0].start, 8);
+}
+
+#[test]
+fn shell_no_exec_check_decides_validity() {
+    let cwd = env::temp_dir();
+    let result = check("echo $((1 + 2)) | cat", Some("bash"), &cwd);
+    assert!(result.valid);
+    assert_eq!(result.shell.as_deref(), Some("bash"));
+
+    let result = check("if true; then echo x", Some("bash"), &cwd);
+    assert!(!result.valid);
+    assert!(result.errors.iter().any(|e| e.source == IssueSource::Shell));
+
+    // 没有 shell 时只用内置分析
+    let result = check("ls &&", None, &cwd);
+    assert!(!result.valid);
+    assert_eq!(result.errors[0].message, "missing command after `&&`");
+}
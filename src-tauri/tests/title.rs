@@ -0,0 +1,84 @@
+use std::fs;
+use std::sync::Arc;
+
+use app::terminal::engine;
+use app::terminal::procfs::{self, ProcStat};
+use app::terminal::sink::MemorySink;
+use app::terminal::title::{self, OscParser};
+
+fn stat(pid: u32, ppid: u32, comm: &str, pgrp: u32, tpgid: i32) -> ProcStat {
+    procfs::parse_stat(&format!(
+        "{} ({}) S {} {} 0 0 {} 0 0 0 0 0 1 1 0 0 20 0 1 0 100 1000 10",
+        pid, comm, ppid, pgrp, tpgid
+    ))
+    .unwrap()
+}
+
+#[test]
+fn strips_osc_sequences_and_keeps_titles() {
+    let mut osc = OscParser::new();
+    assert_eq!(osc.strip("a\x1b]0;first\x07b"), ("ab".to_string(), Some("first".to_string())));
+    assert_eq!(osc.strip("\x1b]2;second\x1b\\c"), ("c".to_string(), Some("second".to_string())));
+    // 超链接等其他 OSC 序列只去掉，不改变标题
+    assert_eq!(osc.strip("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\"), ("link".to_string(), None));
+
+    // 跨两次读取的序列
+    assert_eq!(osc.strip("x\x1b]1;spl"), ("x".to_string(), None));
+    assert_eq!(osc.strip("it\x07y"), ("y".to_string(), Some("split".to_string())));
+}
+
+#[test]
+fn picks_foreground_process_and_name() {
+    let tree = vec![
+        stat(9, 1, "script", 9, -1),
+        stat(10, 9, "zsh", 10, 10),
+        stat(11, 10, "cargo", 10, 10),
+        stat(12, 11, "rustc", 10, 10),
+    ];
+    assert_eq!(title::foreground(&tree).unwrap().pid, 11);
+    assert_eq!(title::foreground(&tree[..2]).unwrap().pid, 9);
+
+    // 交互式程序把自己放到单独的进程组并设为前台
+    let mut tree = tree;
+    tree.push(stat(13, 10, "less", 13, 13));
+    for p in tree.iter_mut().skip(1) {
+        p.tpgid = 13;
+    }
+    assert_eq!(title::foreground(&tree).unwrap().pid, 13);
+
+    let argv = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+    assert_eq!(title::display_name(&tree[2], &argv(&["/usr/bin/cargo", "test", "--release"])), "cargo test");
+    assert_eq!(title::display_name(&tree[2], &argv(&["vim", "/etc/hosts"])), "vim");
+    assert_eq!(title::display_name(&tree[2], &argv(&["ls", "-la"])), "ls");
+    assert_eq!(title::display_name(&tree[2], &[]), "cargo");
+    assert_eq!(title::format_title(Some("cargo test"), "~/proj"), "cargo test — ~/proj");
+}
+
+#[test]
+fn titles_follow_programs_and_foreground_process() {
+    if procfs::stat(std::process::id()).is_none() {
+        eprintln!("/proc not available, skipping");
+        return;
+    }
+    let dir = std::env::temp_dir().join("landingterm-title-test");
+    fs::create_dir_all(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    let cwd = app::terminal::process::format_current_dir(&dir);
+
+    let sink = Arc::new(MemorySink::new());
+    let terminal_id = "title-foreground";
+    engine::execute(sink.clone(), terminal_id, &format!("cd {}", dir.display())).unwrap();
+    engine::execute(sink.clone(), terminal_id, "sleep 2.5").unwrap();
+    let titles: Vec<String> = sink.titles().into_iter().map(|t| t.title).collect();
+    assert_eq!(titles.first(), Some(&cwd));
+    assert!(titles.contains(&format!("sleep 2.5 — {}", cwd)), "{:?}", titles);
+    assert_eq!(titles.last(), Some(&cwd));
+
+    let sink = Arc::new(MemorySink::new());
+    let terminal_id = "title-program";
+    engine::execute(sink.clone(), terminal_id, r"printf '\033]0;my title\007hello\n'").unwrap();
+    let titles = sink.titles();
+    assert!(titles.iter().any(|t| t.title == "my title" && t.from_program), "{:?}", titles);
+    assert!(!titles.last().unwrap().from_program, "{:?}", titles);
+    assert_eq!(sink.outputs()[0].content, "hello");
+}
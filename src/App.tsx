@@ -1,7 +1,10 @@
 import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/tauri';
 import Tabs from './components/Tabs';
+import OrphanPrompt from './components/OrphanPrompt';
 import { Terminal } from "./components/Terminal/Terminal";
 import { setupShortcuts } from './utils/shortcuts'
+import { SessionInfo } from './components/Terminal/types';
 
 interface TerminalInstance {
   id: string;
@@ -12,6 +15,21 @@ function App() {
   const [terminals, setTerminals] = useState<TerminalInstance[]>([
     { id: '1', active: true }
   ]);
+  const [restoredSessions, setRestoredSessions] = useState<SessionInfo[]>([]);
+  const [restored, setRestored] = useState(false);
+
+  // 恢复上次的会话后再创建标签页和终端
+  useEffect(() => {
+    invoke<SessionInfo[]>('plugin:terminal|restore_sessions', { runStartup: true })
+      .then(sessions => {
+        if (sessions.length > 0) {
+          setTerminals(sessions.map((s, i) => ({ id: s.terminalId, active: i === 0 })));
+          setRestoredSessions(sessions);
+        }
+      })
+      .catch(console.error)
+      .finally(() => setRestored(true));
+  }, []);
 
   const handleTabChange = (tabId: string) => {
     setTerminals(prev => prev.map(term => ({
@@ -42,14 +60,18 @@ function App() {
   return (
     <div className="app">
       <div className="titlebar" data-tauri-drag-region>
-        <Tabs 
-          onTabChange={handleTabChange} 
-          onAddTab={handleAddTab}
-          onCloseTab={handleCloseTab}
-        />
+        {restored && (
+          <Tabs 
+            initialSessions={restoredSessions}
+            onTabChange={handleTabChange} 
+            onAddTab={handleAddTab}
+            onCloseTab={handleCloseTab}
+          />
+        )}
       </div>
+      {restored && <OrphanPrompt />}
       <div className="terminals-container">
-        {terminals.map(term => (
+        {restored && terminals.map(term => (
           <div 
             key={term.id}
             className={`flex-1 ${term.active ? 'flex' : 'hidden'}`}
@@ -0,0 +1,57 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/tauri';
+
+interface ProcessRecord {
+  pid: number;
+  pgid: number;
+  command: string;
+  terminalId: string;
+  started_at: number;
+}
+
+// 启动时列出上次运行遗留下来的进程，让用户终止或忽略
+const OrphanPrompt: React.FC = () => {
+  const [orphans, setOrphans] = useState<ProcessRecord[]>([]);
+  const [error, setError] = useState('');
+
+  useEffect(() => {
+    invoke<ProcessRecord[]>('plugin:terminal|list_orphans')
+      .then(setOrphans)
+      .catch(console.error);
+  }, []);
+
+  const resolve = async (pid: number, action: 'kill' | 'ignore') => {
+    try {
+      await invoke('plugin:terminal|resolve_orphan', { pid, action });
+      setError('');
+    } catch (e) {
+      setError(`Error: ${(e as { message?: string })?.message ?? e}`);
+      // 进程已经退出时也从列表里去掉
+      if ((e as { code?: string })?.code !== 'process_exited') return;
+    }
+    setOrphans(prev => prev.filter(o => o.pid !== pid));
+  };
+
+  if (orphans.length === 0) return null;
+
+  return (
+    <div className="orphan-prompt">
+      <div className="orphan-title">
+        {orphans.length === 1 ? 'A command' : `${orphans.length} commands`} from the last session are still running:
+      </div>
+      {orphans.map(o => (
+        <div key={o.pid} className="orphan-item">
+          <span className="orphan-command" title={new Date(o.started_at * 1000).toLocaleString()}>
+            {o.command}
+          </span>
+          <span className="orphan-pid">pid {o.pid}</span>
+          <button onClick={() => resolve(o.pid, 'kill')}>Kill</button>
+          <button onClick={() => resolve(o.pid, 'ignore')}>Ignore</button>
+        </div>
+      ))}
+      {error && <div className="orphan-error">{error}</div>}
+    </div>
+  );
+};
+
+export default OrphanPrompt;
@@ -1,26 +1,50 @@
 import React, { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
 import { appWindow } from '@tauri-apps/api/window';
+import { listen } from '@tauri-apps/api/event';
+import { SessionInfo, TitleChanged } from './Terminal/types';
 
 interface Tab {
   id: string;
   name: string;
   active: boolean;
+  sandboxed?: boolean;
+  // 手动命名过的标签页不跟随自动标题
+  renamed?: boolean;
+  // 后端根据前台进程、工作目录或 OSC 序列生成的标题
+  autoTitle?: string;
 }
 
 interface TabsProps {
+  initialSessions: SessionInfo[];
   onTabChange: (tabId: string) => void;
   onAddTab: (tabId: string) => void;
   onCloseTab: (tabId: string) => void;
 }
 
-const Tabs: React.FC<TabsProps> = ({ onTabChange, onAddTab, onCloseTab }) => {
-  const [tabs, setTabs] = useState<Tab[]>([
-    { id: '1', name: 'Term1', active: true }
-  ]);
-  const [tabCounter, setTabCounter] = useState(2);
+// 从恢复的会话创建初始标签页
+const initialTabs = (sessions: SessionInfo[]): Tab[] => {
+  if (sessions.length === 0) {
+    return [{ id: '1', name: 'Term1', active: true }];
+  }
+  return sessions.map((s, i) => ({
+    id: s.terminalId,
+    name: s.title ?? `Term${i + 1}`,
+    active: i === 0,
+    sandboxed: s.sandboxed,
+    renamed: s.title != null
+  }));
+};
+
+const displayName = (tab: Tab) => (tab.renamed ? tab.name : tab.autoTitle ?? tab.name);
+
+const Tabs: React.FC<TabsProps> = ({ initialSessions, onTabChange, onAddTab, onCloseTab }) => {
+  const [tabs, setTabs] = useState<Tab[]>(() => initialTabs(initialSessions));
+  const [tabCounter, setTabCounter] = useState(() =>
+    Math.max(1, ...tabs.map(t => Number(t.id)).filter(n => !isNaN(n))) + 1
+  );
   const [editingTab, setEditingTab] = useState<string | null>(null);
-  const [usedNames, setUsedNames] = useState<Set<string>>(new Set(['Term1']));
+  const [usedNames, setUsedNames] = useState<Set<string>>(() => new Set(tabs.map(t => t.name)));
   const [contextMenu, setContextMenu] = useState<{
     visible: boolean;
     x: number;
@@ -43,16 +67,19 @@ const Tabs: React.FC<TabsProps> = ({ onTabChange, onAddTab, onCloseTab }) => {
     return name;
   };
 
-  const addTab = () => {
+  // id 由后端指定时（例如控制接口打开的标签页）不占用本地计数
+  const addTab = (terminalId?: string) => {
     const newName = getNextAvailableName();
     const newTab = {
-      id: tabCounter.toString(),
+      id: terminalId ?? tabCounter.toString(),
       name: newName,
       active: true
     };
     
     setTabs(prev => prev.map(t => ({ ...t, active: false })).concat(newTab));
-    setTabCounter(prev => prev + 1);
+    if (!terminalId) {
+      setTabCounter(prev => prev + 1);
+    }
     setUsedNames(prev => new Set(prev).add(newName));
     onAddTab(newTab.id);
     onTabChange(newTab.id);
@@ -119,6 +146,30 @@ const Tabs: React.FC<TabsProps> = ({ onTabChange, onAddTab, onCloseTab }) => {
     onTabChange(currentTabId);
   };
 
+  // 控制接口请求打开新的标签页
+  // 监听只注册一次，通过 ref 调用最新的 addTab，避免重新注册期间漏掉或重复处理事件
+  const addTabRef = useRef(addTab);
+  addTabRef.current = addTab;
+  useEffect(() => {
+    const unlistenPromise = listen<{ terminalId: string }>('terminal-open-requested', (event) => {
+      addTabRef.current(event.payload.terminalId);
+    });
+    return () => {
+      unlistenPromise.then(unlisten => unlisten());
+    };
+  }, []);
+
+  // 标签页标题跟随正在运行的程序，例如 "cargo test — ~/proj"
+  useEffect(() => {
+    const unlistenPromise = listen<TitleChanged>('terminal-title-changed', (event) => {
+      const { terminalId, title } = event.payload;
+      setTabs(prev => prev.map(t => (t.id === terminalId ? { ...t, autoTitle: title } : t)));
+    });
+    return () => {
+      unlistenPromise.then(unlisten => unlisten());
+    };
+  }, []);
+
   // 添加点击外部关闭菜单的处理
   useEffect(() => {
     const handleClickOutside = () => {
@@ -139,12 +190,31 @@ const Tabs: React.FC<TabsProps> = ({ onTabChange, onAddTab, onCloseTab }) => {
     });
   };
 
+  // 开启或关闭标签页的沙箱模式；开启时可以选择断开网络
+  const toggleSandbox = (tabId: string) => {
+    const tab = tabs.find(t => t.id === tabId);
+    if (!tab) return;
+    const sandbox = tab.sandboxed
+      ? null
+      : { allow_network: !window.confirm('Cut off network access inside the sandbox?') };
+    invoke('plugin:terminal|set_terminal_sandbox', { terminalId: tabId, sandbox })
+      .then(() => {
+        setTabs(prev => prev.map(t => (t.id === tabId ? { ...t, sandboxed: !tab.sandboxed } : t)));
+      })
+      .catch(error => window.alert(`Sandbox: ${(error as { message?: string })?.message ?? error}`));
+  };
+
   const handleDoubleClick = (tabId: string) => {
     setEditingTab(tabId);
   };
 
   const handleNameChange = (tabId: string, newName: string) => {
     const oldTab = tabs.find(t => t.id === tabId);
+    // 没有修改时保持跟随自动标题
+    if (oldTab && newName === displayName(oldTab)) {
+      setEditingTab(null);
+      return;
+    }
     if (oldTab) {
       setUsedNames(prev => {
         const newSet = new Set(prev);
@@ -156,9 +226,13 @@ const Tabs: React.FC<TabsProps> = ({ onTabChange, onAddTab, onCloseTab }) => {
 
     setTabs(prev =>
       prev.map(tab =>
-        tab.id === tabId ? { ...tab, name: newName || tab.name } : tab
+        tab.id === tabId ? { ...tab, name: newName || tab.name, renamed: tab.renamed || !!newName } : tab
       )
     );
+    if (newName) {
+      invoke('plugin:terminal|set_terminal_title', { terminalId: tabId, title: newName })
+        .catch(console.error);
+    }
     setEditingTab(null);
   };
 
@@ -233,13 +307,13 @@ const Tabs: React.FC<TabsProps> = ({ onTabChange, onAddTab, onCloseTab }) => {
             onClick={() => switchTab(tab.id)}
             onDoubleClick={() => handleDoubleClick(tab.id)}
             onContextMenu={(e) => handleContextMenu(e, tab)}
-            title={`${tab.name} (⌘T to create, ⌘W to close, Ctrl+Tab to switch)`}
+            title={`${displayName(tab)} (⌘T to create, ⌘W to close, Ctrl+Tab to switch)`}
           >
             <div className="tab-content">
               {editingTab === tab.id ? (
                 <input
                   type="text"
-                  defaultValue={tab.name}
+                  defaultValue={displayName(tab)}
                   autoFocus
                   onBlur={(e) => handleNameChange(tab.id, e.target.value)}
                   onKeyDown={(e) => handleKeyDown(e, tab.id)}
@@ -247,7 +321,10 @@ const Tabs: React.FC<TabsProps> = ({ onTabChange, onAddTab, onCloseTab }) => {
                   className="tab-name-input"
                 />
               ) : (
-                <span className="tab-name">{tab.name}</span>
+                <span className="tab-name">
+                  {tab.sandboxed && <span className="tab-sandbox" title="Sandboxed">🔒</span>}
+                  {displayName(tab)}
+                </span>
               )}
             </div>
             <button
@@ -261,7 +338,7 @@ const Tabs: React.FC<TabsProps> = ({ onTabChange, onAddTab, onCloseTab }) => {
         ))}
         <button
           className="new-tab"
-          onClick={addTab}
+          onClick={() => addTab()}
           title="New tab (⌘T)"
         >
           +
@@ -285,6 +362,30 @@ const Tabs: React.FC<TabsProps> = ({ onTabChange, onAddTab, onCloseTab }) => {
           >
             Rename
           </div>
+          <div
+            className="context-menu-item"
+            onClick={() => {
+              const command = window.prompt('Startup command (leave empty to clear)');
+              if (command !== null) {
+                invoke('plugin:terminal|set_startup_command', {
+                  terminalId: contextMenu.tabId,
+                  command: command || null
+                }).catch(console.error);
+              }
+              setContextMenu(prev => ({ ...prev, visible: false }));
+            }}
+          >
+            Set Startup Command
+          </div>
+          <div
+            className="context-menu-item"
+            onClick={() => {
+              toggleSandbox(contextMenu.tabId);
+              setContextMenu(prev => ({ ...prev, visible: false }));
+            }}
+          >
+            {tabs.find(t => t.id === contextMenu.tabId)?.sandboxed ? 'Leave Sandbox' : 'Run in Sandbox'}
+          </div>
           <div
             className="context-menu-item"
             onClick={() => {
@@ -1,14 +1,33 @@
 import React, { useEffect, useRef, useState } from 'react';
 import { writeText } from '@tauri-apps/api/clipboard';
-import { CommandBlockProps, ContextMenuPosition } from './types';
+import { open } from '@tauri-apps/api/shell';
+import { CommandBlockProps, ContextMenuPosition, ProcessStats, StatsSummary } from './types';
 import { ContextMenu } from './ContextMenu';
 import './styles.css';
 
-export const CommandBlock: React.FC<CommandBlockProps> = ({ command, output, directory }) => {
+const formatBytes = (bytes: number) => {
+  if (bytes >= 1024 * 1024 * 1024) return `${(bytes / 1024 / 1024 / 1024).toFixed(1)} GB`;
+  if (bytes >= 1024 * 1024) return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+  return `${Math.round(bytes / 1024)} KB`;
+};
+
+// 运行中显示当前占用，结束后显示汇总
+const formatUsage = (stats?: ProcessStats, summary?: StatsSummary) => {
+  if (stats) {
+    return `CPU ${Math.round(stats.cpu_percent)}% · ${formatBytes(stats.rss_bytes)} · ${stats.processes} proc`;
+  }
+  if (summary) {
+    return `CPU ${summary.cpu_secs.toFixed(1)}s · peak ${formatBytes(summary.peak_rss_bytes)}`;
+  }
+  return null;
+};
+
+export const CommandBlock: React.FC<CommandBlockProps> = ({ command, output, directory, suggestions, stats, summary, ports, onRunSuggestion }) => {
   const [contextMenu, setContextMenu] = useState<ContextMenuPosition | null>(null);
   const [copyStatus, setCopyStatus] = useState<string>('');
   const [selectedText, setSelectedText] = useState<string>('');
   const menuRef = useRef<HTMLDivElement>(null);
+  const usage = formatUsage(stats, summary);
 
   const handleContextMenu = (e: React.MouseEvent) => {
     e.preventDefault();
@@ -80,6 +99,7 @@ export const CommandBlock: React.FC<CommandBlockProps> = ({ command, output, dir
       <div className="command-input">
         <span className="prompt">{directory} $ </span>
         <span className="command-text">{command}</span>
+        {usage && <span className="command-usage">{usage}</span>}
       </div>
 
       {output.length > 0 && (
@@ -96,6 +116,41 @@ export const CommandBlock: React.FC<CommandBlockProps> = ({ command, output, dir
         </div>
       )}
 
+      {ports && ports.length > 0 && (
+        <div className="command-ports">
+          {ports.map(p => p.protocol === 'tcp' ? (
+            <button
+              key={`${p.protocol}-${p.port}-${p.pid}`}
+              className="command-port"
+              onClick={() => open(`http://localhost:${p.port}`).catch(console.error)}
+              title={`Listening on ${p.address}:${p.port} (pid ${p.pid})`}
+            >
+              open http://localhost:{p.port}
+            </button>
+          ) : (
+            <span key={`${p.protocol}-${p.port}-${p.pid}`} title={`pid ${p.pid}`}>
+              udp {p.address}:{p.port}
+            </span>
+          ))}
+        </div>
+      )}
+
+      {suggestions && suggestions.length > 0 && (
+        <div className="command-suggestions">
+          <span>Did you mean:</span>
+          {suggestions.map(s => (
+            <button
+              key={s.command}
+              className="command-suggestion"
+              onClick={() => onRunSuggestion?.(s.command)}
+              title={`Run "${s.command}" (${s.source})`}
+            >
+              {s.command}
+            </button>
+          ))}
+        </div>
+      )}
+
       {contextMenu && (
         <ContextMenu
           ref={menuRef}
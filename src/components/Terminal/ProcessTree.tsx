@@ -0,0 +1,70 @@
+import React, { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/tauri';
+import { ProcessNode } from './types';
+import './styles.css';
+
+interface ProcessTreeProps {
+  terminalId: string;
+}
+
+const SIGNALS = ['INT', 'TERM', 'KILL', 'STOP', 'CONT'];
+
+// 正在运行的命令的进程树，每个进程都可以单独发送信号
+export const ProcessTree: React.FC<ProcessTreeProps> = ({ terminalId }) => {
+  const [root, setRoot] = useState<ProcessNode | null>(null);
+  const [error, setError] = useState<string>('');
+
+  const refresh = async () => {
+    try {
+      setRoot(await invoke<ProcessNode | null>('plugin:terminal|get_process_tree', { terminalId }));
+    } catch (e) {
+      setError(`Error: ${(e as { message?: string })?.message ?? e}`);
+    }
+  };
+
+  useEffect(() => {
+    refresh();
+    const timer = setInterval(refresh, 2000);
+    return () => clearInterval(timer);
+  }, [terminalId]);
+
+  const sendSignal = async (pid: number, signal: string) => {
+    try {
+      await invoke('plugin:terminal|send_process_signal', { terminalId, pid, signal });
+      setError('');
+      refresh();
+    } catch (e) {
+      setError(`Error: ${(e as { message?: string })?.message ?? e}`);
+    }
+  };
+
+  const renderNode = (node: ProcessNode, depth: number): React.ReactNode => (
+    <React.Fragment key={node.pid}>
+      <div className="process-node" style={{ paddingLeft: depth * 16 }}>
+        <span className="process-pid">{node.pid}</span>
+        <span className={`process-state state-${node.state}`}>{node.state}</span>
+        <span
+          className="process-command"
+          title={[node.cwd, node.start_time && new Date(node.start_time * 1000).toLocaleString()].filter(Boolean).join('\n')}
+        >
+          {node.argv.length > 0 ? node.argv.join(' ') : `[${node.name}]`}
+        </span>
+        <span className="process-signals">
+          {SIGNALS.map(signal => (
+            <button key={signal} onClick={() => sendSignal(node.pid, signal)} title={`Send SIG${signal}`}>
+              {signal}
+            </button>
+          ))}
+        </span>
+      </div>
+      {node.children.map(child => renderNode(child, depth + 1))}
+    </React.Fragment>
+  );
+
+  return (
+    <div className="process-tree">
+      {root ? renderNode(root, 0) : <div className="process-empty">No running processes</div>}
+      {error && <div className="process-error">{error}</div>}
+    </div>
+  );
+};
@@ -3,10 +3,11 @@ import { invoke } from '@tauri-apps/api/tauri';
 import { listen } from '@tauri-apps/api/event';
 import { writeText } from '@tauri-apps/api/clipboard';
 import './styles.css';
-import { IconChevronDown, IconStar, IconPlayerStop } from '@tabler/icons-react';
+import { IconChevronDown, IconStar, IconPlayerStop, IconHierarchy } from '@tabler/icons-react';
 import { CommandBlock } from './CommandBlock';
 import { FavoriteCommands } from './FavoriteCommands';
-import { TerminalProps, CommandBlock as CommandBlockType, StreamOutput, ContextMenuPosition } from './types';
+import { ProcessTree } from './ProcessTree';
+import { TerminalProps, CommandBlock as CommandBlockType, StreamOutput, ContextMenuPosition, SessionInfo, Completions, Correction, ExecuteOutcome, ProcessStats, StatsSummary, PortOpened } from './types';
 import useFavoriteStore from '../../store/favoriteStore';
 
 interface CommandResult {
@@ -181,6 +182,9 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
   const [isSelecting, setIsSelecting] = useState(false);
   const { isOpen, setIsOpen } = useFavoriteStore();
   const [inputHeight, setInputHeight] = useState(0);
+  const [suggestion, setSuggestion] = useState<string>('');
+  const [completions, setCompletions] = useState<Completions | null>(null);
+  const [showProcesses, setShowProcesses] = useState(false);
 
   const adjustTextareaHeight = () => {
     const textarea = inputRef.current;
@@ -251,21 +255,110 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
   // 监听输入变化
   const handleInputChange = (e: React.ChangeEvent<HTMLTextAreaElement>) => {
     setInput(e.target.value);
+    setCompletions(null);
   };
 
+  // 用补全替换输入中 [start, end) 的字符，并把光标放到替换内容之后
+  const applyCompletion = (start: number, end: number, value: string) => {
+    const chars = Array.from(input);
+    const head = chars.slice(0, start).join('') + value;
+    setInput(head + chars.slice(end).join(''));
+    requestAnimationFrame(() => {
+      inputRef.current?.setSelectionRange(head.length, head.length);
+    });
+  };
+
+  const handleComplete = async () => {
+    const textarea = inputRef.current;
+    const cursor = Array.from(input.slice(0, textarea?.selectionStart ?? input.length)).length;
+    try {
+      const result = await invoke<Completions>('plugin:terminal|complete', {
+        terminalId: id,
+        line: input,
+        cursor
+      });
+      const { start, end, candidates } = result;
+      if (candidates.length === 0) {
+        setCompletions(null);
+        return;
+      }
+      if (candidates.length === 1) {
+        // 目录之后继续补全，其他情况补上空格
+        const only = candidates[0];
+        applyCompletion(start, end, only.kind === 'directory' ? only.value : `${only.value} `);
+        setCompletions(null);
+        return;
+      }
+      // 多个候选时先补全公共前缀，再列出候选
+      let prefix = candidates[0].value;
+      for (const c of candidates) {
+        while (!c.value.startsWith(prefix)) prefix = prefix.slice(0, -1);
+      }
+      const typed = Array.from(input).slice(start, end).join('');
+      if (prefix.length > typed.length) {
+        applyCompletion(start, end, prefix);
+        setCompletions({ ...result, end: start + Array.from(prefix).length });
+      } else {
+        setCompletions(result);
+      }
+    } catch (error) {
+      console.error('Failed to complete:', error);
+    }
+  };
+
+  // 根据历史记录给出行内建议（只对单行输入）
+  useEffect(() => {
+    if (!input.trim() || input.includes('\n')) {
+      setSuggestion('');
+      return;
+    }
+    let cancelled = false;
+    invoke<{ completion: string } | null>('plugin:terminal|suggest', { terminalId: id, prefix: input })
+      .then(result => {
+        if (!cancelled) setSuggestion(result?.completion ?? '');
+      })
+      .catch(() => {
+        if (!cancelled) setSuggestion('');
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [input, id]);
+
   useEffect(() => {
     adjustTextareaHeight();
   }, [input]);
 
   useEffect(() => {
-    invoke('execute_command_stream', { 
-      command: 'pwd',
-      terminal_id: id
-    }).catch(console.error);
+    // 恢复该终端的工作目录、命令块以及仍在运行的命令
+    invoke<SessionInfo[]>('plugin:terminal|list_sessions', { lines: 500 })
+      .then(sessions => {
+        const session = sessions.find(s => s.terminalId === id);
+        if (!session) return;
+        setCurrentDir(session.current_dir);
+
+        const restoredBlocks: CommandBlockType[] = session.blocks.length > 0
+          ? session.blocks.map((block, i) => ({
+              id: i - session.blocks.length,
+              command: block.command,
+              output: block.output,
+              directory: block.directory
+            }))
+          : session.scrollback.length > 0
+            ? [{ id: -1, command: session.command ?? '', output: session.scrollback, directory: session.current_dir }]
+            : [];
+        if (restoredBlocks.length === 0) return;
+        setCommandBlocks(prev => [...restoredBlocks, ...prev]);
+        if (session.running) {
+          setCurrentCommandBlock(restoredBlocks[restoredBlocks.length - 1]);
+          setIsExecuting(true);
+        }
+      })
+      .catch(console.error);
 
-    invoke('create_terminal', { id });
+    invoke('plugin:terminal|create_terminal', { id });
     return () => {
-      invoke('close_terminal', { id });
+      invoke('plugin:terminal|close_terminal', { id });
     };
   }, [id]);
 
@@ -364,8 +457,39 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
         }
       });
 
-      const unlistenComplete = await listen<{ terminalId: string }>('terminal-command-complete', (event) => {
+      const unlistenStats = await listen<ProcessStats>('terminal-process-stats', (event) => {
+        if (event.payload.terminalId !== id) return;
+        setCommandBlocks(prev => {
+          if (prev.length === 0) return prev;
+          const last = prev[prev.length - 1];
+          return [...prev.slice(0, -1), { ...last, stats: event.payload }];
+        });
+      });
+
+      const unlistenPorts = await listen<PortOpened>('terminal-port-opened', (event) => {
+        if (event.payload.terminalId !== id) return;
+        setCommandBlocks(prev => {
+          if (prev.length === 0) return prev;
+          const last = prev[prev.length - 1];
+          return [...prev.slice(0, -1), { ...last, ports: [...(last.ports ?? []), event.payload] }];
+        });
+      });
+
+      const unlistenComplete = await listen<{ terminalId: string; suggestions?: Correction[]; stats?: StatsSummary | null }>('terminal-command-complete', (event) => {
         if (event.payload.terminalId !== id) return;
+        // 命令不存在时在命令块下方显示改正建议
+        const suggestions = event.payload.suggestions ?? [];
+        const summary = event.payload.stats ?? undefined;
+        setCommandBlocks(prev => {
+          if (prev.length === 0) return prev;
+          const last = prev[prev.length - 1];
+          return [...prev.slice(0, -1), {
+            ...last,
+            suggestions: suggestions.length > 0 ? suggestions : last.suggestions,
+            stats: undefined,
+            summary,
+          }];
+        });
         setCurrentCommandBlock(null);
         setIsExecuting(false);
         scrollToBottom(50);
@@ -373,6 +497,8 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
 
       return () => {
         unlisten();
+        unlistenStats();
+        unlistenPorts();
         unlistenComplete();
       };
     };
@@ -380,6 +506,28 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
     setupListeners();
   }, [currentCommandBlock, id]);
 
+  // 通过控制接口发起的命令：创建对应的命令块
+  useEffect(() => {
+    const unlistenPromise = listen<{ terminalId: string; command: string }>('terminal-command-started', (event) => {
+      if (event.payload.terminalId !== id) return;
+      const newBlock: CommandBlockType = {
+        id: blockId,
+        command: event.payload.command,
+        output: [],
+        directory: currentDir
+      };
+      setCommandBlocks(prev => [...prev, newBlock]);
+      setCurrentCommandBlock(newBlock);
+      setBlockId(prev => prev + 1);
+      setIsExecuting(true);
+      setAutoScroll(true);
+    });
+
+    return () => {
+      unlistenPromise.then(unlisten => unlisten());
+    };
+  }, [id, blockId, currentDir]);
+
   useEffect(() => {
     const terminal = terminalRef.current;
     if (!terminal) return;
@@ -407,7 +555,51 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
     adjustTextareaHeight();
   }, [input]);
 
+  // 执行命令；危险命令先询问用户，取消时返回 false
+  const runCommand = async (command: string): Promise<boolean> => {
+    const outcome = await invoke<ExecuteOutcome>('plugin:terminal|execute_command_stream', {
+      command,
+      terminalId: id
+    });
+    if (outcome.status !== 'needs_confirmation') return true;
+    const reasons = outcome.matches.map(m => `- ${m.reason}`).join('\n');
+    if (!window.confirm(`This command looks dangerous (${outcome.risk} risk):\n${reasons}\n\nRun it anyway?`)) {
+      return false;
+    }
+    await invoke('plugin:terminal|execute_command_stream', { command, terminalId: id, confirmed: true });
+    return true;
+  };
+
+  // 用户取消了危险命令：结束当前命令块
+  const cancelCommand = () => {
+    setCommandBlocks(prev => {
+      if (prev.length === 0) return prev;
+      const last = prev[prev.length - 1];
+      return [...prev.slice(0, -1), { ...last, output: [...last.output, 'Cancelled'] }];
+    });
+    setIsExecuting(false);
+    setCurrentCommandBlock(null);
+  };
+
   const handleKeyPress = async (e: React.KeyboardEvent) => {
+    // 光标在末尾时按 → 接受建议
+    const textarea = inputRef.current;
+    if (e.key === 'ArrowRight' && suggestion && textarea?.selectionStart === input.length) {
+      e.preventDefault();
+      setInput(input + suggestion);
+      setSuggestion('');
+      return;
+    }
+    if (e.key === 'Tab' && !e.shiftKey) {
+      e.preventDefault();
+      await handleComplete();
+      return;
+    }
+    if (e.key === 'Escape' && completions) {
+      e.preventDefault();
+      setCompletions(null);
+      return;
+    }
     if (e.key === 'Enter') {
       if (e.shiftKey) {
         // 换行时立即调整高度并滚动
@@ -435,15 +627,14 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
           const cmdToRun = input; 
           setInput('');
 
-          await invoke('execute_command_stream', { 
-            command: cmdToRun,
-            terminalId: id
-          });
+          if (!(await runCommand(cmdToRun))) {
+            cancelCommand();
+          }
         } catch (error) {
           const errorBlock: CommandBlockType = {
             id: blockId,
             command: input,
-            output: [`Error: ${error}`],
+            output: [`Error: ${(error as { message?: string })?.message ?? error}`],
             directory: currentDir
           };
           setCommandBlocks(prev => [...prev, errorBlock]);
@@ -481,15 +672,14 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
       setCurrentCommandBlock(newBlock);
       setBlockId(prev => prev + 1);
 
-      await invoke('execute_command_stream', { 
-        command,
-        terminalId: id
-      });
+      if (!(await runCommand(command))) {
+        cancelCommand();
+      }
     } catch (error) {
       const errorBlock: CommandBlockType = {
         id: blockId,
         command: command,
-        output: [`Error: ${error}`],
+        output: [`Error: ${(error as { message?: string })?.message ?? error}`],
         directory: currentDir
       };
       setCommandBlocks(prev => [...prev, errorBlock]);
@@ -503,7 +693,7 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
   const handleStopCommand = async () => {
     if (!isExecuting) return;
     try {
-      await invoke('stop_command', { terminalId: id });
+      await invoke('plugin:terminal|stop_command', { terminalId: id });
       setIsExecuting(false);
       setCurrentCommandBlock(null);
     } catch (error) {
@@ -539,7 +729,7 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
           <div className="terminal-content" style={{ paddingBottom: `${inputHeight}px` }}>
             <div className="terminal-blocks">
               {commandBlocks.map((block) => (
-                <CommandBlock key={block.id} {...block} />
+                <CommandBlock key={block.id} {...block} onRunSuggestion={handleRunFavoriteCommand} />
               ))}
             </div>
           </div>
@@ -557,22 +747,49 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
           </button>
         )}
         <div className={`terminal-input-container ${isOpen ? 'with-panel' : ''}`}>
+          {completions && (
+            <div className="completion-menu">
+              {completions.candidates.map(c => (
+                <button
+                  key={`${c.kind}-${c.value}`}
+                  className="completion-item"
+                  onMouseDown={e => e.preventDefault()}
+                  onClick={() => {
+                    applyCompletion(completions.start, completions.end, c.kind === 'directory' ? c.value : `${c.value} `);
+                    setCompletions(null);
+                    inputRef.current?.focus();
+                  }}
+                >
+                  <span className={`completion-name ${c.kind}`}>{c.display}</span>
+                  {c.description && <span className="completion-description">{c.description}</span>}
+                </button>
+              ))}
+            </div>
+          )}
+          {showProcesses && isExecuting && <ProcessTree terminalId={id} />}
           <div className="terminal-input-line">
             <div className="terminal-input-main">
               <span className="prompt">{currentDir} $ </span>
-              <textarea
-                ref={inputRef}
-                value={input}
-                onChange={handleInputChange}
-                onKeyDown={handleKeyPress}
-                className="terminal-input"
-                autoFocus
-                autoCapitalize="none"
-                autoCorrect="off"
-                spellCheck="false"
-                autoComplete="off"
-                rows={1}
-              />
+              <div className="terminal-input-wrapper">
+                {suggestion && (
+                  <div className="terminal-input-ghost" aria-hidden="true">
+                    <span className="invisible">{input}</span>{suggestion}
+                  </div>
+                )}
+                <textarea
+                  ref={inputRef}
+                  value={input}
+                  onChange={handleInputChange}
+                  onKeyDown={handleKeyPress}
+                  className="terminal-input"
+                  autoFocus
+                  autoCapitalize="none"
+                  autoCorrect="off"
+                  spellCheck="false"
+                  autoComplete="off"
+                  rows={1}
+                />
+              </div>
             </div>
             <div className="terminal-input-tools">
               {isExecuting && (
@@ -584,6 +801,15 @@ export const Terminal: React.FC<TerminalProps> = ({ id }) => {
                   <IconPlayerStop size={16} />
                 </button>
               )}
+              {isExecuting && (
+                <button
+                  className={`terminal-icon-button ${showProcesses ? 'active' : ''}`}
+                  onClick={() => setShowProcesses(!showProcesses)}
+                  title={showProcesses ? "Hide processes" : "Show processes"}
+                >
+                  <IconHierarchy size={16} />
+                </button>
+              )}
               <button
                 className={`terminal-icon-button ${isOpen ? 'active' : ''}`}
                 onClick={() => {
@@ -8,11 +8,82 @@ export interface CommandResult {
   current_dir: string;
 }
 
+// 命令不存在时后端给出的改正建议
+export interface Correction {
+  command: string;
+  replacement: string;
+  source: 'alias' | 'history' | 'builtin' | 'path';
+  distance: number;
+}
+
+export interface GuardMatch {
+  rule: string;
+  risk: 'low' | 'medium' | 'high' | 'critical';
+  reason: string;
+  start: number;
+  end: number;
+}
+
+// execute_command_stream 的结果：危险命令需要确认后带 confirmed 重新提交
+export type ExecuteOutcome =
+  | { status: 'completed' }
+  | { status: 'needs_confirmation'; risk: GuardMatch['risk']; matches: GuardMatch[]; blocked: boolean };
+
 export interface CommandBlock {
   id: number;
   command: string;
   output: string[];
   directory: string;
+  suggestions?: Correction[];
+  // 运行中的资源占用，结束后换成汇总
+  stats?: ProcessStats;
+  summary?: StatsSummary;
+  // 命令运行期间新开的监听端口
+  ports?: PortOpened[];
+}
+
+// terminal-port-opened 事件
+export interface PortOpened {
+  terminalId: string;
+  port: number;
+  protocol: 'tcp' | 'udp';
+  pid: number;
+  address: string;
+}
+
+// terminal-process-stats 事件：命令整棵进程树的资源占用
+export interface ProcessStats {
+  terminalId: string;
+  pid: number;
+  processes: number;
+  threads: number;
+  cpu_percent: number;
+  rss_bytes: number;
+  read_bytes: number;
+  write_bytes: number;
+}
+
+// get_process_tree 返回的进程节点
+export interface ProcessNode {
+  pid: number;
+  ppid: number;
+  name: string;
+  argv: string[];
+  state: string;
+  cwd: string | null;
+  start_time: number | null;
+  children: ProcessNode[];
+}
+
+export interface StatsSummary {
+  samples: number;
+  cpu_secs: number;
+  peak_cpu_percent: number;
+  peak_rss_bytes: number;
+  read_bytes: number;
+  write_bytes: number;
+  peak_threads: number;
+  peak_processes: number;
 }
 
 export interface StreamOutput {
@@ -23,6 +94,48 @@ export interface StreamOutput {
   terminalId: string;
 }
 
+export interface SessionCommandBlock {
+  command: string;
+  directory: string;
+  output: string[];
+  code: number | null;
+}
+
+export interface SessionInfo {
+  terminalId: string;
+  current_dir: string;
+  command: string | null;
+  running: boolean;
+  scrollback: string[];
+  title: string | null;
+  startup_command: string | null;
+  blocks: SessionCommandBlock[];
+  sandboxed?: boolean;
+}
+
+// terminal-title-changed 事件
+export interface TitleChanged {
+  terminalId: string;
+  title: string;
+  process: string | null;
+  cwd: string;
+  from_program: boolean;
+}
+
+export interface CompletionCandidate {
+  value: string;
+  display: string;
+  kind: 'command' | 'builtin' | 'file' | 'directory' | 'variable' | 'subcommand' | 'branch' | 'remote' | 'argument';
+  description: string | null;
+}
+
+// start/end 为输入中被替换的字符范围（按 Unicode 字符计算）
+export interface Completions {
+  start: number;
+  end: number;
+  candidates: CompletionCandidate[];
+}
+
 export interface ContextMenuPosition {
   x: number;
   y: number;
@@ -35,4 +148,6 @@ export interface ContextMenuProps {
   status: string;
 }
 
-export interface CommandBlockProps extends CommandBlock {} 
\ No newline at end of file
+export interface CommandBlockProps extends CommandBlock {
+  onRunSuggestion?: (command: string) => void;
+} 
\ No newline at end of file